@@ -19,6 +19,7 @@ use super::{
 pub enum UnaryOp {
     Minus,
     Not,
+    Exists,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
@@ -245,6 +246,9 @@ pub enum Expr {
     Call {
         callee: Box<Expr>,
         args: Vec<Expr>,
+        // Set for `count(DISTINCT x)`-style aggregate calls in queries.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        distinct: bool,
         #[serde(skip)]
         #[derivative(PartialEq = "ignore")]
         #[derivative(Hash = "ignore")]
@@ -297,6 +301,20 @@ pub enum Expr {
 }
 
 impl Expr {
+    /// Returns the query if the expression is a (possibly parenthesized)
+    /// SELECT.
+    pub fn as_subquery(&self) -> Option<&Expr> {
+        match self {
+            Expr::Select { .. } => Some(self),
+            Expr::Grouping { expr, .. } => expr.as_subquery(),
+            _ => None,
+        }
+    }
+
+    pub fn is_subquery(&self) -> bool {
+        self.as_subquery().is_some()
+    }
+
     pub fn sign(&self) -> String {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
@@ -367,7 +385,19 @@ impl Display for Expr {
                 Literal::Str(s) => write!(f, "Str(\"{s}\")"),
                 Literal::Num(n) => write!(f, "Num({n:?})"),
                 Literal::Bool(b) => write!(f, "{b}"),
-                Literal::Object(o) => write!(f, "Object({o:?})"),
+                Literal::Object(o) => {
+                    let mut fields: Vec<_> = o.iter().collect();
+                    fields.sort_by_key(|(k, _)| *k);
+                    write!(
+                        f,
+                        "Object({{{}}})",
+                        fields
+                            .iter()
+                            .map(|(k, v)| format!("{k}: {v}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
                 Literal::Array(a) => write!(
                     f,
                     "Array({})",
@@ -432,11 +462,17 @@ impl Display for Expr {
             } => {
                 write!(f, "{left} {operation:?} {right}")
             }
-            Expr::Call { callee, args, .. } => {
+            Expr::Call {
+                callee,
+                args,
+                distinct,
+                ..
+            } => {
                 write!(
                     f,
-                    "{}({})",
+                    "{}({}{})",
                     callee,
+                    if *distinct { "DISTINCT " } else { "" },
                     args.iter()
                         .map(|x| x.to_string())
                         .collect::<Vec<_>>()
@@ -530,6 +566,7 @@ pub mod test_utils {
         Expr::Call {
             callee: Box::new(create_identifier_expr(callee)),
             args,
+            distinct: false,
             span: Span::default(),
             id: 0,
         }
@@ -688,6 +725,7 @@ pub mod tests {
                 id: 15,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 16,
         };
@@ -849,6 +887,7 @@ pub mod tests {
                 id: 24,
             }),
             args: vec![],
+            distinct: false,
             span: test_span,
             id: 25,
         };
@@ -1019,6 +1058,7 @@ pub mod tests {
                     id: 3,
                 },
             ],
+            distinct: false,
             span: Span::default(),
             id: 4,
         };
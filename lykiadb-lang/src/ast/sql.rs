@@ -62,6 +62,17 @@ pub struct SqlCollectionIdentifier {
     pub alias: Option<Identifier>,
 }
 
+/// A constraint declared along with a collection. Fields are paths into
+/// the documents, e.g. `address.city`.
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+#[serde(tag = "@type")]
+pub enum SqlConstraint {
+    #[serde(rename = "SqlConstraint::PrimaryKey")]
+    PrimaryKey { fields: Vec<Vec<Identifier>> },
+    #[serde(rename = "SqlConstraint::Unique")]
+    Unique { fields: Vec<Vec<Identifier>> },
+}
+
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
 #[serde(tag = "@type")]
 pub enum SqlProjection {
@@ -182,11 +193,18 @@ pub struct SqlInsert {
     pub values: SqlValues,
 }
 
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+#[serde(tag = "@type")]
+pub struct SqlAssignment {
+    pub path: Vec<Identifier>,
+    pub expr: Box<Expr>,
+}
+
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
 #[serde(tag = "@type")]
 pub struct SqlUpdate {
     pub collection: SqlCollectionIdentifier,
-    pub assignments: Vec<Expr>,
+    pub assignments: Vec<SqlAssignment>,
     pub r#where: Option<Box<Expr>>,
 }
 
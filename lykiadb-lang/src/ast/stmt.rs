@@ -1,7 +1,11 @@
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 
-use super::{Identifier, Span, Spanned, expr::Expr};
+use super::{
+    Identifier, Span, Spanned,
+    expr::Expr,
+    sql::{SqlCollectionIdentifier, SqlConstraint},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Derivative)]
 #[serde(tag = "@type")]
@@ -71,11 +75,65 @@ pub enum Stmt {
     #[serde(rename = "Stmt::Explain")]
     Explain {
         expr: Box<Expr>,
+        // Runs the query and reports what each node of its plan did.
+        analyze: bool,
         #[serde(skip)]
         #[derivative(PartialEq = "ignore")]
         #[derivative(Hash = "ignore")]
         span: Span,
     },
+    #[serde(rename = "Stmt::CreateCollection")]
+    CreateCollection {
+        collection: SqlCollectionIdentifier,
+        constraints: Vec<SqlConstraint>,
+        #[serde(skip)]
+        #[derivative(PartialEq = "ignore")]
+        #[derivative(Hash = "ignore")]
+        span: Span,
+    },
+    #[serde(rename = "Stmt::CreateIndex")]
+    CreateIndex {
+        name: Identifier,
+        collection: SqlCollectionIdentifier,
+        // Each field is a path into the documents, e.g. `address.city`.
+        fields: Vec<Vec<Identifier>>,
+        #[serde(skip)]
+        #[derivative(PartialEq = "ignore")]
+        #[derivative(Hash = "ignore")]
+        span: Span,
+    },
+    #[serde(rename = "Stmt::DropCollection")]
+    DropCollection {
+        collection: SqlCollectionIdentifier,
+        #[serde(skip)]
+        #[derivative(PartialEq = "ignore")]
+        #[derivative(Hash = "ignore")]
+        span: Span,
+    },
+    #[serde(rename = "Stmt::Analyze")]
+    Analyze {
+        collection: SqlCollectionIdentifier,
+        #[serde(skip)]
+        #[derivative(PartialEq = "ignore")]
+        #[derivative(Hash = "ignore")]
+        span: Span,
+    },
+    #[serde(rename = "Stmt::Transaction")]
+    Transaction {
+        action: TransactionAction,
+        #[serde(skip)]
+        #[derivative(PartialEq = "ignore")]
+        #[derivative(Hash = "ignore")]
+        span: Span,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone, Copy, Hash)]
+#[serde(tag = "@type")]
+pub enum TransactionAction {
+    Begin,
+    Commit,
+    Rollback,
 }
 
 impl Spanned for Stmt {
@@ -89,6 +147,11 @@ impl Spanned for Stmt {
             Stmt::Loop { span, .. } => *span,
             Stmt::Return { span, .. } => *span,
             Stmt::Explain { span, .. } => *span,
+            Stmt::CreateCollection { span, .. } => *span,
+            Stmt::CreateIndex { span, .. } => *span,
+            Stmt::DropCollection { span, .. } => *span,
+            Stmt::Analyze { span, .. } => *span,
+            Stmt::Transaction { span, .. } => *span,
         }
     }
 }
@@ -133,9 +133,11 @@ mod tests {
                     id: 0,
                 }),
                 args: vec![],
+                distinct: false,
                 span: Span::default(),
                 id: 0,
             }],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -174,12 +176,15 @@ mod tests {
                         id: 0,
                     }),
                     args: vec![],
+                    distinct: false,
                     span: Span::default(),
                     id: 0,
                 }],
+                distinct: false,
                 span: Span::default(),
                 id: 0,
             }],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -206,6 +211,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -217,6 +223,7 @@ mod tests {
                 id: 1,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 1,
         };
@@ -228,6 +235,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![avg0.clone(), avg1.clone()],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
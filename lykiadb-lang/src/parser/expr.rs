@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::ast::expr::{BinaryOp, Expr, TernaryOp, TypeAnnotation};
+use crate::ast::expr::{BinaryOp, Expr, TernaryOp, TypeAnnotation, UnaryOp};
 use crate::ast::stmt::Stmt;
 use crate::ast::{IdentifierKind, Literal, Spanned};
 use crate::tokenizer::token::{
@@ -145,43 +145,43 @@ impl ExprParser {
     }
 
     fn or(&mut self, cparser: &mut Parser) -> ParseResult<Box<Expr>> {
-        let expr = self.and(cparser)?;
+        let mut expr = self.and(cparser)?;
         let operator = if cparser.get_count("in_select_depth") > 0 {
             skw!(Or)
         } else {
             sym!(LogicalOr)
         };
-        if cparser.match_next(&operator) {
+        while cparser.match_next(&operator) {
             let op = cparser.peek_bw(1);
             let right = self.and(cparser)?;
-            return Ok(Box::new(Expr::Logical {
+            expr = Box::new(Expr::Logical {
                 left: expr.clone(),
                 operation: cparser.tok_type_to_op(op.tok_type.clone()),
                 right: right.clone(),
                 span: cparser.get_merged_span(&expr.get_span(), &right.get_span()),
                 id: cparser.get_expr_id(),
-            }));
+            });
         }
         Ok(expr)
     }
 
     fn and(&mut self, cparser: &mut Parser) -> ParseResult<Box<Expr>> {
-        let expr = self.equality(cparser)?;
+        let mut expr = self.equality(cparser)?;
         let operator = if cparser.get_count("in_select_depth") > 0 {
             skw!(And)
         } else {
             sym!(LogicalAnd)
         };
-        if cparser.match_next(&operator) {
+        while cparser.match_next(&operator) {
             let op = cparser.peek_bw(1);
             let right = self.equality(cparser)?;
-            return Ok(Box::new(Expr::Logical {
+            expr = Box::new(Expr::Logical {
                 left: expr.clone(),
                 operation: cparser.tok_type_to_op(op.tok_type.clone()),
                 right: right.clone(),
                 span: cparser.get_merged_span(&expr.get_span(), &right.get_span()),
                 id: cparser.get_expr_id(),
-            }));
+            });
         }
         Ok(expr)
     }
@@ -319,6 +319,31 @@ impl ExprParser {
                 id: cparser.get_expr_id(),
             }));
         }
+        if cparser.get_count("in_select_depth") > 0 {
+            if cparser.match_next(&skw!(Not)) {
+                let token = (*cparser.peek_bw(1)).clone();
+                let unary = self.unary(cparser)?;
+                return Ok(Box::new(Expr::Unary {
+                    operation: UnaryOp::Not,
+                    expr: unary.clone(),
+                    span: cparser.get_merged_span(&token.span, &(unary).get_span()),
+                    id: cparser.get_expr_id(),
+                }));
+            }
+            if cparser.match_next(&skw!(Exists)) {
+                let token = (*cparser.peek_bw(1)).clone();
+                let subquery = self.unary(cparser)?;
+                if !subquery.is_subquery() {
+                    return Err(ParseError::UnexpectedToken { token });
+                }
+                return Ok(Box::new(Expr::Unary {
+                    operation: UnaryOp::Exists,
+                    expr: subquery.clone(),
+                    span: cparser.get_merged_span(&token.span, &(subquery).get_span()),
+                    id: cparser.get_expr_id(),
+                }));
+            }
+        }
         cparser.consume_call()
     }
 
@@ -385,6 +410,9 @@ impl ExprParser {
     fn finish_call(&mut self, callee: Box<Expr>, cparser: &mut Parser) -> ParseResult<Box<Expr>> {
         let mut arguments: Vec<Expr> = vec![];
 
+        let distinct =
+            cparser.get_count("in_select_depth") > 0 && cparser.match_next(&skw!(Distinct));
+
         if !cparser.cmp_tok(&sym!(RightParen)) {
             arguments.push(*self.expression(cparser)?);
             while cparser.match_next(&sym!(Comma)) {
@@ -398,6 +426,7 @@ impl ExprParser {
             callee: callee.clone(),
             span: cparser.get_merged_span(&(callee).get_span(), &paren.span),
             args: arguments,
+            distinct,
             id: cparser.get_expr_id(),
         }))
     }
@@ -2,6 +2,7 @@ use self::program::Program;
 use super::ast::expr::BinaryOp;
 use super::ast::stmt::Stmt;
 use crate::ast::expr::{Expr, UnaryOp};
+use crate::ast::sql::SqlCollectionIdentifier;
 use crate::ast::{Span, Spanned};
 use crate::tokenizer::token::{SqlKeyword, Symbol::*, Token, TokenType, TokenType::*};
 use expr::ExprParser;
@@ -78,6 +79,11 @@ impl<'a> Parser<'a> {
         expr.sql_insert(self)
     }
 
+    fn consume_collection_identifier(&mut self) -> ParseResult<Option<SqlCollectionIdentifier>> {
+        let mut sql = SqlParser {};
+        sql.sql_collection_identifier(self)
+    }
+
     fn consume_call2(&mut self) -> ParseResult<Box<Expr>> {
         let mut expr = ExprParser {};
         expr.call(self)
@@ -239,6 +239,11 @@ impl Resolver<'_> {
             Stmt::Explain { expr, .. } => {
                 self.resolve_expr(expr)?;
             }
+            Stmt::CreateCollection { .. }
+            | Stmt::CreateIndex { .. }
+            | Stmt::DropCollection { .. }
+            | Stmt::Analyze { .. }
+            | Stmt::Transaction { .. } => {}
         };
         Ok(())
     }
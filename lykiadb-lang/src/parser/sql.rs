@@ -2,10 +2,10 @@ use crate::ast::{
     Span,
     expr::Expr,
     sql::{
-        SqlCollectionIdentifier, SqlCompoundOperator, SqlDelete, SqlDistinct, SqlExpressionSource,
-        SqlFrom, SqlInsert, SqlJoinType, SqlLimitClause, SqlOrderByClause, SqlOrdering,
-        SqlProjection, SqlSelect, SqlSelectCompound, SqlSelectCore, SqlSource, SqlUpdate,
-        SqlValues,
+        SqlAssignment, SqlCollectionIdentifier, SqlCompoundOperator, SqlDelete, SqlDistinct,
+        SqlExpressionSource, SqlFrom, SqlInsert, SqlJoinType, SqlLimitClause, SqlOrderByClause,
+        SqlOrdering, SqlProjection, SqlSelect, SqlSelectCompound, SqlSelectCore, SqlSource,
+        SqlUpdate, SqlValues,
     },
 };
 
@@ -35,6 +35,7 @@ impl SqlParser {
         if !cparser.match_next(&skw!(Insert)) {
             return self.sql_update(cparser);
         }
+        let insert_tok = cparser.peek_bw(1).clone();
 
         cparser.expect(&skw!(Into))?;
 
@@ -64,7 +65,7 @@ impl SqlParser {
             };
             Ok(Box::new(Expr::Insert {
                 command: SqlInsert { collection, values },
-                span: Span::default(),
+                span: cparser.get_merged_span(&insert_tok.span, &cparser.peek_bw(1).span),
                 id: cparser.get_expr_id(),
             }))
         } else {
@@ -78,6 +79,7 @@ impl SqlParser {
         if !cparser.match_next(&skw!(Update)) {
             return self.sql_delete(cparser);
         }
+        let update_tok = cparser.peek_bw(1).clone();
 
         let collection = match self.sql_collection_identifier(cparser)? {
             Some(col) => col,
@@ -90,12 +92,30 @@ impl SqlParser {
 
         cparser.expect(&skw!(Set))?;
 
-        let mut assignments: Vec<Expr> = vec![];
+        // Both the assigned expressions and the WHERE clause are evaluated
+        // against documents, just like the clauses of a SELECT.
+        cparser.increment_count("in_select_depth");
+
+        let mut assignments: Vec<SqlAssignment> = vec![];
 
         loop {
-            cparser.expect(&Identifier { dollar: false })?;
+            let mut path = vec![
+                cparser
+                    .expect(&Identifier { dollar: false })?
+                    .extract_identifier()?,
+            ];
+            while cparser.match_next(&sym!(Dot)) {
+                path.push(
+                    cparser
+                        .expect(&Identifier { dollar: false })?
+                        .extract_identifier()?,
+                );
+            }
             cparser.expect(&sym!(Equal))?;
-            assignments.push(*cparser.consume_expr()?);
+            assignments.push(SqlAssignment {
+                path,
+                expr: cparser.consume_expr()?,
+            });
             if !cparser.match_next(&sym!(Comma)) {
                 break;
             }
@@ -107,13 +127,15 @@ impl SqlParser {
             None
         };
 
+        cparser.decrement_count("in_select_depth");
+
         Ok(Box::new(Expr::Update {
             command: SqlUpdate {
                 collection,
                 assignments,
                 r#where,
             },
-            span: Span::default(),
+            span: cparser.get_merged_span(&update_tok.span, &cparser.peek_bw(1).span),
             id: cparser.get_expr_id(),
         }))
     }
@@ -127,7 +149,10 @@ impl SqlParser {
 
         if let Some(collection) = self.sql_collection_identifier(cparser)? {
             let r#where = if cparser.match_next(&skw!(Where)) {
-                Some(cparser.consume_expr()?)
+                cparser.increment_count("in_select_depth");
+                let predicate = cparser.consume_expr()?;
+                cparser.decrement_count("in_select_depth");
+                Some(predicate)
             } else {
                 None
             };
@@ -147,7 +172,7 @@ impl SqlParser {
         }
     }
 
-    fn sql_collection_identifier(
+    pub fn sql_collection_identifier(
         &mut self,
         cparser: &mut Parser,
     ) -> ParseResult<Option<SqlCollectionIdentifier>> {
@@ -1,8 +1,11 @@
-use super::{ParseResult, Parser};
+use super::{ParseError, ParseResult, Parser};
 use crate::ast::expr::Expr;
-use crate::ast::stmt::Stmt;
+use crate::ast::sql::{SqlCollectionIdentifier, SqlConstraint};
+use crate::ast::stmt::{Stmt, TransactionAction};
 use crate::ast::{Literal, Spanned};
-use crate::tokenizer::token::{Keyword::*, SqlKeyword::*, Symbol::*, TokenType, TokenType::*};
+use crate::tokenizer::token::{
+    Keyword::*, SqlKeyword::*, Symbol::*, Token, TokenType, TokenType::*,
+};
 use crate::{kw, skw, sym};
 
 pub struct StmtParser {}
@@ -29,6 +32,12 @@ impl StmtParser {
         match_next!(self, cparser, &kw!(Loop), loop_statement);
         match_next!(self, cparser, &kw!(Return), return_statement);
         match_next!(self, cparser, &skw!(Explain), explain_statement);
+        match_next!(self, cparser, &skw!(Create), create_statement);
+        match_next!(self, cparser, &skw!(Drop), drop_statement);
+        match_next!(self, cparser, &skw!(Analyze), analyze_statement);
+        match_next!(self, cparser, &skw!(Begin), transaction_statement);
+        match_next!(self, cparser, &skw!(Commit), transaction_statement);
+        match_next!(self, cparser, &skw!(Rollback), transaction_statement);
         if cparser.peek_next_all_of(&[sym!(LeftBrace), Identifier { dollar: false }, sym!(Colon)])
             || cparser.peek_next_all_of(&[sym!(LeftBrace), Str, sym!(Colon)])
             || cparser.peek_next_all_of(&[sym!(LeftBrace), Num, sym!(Colon)])
@@ -195,10 +204,166 @@ impl StmtParser {
     }
 
     fn explain_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
+        let analyze = cparser.match_next(&skw!(Analyze));
         let expr = cparser.consume_expr()?;
         let span = expr.get_span();
         cparser.expect(&sym!(Semicolon))?;
-        Ok(Box::new(Stmt::Explain { expr, span }))
+        Ok(Box::new(Stmt::Explain {
+            expr,
+            analyze,
+            span,
+        }))
+    }
+
+    fn create_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
+        let create_tok = cparser.peek_bw(1);
+        if cparser.match_next(&skw!(Index)) {
+            return self.create_index_statement(cparser, create_tok);
+        }
+        cparser.expect(&skw!(Collection))?;
+        let collection = self.ddl_collection(cparser)?;
+
+        let mut constraints = vec![];
+        if cparser.match_next(&sym!(LeftParen)) {
+            loop {
+                if cparser.match_next(&skw!(Primary)) {
+                    // A collection has a single primary key.
+                    if constraints
+                        .iter()
+                        .any(|c| matches!(c, SqlConstraint::PrimaryKey { .. }))
+                    {
+                        return Err(ParseError::UnexpectedToken {
+                            token: cparser.peek_bw(1).clone(),
+                        });
+                    }
+                    cparser.expect(&skw!(Key))?;
+                    constraints.push(SqlConstraint::PrimaryKey {
+                        fields: self.field_list(cparser)?,
+                    });
+                } else {
+                    cparser.expect(&skw!(Unique))?;
+                    constraints.push(SqlConstraint::Unique {
+                        fields: self.field_list(cparser)?,
+                    });
+                }
+                if !cparser.match_next(&sym!(Comma)) {
+                    break;
+                }
+            }
+            cparser.expect(&sym!(RightParen))?;
+        }
+
+        cparser.expect(&sym!(Semicolon))?;
+        let closing_tok = cparser.peek_bw(1);
+        Ok(Box::new(Stmt::CreateCollection {
+            collection,
+            constraints,
+            span: cparser.get_merged_span(&create_tok.span, &closing_tok.span),
+        }))
+    }
+
+    fn create_index_statement(
+        &mut self,
+        cparser: &mut Parser,
+        create_tok: &Token,
+    ) -> ParseResult<Box<Stmt>> {
+        let name = cparser
+            .expect(&Identifier { dollar: false })?
+            .extract_identifier()?;
+        cparser.expect(&skw!(On))?;
+        let collection = self.ddl_collection(cparser)?;
+
+        let fields = self.field_list(cparser)?;
+        cparser.expect(&sym!(Semicolon))?;
+
+        let closing_tok = cparser.peek_bw(1);
+        Ok(Box::new(Stmt::CreateIndex {
+            name,
+            collection,
+            fields,
+            span: cparser.get_merged_span(&create_tok.span, &closing_tok.span),
+        }))
+    }
+
+    // A parenthesized list of field paths, e.g. `(address.city, name)`.
+    fn field_list(
+        &mut self,
+        cparser: &mut Parser,
+    ) -> ParseResult<Vec<Vec<crate::ast::Identifier>>> {
+        cparser.expect(&sym!(LeftParen))?;
+        let mut fields = vec![];
+        loop {
+            let mut path = vec![
+                cparser
+                    .expect(&Identifier { dollar: false })?
+                    .extract_identifier()?,
+            ];
+            while cparser.match_next(&sym!(Dot)) {
+                path.push(
+                    cparser
+                        .expect(&Identifier { dollar: false })?
+                        .extract_identifier()?,
+                );
+            }
+            fields.push(path);
+            if !cparser.match_next(&sym!(Comma)) {
+                break;
+            }
+        }
+        cparser.expect(&sym!(RightParen))?;
+        Ok(fields)
+    }
+
+    fn drop_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
+        let drop_tok = cparser.peek_bw(1);
+        cparser.expect(&skw!(Collection))?;
+        let collection = self.ddl_collection(cparser)?;
+        cparser.expect(&sym!(Semicolon))?;
+        let closing_tok = cparser.peek_bw(1);
+        Ok(Box::new(Stmt::DropCollection {
+            collection,
+            span: cparser.get_merged_span(&drop_tok.span, &closing_tok.span),
+        }))
+    }
+
+    fn analyze_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
+        let analyze_tok = cparser.peek_bw(1);
+        let collection = self.ddl_collection(cparser)?;
+        cparser.expect(&sym!(Semicolon))?;
+        let closing_tok = cparser.peek_bw(1);
+        Ok(Box::new(Stmt::Analyze {
+            collection,
+            span: cparser.get_merged_span(&analyze_tok.span, &closing_tok.span),
+        }))
+    }
+
+    // Collections are named without an alias in DDL statements.
+    fn ddl_collection(&mut self, cparser: &mut Parser) -> ParseResult<SqlCollectionIdentifier> {
+        match cparser.consume_collection_identifier()? {
+            Some(collection) if collection.alias.is_none() => Ok(collection),
+            Some(_) => Err(ParseError::UnexpectedToken {
+                token: cparser.peek_bw(1).clone(),
+            }),
+            None => Err(ParseError::UnexpectedToken {
+                token: cparser.peek_bw(0).clone(),
+            }),
+        }
+    }
+
+    fn transaction_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
+        let action_tok = cparser.peek_bw(1);
+        let action = match action_tok.tok_type {
+            SqlKeyword(Begin) => TransactionAction::Begin,
+            SqlKeyword(Commit) => TransactionAction::Commit,
+            _ => TransactionAction::Rollback,
+        };
+        cparser.match_next(&skw!(Transaction));
+        cparser.expect(&sym!(Semicolon))?;
+        let closing_tok = cparser.peek_bw(1);
+        Ok(Box::new(Stmt::Transaction {
+            action,
+            span: cparser.get_merged_span(&action_tok.span, &closing_tok.span),
+        }))
     }
 
     fn expression_statement(&mut self, cparser: &mut Parser) -> ParseResult<Box<Stmt>> {
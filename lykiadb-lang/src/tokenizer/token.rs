@@ -98,6 +98,7 @@ pub enum SqlKeyword {
     Order,
     By,
     Explain,
+    Analyze,
     Offset,
     Limit,
     And,
@@ -108,6 +109,7 @@ pub enum SqlKeyword {
     Like,
     In,
     Between,
+    Exists,
     //
     Join,
     Inner,
@@ -227,11 +229,13 @@ pub static SQL_KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "AND" => skw!(SqlKeyword::And),
     "OR" => skw!(SqlKeyword::Or),
     "EXPLAIN" => skw!(SqlKeyword::Explain),
+    "ANALYZE" => skw!(SqlKeyword::Analyze),
     "IS" => skw!(SqlKeyword::Is),
     "NOT" => skw!(SqlKeyword::Not),
     "LIKE" => skw!(SqlKeyword::Like),
     "IN" => skw!(SqlKeyword::In),
     "BETWEEN" => skw!(SqlKeyword::Between),
+    "EXISTS" => skw!(SqlKeyword::Exists),
     "OFFSET" => skw!(SqlKeyword::Offset),
     "LIMIT" => skw!(SqlKeyword::Limit),
     "JOIN" => skw!(SqlKeyword::Join),
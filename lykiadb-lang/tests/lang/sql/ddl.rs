@@ -0,0 +1,261 @@
+use crate::assert_parsing;
+use crate::lang::compare_parsed_to_expected;
+use serde_json::json;
+
+assert_parsing! {
+    create_collection: {
+        "CREATE COLLECTION users;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::CreateCollection",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": null
+              },
+              "constraints": []
+            }
+          ]
+        }
+    },
+
+    create_collection_in_namespace: {
+        "create collection db.users;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::CreateCollection",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "db"
+                }
+              },
+              "constraints": []
+            }
+          ]
+        }
+    },
+
+    create_collection_with_constraints: {
+        "CREATE COLLECTION users (PRIMARY KEY (id), UNIQUE (email), UNIQUE (name.first, name.last));" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::CreateCollection",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": null
+              },
+              "constraints": [
+                {
+                  "@type": "SqlConstraint::PrimaryKey",
+                  "fields": [
+                    [
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "id"
+                      }
+                    ]
+                  ]
+                },
+                {
+                  "@type": "SqlConstraint::Unique",
+                  "fields": [
+                    [
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "email"
+                      }
+                    ]
+                  ]
+                },
+                {
+                  "@type": "SqlConstraint::Unique",
+                  "fields": [
+                    [
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "name"
+                      },
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "first"
+                      }
+                    ],
+                    [
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "name"
+                      },
+                      {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "last"
+                      }
+                    ]
+                  ]
+                }
+              ]
+            }
+          ]
+        }
+    },
+
+    drop_collection: {
+        "DROP COLLECTION db.users;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::DropCollection",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "db"
+                }
+              }
+            }
+          ]
+        }
+    },
+
+    analyze: {
+        "ANALYZE users;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Analyze",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": null
+              }
+            }
+          ]
+        }
+    },
+
+    create_index: {
+        "CREATE INDEX by_age ON users (age);" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::CreateIndex",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": null
+              },
+              "fields": [
+                [
+                  {
+                    "@type": "Identifier",
+                    "kind": "IdentifierKind::Symbol",
+                    "name": "age"
+                  }
+                ]
+              ],
+              "name": {
+                "@type": "Identifier",
+                "kind": "IdentifierKind::Symbol",
+                "name": "by_age"
+              }
+            }
+          ]
+        }
+    },
+
+    create_index_on_paths: {
+        "create index by_city on db.users (address.city, age);" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::CreateIndex",
+              "collection": {
+                "@type": "SqlCollectionIdentifier",
+                "alias": null,
+                "name": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "users"
+                },
+                "namespace": {
+                  "@type": "Identifier",
+                  "kind": "IdentifierKind::Symbol",
+                  "name": "db"
+                }
+              },
+              "fields": [
+                [
+                  {
+                    "@type": "Identifier",
+                    "kind": "IdentifierKind::Symbol",
+                    "name": "address"
+                  },
+                  {
+                    "@type": "Identifier",
+                    "kind": "IdentifierKind::Symbol",
+                    "name": "city"
+                  }
+                ],
+                [
+                  {
+                    "@type": "Identifier",
+                    "kind": "IdentifierKind::Symbol",
+                    "name": "age"
+                  }
+                ]
+              ],
+              "name": {
+                "@type": "Identifier",
+                "kind": "IdentifierKind::Symbol",
+                "name": "by_city"
+              }
+            }
+          ]
+        }
+    }
+}
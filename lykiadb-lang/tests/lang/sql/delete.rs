@@ -0,0 +1,178 @@
+use crate::assert_parsing;
+use crate::lang::compare_parsed_to_expected;
+use serde_json::json;
+
+assert_parsing! {
+    plain: {
+        "DELETE FROM db.users WHERE age > 10 and name = 'Jane';" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Delete",
+                "command": {
+                  "@type": "SqlDelete",
+                  "collection": {
+                    "@type": "SqlCollectionIdentifier",
+                    "alias": null,
+                    "name": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "users"
+                    },
+                    "namespace": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "db"
+                    }
+                  },
+                  "where": {
+                    "@type": "Expr::Logical",
+                    "left": {
+                      "@type": "Expr::Binary",
+                      "left": {
+                        "@type": "Expr::FieldPath",
+                        "head": {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "age"
+                        },
+                        "tail": []
+                      },
+                      "operation": {
+                        "@type": "Greater"
+                      },
+                      "right": {
+                        "@type": "Expr::Literal",
+                        "raw": "10",
+                        "value": {
+                          "Num": 10.0
+                        }
+                      }
+                    },
+                    "operation": {
+                      "@type": "And"
+                    },
+                    "right": {
+                      "@type": "Expr::Binary",
+                      "left": {
+                        "@type": "Expr::FieldPath",
+                        "head": {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "name"
+                        },
+                        "tail": []
+                      },
+                      "operation": {
+                        "@type": "IsEqual"
+                      },
+                      "right": {
+                        "@type": "Expr::Literal",
+                        "raw": "Jane",
+                        "value": {
+                          "Str": "Jane"
+                        }
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          ]
+        }
+    },
+
+    without_where: {
+        "DELETE FROM users;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Delete",
+                "command": {
+                  "@type": "SqlDelete",
+                  "collection": {
+                    "@type": "SqlCollectionIdentifier",
+                    "alias": null,
+                    "name": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "users"
+                    },
+                    "namespace": null
+                  },
+                  "where": null
+                }
+              }
+            }
+          ]
+        }
+    },
+
+    with_alias: {
+        "DELETE FROM users u WHERE u.address.city = 'Istanbul';" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Delete",
+                "command": {
+                  "@type": "SqlDelete",
+                  "collection": {
+                    "@type": "SqlCollectionIdentifier",
+                    "alias": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "u"
+                    },
+                    "name": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "users"
+                    },
+                    "namespace": null
+                  },
+                  "where": {
+                    "@type": "Expr::Binary",
+                    "left": {
+                      "@type": "Expr::FieldPath",
+                      "head": {
+                        "@type": "Identifier",
+                        "kind": "IdentifierKind::Symbol",
+                        "name": "u"
+                      },
+                      "tail": [
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "address"
+                        },
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "city"
+                        }
+                      ]
+                    },
+                    "operation": {
+                      "@type": "IsEqual"
+                    },
+                    "right": {
+                      "@type": "Expr::Literal",
+                      "raw": "Istanbul",
+                      "value": {
+                        "Str": "Istanbul"
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          ]
+        }
+    }
+}
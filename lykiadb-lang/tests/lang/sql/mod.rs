@@ -1,3 +1,6 @@
+pub mod ddl;
+pub mod delete;
+pub mod explain;
 pub mod insert_values;
 pub mod select_compound;
 pub mod select_distinct;
@@ -9,3 +12,5 @@ pub mod select_order;
 pub mod select_projection;
 pub mod select_where;
 pub mod sql_expr;
+pub mod transaction;
+pub mod update;
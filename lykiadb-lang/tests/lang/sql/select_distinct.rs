@@ -176,5 +176,87 @@ assert_parsing! {
               }
             ]
           }
+    },
+    distinct_aggregate_argument: {
+        "SELECT count(DISTINCT b.author) FROM books b;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Select",
+                "query": {
+                  "@type": "SqlSelect",
+                  "core": {
+                    "@type": "SqlSelectCore",
+                    "compound": null,
+                    "distinct": {
+                      "@type": "SqlDistinct::ImplicitAll"
+                    },
+                    "from": {
+                      "@type": "SqlFrom::Group",
+                      "values": [
+                        {
+                          "@type": "SqlCollectionIdentifier",
+                          "alias": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "b"
+                          },
+                          "name": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "books"
+                          },
+                          "namespace": null
+                        }
+                      ]
+                    },
+                    "group_by": null,
+                    "having": null,
+                    "projection": [
+                      {
+                        "@type": "SqlProjection::Expr",
+                        "alias": null,
+                        "expr": {
+                          "@type": "Expr::Call",
+                          "args": [
+                            {
+                              "@type": "Expr::FieldPath",
+                              "head": {
+                                "@type": "Identifier",
+                                "kind": "IdentifierKind::Symbol",
+                                "name": "b"
+                              },
+                              "tail": [
+                                {
+                                  "@type": "Identifier",
+                                  "kind": "IdentifierKind::Symbol",
+                                  "name": "author"
+                                }
+                              ]
+                            }
+                          ],
+                          "callee": {
+                            "@type": "Expr::Variable",
+                            "name": {
+                              "@type": "Identifier",
+                              "kind": "IdentifierKind::Symbol",
+                              "name": "count"
+                            }
+                          },
+                          "distinct": true
+                        }
+                      }
+                    ],
+                    "where": null
+                  },
+                  "limit": null,
+                  "order_by": null
+                }
+              }
+            }
+          ]
+        }
     }
 }
@@ -330,5 +330,299 @@ assert_parsing! {
               }
             ]
           }
+    },
+    chained_conjunctions: {
+        "SELECT * FROM books b WHERE b.x = 1 and b.y = 2 and b.z = 3;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Select",
+                "query": {
+                  "@type": "SqlSelect",
+                  "core": {
+                    "@type": "SqlSelectCore",
+                    "compound": null,
+                    "distinct": {
+                      "@type": "SqlDistinct::ImplicitAll"
+                    },
+                    "from": {
+                      "@type": "SqlFrom::Group",
+                      "values": [
+                        {
+                          "@type": "SqlCollectionIdentifier",
+                          "alias": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "b"
+                          },
+                          "name": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "books"
+                          },
+                          "namespace": null
+                        }
+                      ]
+                    },
+                    "group_by": null,
+                    "having": null,
+                    "projection": [
+                      {
+                        "@type": "SqlProjection::All",
+                        "collection": null
+                      }
+                    ],
+                    "where": {
+                      "@type": "Expr::Logical",
+                      "left": {
+                        "@type": "Expr::Logical",
+                        "left": {
+                          "@type": "Expr::Binary",
+                          "left": {
+                            "@type": "Expr::FieldPath",
+                            "head": {
+                              "@type": "Identifier",
+                              "kind": "IdentifierKind::Symbol",
+                              "name": "b"
+                            },
+                            "tail": [
+                              {
+                                "@type": "Identifier",
+                                "kind": "IdentifierKind::Symbol",
+                                "name": "x"
+                              }
+                            ]
+                          },
+                          "operation": {
+                            "@type": "IsEqual"
+                          },
+                          "right": {
+                            "@type": "Expr::Literal",
+                            "raw": "1",
+                            "value": {
+                              "Num": 1.0
+                            }
+                          }
+                        },
+                        "operation": {
+                          "@type": "And"
+                        },
+                        "right": {
+                          "@type": "Expr::Binary",
+                          "left": {
+                            "@type": "Expr::FieldPath",
+                            "head": {
+                              "@type": "Identifier",
+                              "kind": "IdentifierKind::Symbol",
+                              "name": "b"
+                            },
+                            "tail": [
+                              {
+                                "@type": "Identifier",
+                                "kind": "IdentifierKind::Symbol",
+                                "name": "y"
+                              }
+                            ]
+                          },
+                          "operation": {
+                            "@type": "IsEqual"
+                          },
+                          "right": {
+                            "@type": "Expr::Literal",
+                            "raw": "2",
+                            "value": {
+                              "Num": 2.0
+                            }
+                          }
+                        }
+                      },
+                      "operation": {
+                        "@type": "And"
+                      },
+                      "right": {
+                        "@type": "Expr::Binary",
+                        "left": {
+                          "@type": "Expr::FieldPath",
+                          "head": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "b"
+                          },
+                          "tail": [
+                            {
+                              "@type": "Identifier",
+                              "kind": "IdentifierKind::Symbol",
+                              "name": "z"
+                            }
+                          ]
+                        },
+                        "operation": {
+                          "@type": "IsEqual"
+                        },
+                        "right": {
+                          "@type": "Expr::Literal",
+                          "raw": "3",
+                          "value": {
+                            "Num": 3.0
+                          }
+                        }
+                      }
+                    }
+                  },
+                  "limit": null,
+                  "order_by": null
+                }
+              }
+            }
+          ]
+        }
+    },
+
+    exists_subquery: {
+        "SELECT * FROM books b WHERE NOT EXISTS (SELECT * FROM comments c WHERE c.book_id = b.id);" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Select",
+                "query": {
+                  "@type": "SqlSelect",
+                  "core": {
+                    "@type": "SqlSelectCore",
+                    "compound": null,
+                    "distinct": {
+                      "@type": "SqlDistinct::ImplicitAll"
+                    },
+                    "from": {
+                      "@type": "SqlFrom::Group",
+                      "values": [
+                        {
+                          "@type": "SqlCollectionIdentifier",
+                          "alias": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "b"
+                          },
+                          "name": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "books"
+                          },
+                          "namespace": null
+                        }
+                      ]
+                    },
+                    "group_by": null,
+                    "having": null,
+                    "projection": [
+                      {
+                        "@type": "SqlProjection::All",
+                        "collection": null
+                      }
+                    ],
+                    "where": {
+                      "@type": "Expr::Unary",
+                      "expr": {
+                        "@type": "Expr::Unary",
+                        "expr": {
+                          "@type": "Expr::Grouping",
+                          "expr": {
+                            "@type": "Expr::Select",
+                            "query": {
+                              "@type": "SqlSelect",
+                              "core": {
+                                "@type": "SqlSelectCore",
+                                "compound": null,
+                                "distinct": {
+                                  "@type": "SqlDistinct::ImplicitAll"
+                                },
+                                "from": {
+                                  "@type": "SqlFrom::Group",
+                                  "values": [
+                                    {
+                                      "@type": "SqlCollectionIdentifier",
+                                      "alias": {
+                                        "@type": "Identifier",
+                                        "kind": "IdentifierKind::Symbol",
+                                        "name": "c"
+                                      },
+                                      "name": {
+                                        "@type": "Identifier",
+                                        "kind": "IdentifierKind::Symbol",
+                                        "name": "comments"
+                                      },
+                                      "namespace": null
+                                    }
+                                  ]
+                                },
+                                "group_by": null,
+                                "having": null,
+                                "projection": [
+                                  {
+                                    "@type": "SqlProjection::All",
+                                    "collection": null
+                                  }
+                                ],
+                                "where": {
+                                  "@type": "Expr::Binary",
+                                  "left": {
+                                    "@type": "Expr::FieldPath",
+                                    "head": {
+                                      "@type": "Identifier",
+                                      "kind": "IdentifierKind::Symbol",
+                                      "name": "c"
+                                    },
+                                    "tail": [
+                                      {
+                                        "@type": "Identifier",
+                                        "kind": "IdentifierKind::Symbol",
+                                        "name": "book_id"
+                                      }
+                                    ]
+                                  },
+                                  "operation": {
+                                    "@type": "IsEqual"
+                                  },
+                                  "right": {
+                                    "@type": "Expr::FieldPath",
+                                    "head": {
+                                      "@type": "Identifier",
+                                      "kind": "IdentifierKind::Symbol",
+                                      "name": "b"
+                                    },
+                                    "tail": [
+                                      {
+                                        "@type": "Identifier",
+                                        "kind": "IdentifierKind::Symbol",
+                                        "name": "id"
+                                      }
+                                    ]
+                                  }
+                                }
+                              },
+                              "limit": null,
+                              "order_by": null
+                            }
+                          }
+                        },
+                        "operation": {
+                          "@type": "Exists"
+                        }
+                      },
+                      "operation": {
+                        "@type": "Not"
+                      }
+                    }
+                  },
+                  "limit": null,
+                  "order_by": null
+                }
+              }
+            }
+          ]
+        }
     }
 }
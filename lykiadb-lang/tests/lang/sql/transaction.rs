@@ -0,0 +1,61 @@
+use crate::assert_parsing;
+use crate::lang::compare_parsed_to_expected;
+use serde_json::json;
+
+assert_parsing! {
+    begin: {
+        "BEGIN;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Transaction",
+              "action": {
+                "@type": "Begin"
+              }
+            }
+          ]
+        }
+    },
+
+    begin_transaction: {
+        "BEGIN TRANSACTION;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Transaction",
+              "action": {
+                "@type": "Begin"
+              }
+            }
+          ]
+        }
+    },
+
+    commit: {
+        "COMMIT;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Transaction",
+              "action": {
+                "@type": "Commit"
+              }
+            }
+          ]
+        }
+    },
+
+    rollback: {
+        "ROLLBACK TRANSACTION;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Transaction",
+              "action": {
+                "@type": "Rollback"
+              }
+            }
+          ]
+        }
+    }
+}
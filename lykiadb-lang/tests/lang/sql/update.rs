@@ -0,0 +1,199 @@
+use crate::assert_parsing;
+use crate::lang::compare_parsed_to_expected;
+use serde_json::json;
+
+assert_parsing! {
+    plain: {
+        "UPDATE db.users SET name = 'John', address.city = 'Istanbul' WHERE age > 10 and name = 'Jane';" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Update",
+                "command": {
+                  "@type": "SqlUpdate",
+                  "assignments": [
+                    {
+                      "@type": "SqlAssignment",
+                      "expr": {
+                        "@type": "Expr::Literal",
+                        "raw": "John",
+                        "value": {
+                          "Str": "John"
+                        }
+                      },
+                      "path": [
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "name"
+                        }
+                      ]
+                    },
+                    {
+                      "@type": "SqlAssignment",
+                      "expr": {
+                        "@type": "Expr::Literal",
+                        "raw": "Istanbul",
+                        "value": {
+                          "Str": "Istanbul"
+                        }
+                      },
+                      "path": [
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "address"
+                        },
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "city"
+                        }
+                      ]
+                    }
+                  ],
+                  "collection": {
+                    "@type": "SqlCollectionIdentifier",
+                    "alias": null,
+                    "name": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "users"
+                    },
+                    "namespace": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "db"
+                    }
+                  },
+                  "where": {
+                    "@type": "Expr::Logical",
+                    "left": {
+                      "@type": "Expr::Binary",
+                      "left": {
+                        "@type": "Expr::FieldPath",
+                        "head": {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "age"
+                        },
+                        "tail": []
+                      },
+                      "operation": {
+                        "@type": "Greater"
+                      },
+                      "right": {
+                        "@type": "Expr::Literal",
+                        "raw": "10",
+                        "value": {
+                          "Num": 10.0
+                        }
+                      }
+                    },
+                    "operation": {
+                      "@type": "And"
+                    },
+                    "right": {
+                      "@type": "Expr::Binary",
+                      "left": {
+                        "@type": "Expr::FieldPath",
+                        "head": {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "name"
+                        },
+                        "tail": []
+                      },
+                      "operation": {
+                        "@type": "IsEqual"
+                      },
+                      "right": {
+                        "@type": "Expr::Literal",
+                        "raw": "Jane",
+                        "value": {
+                          "Str": "Jane"
+                        }
+                      }
+                    }
+                  }
+                }
+              }
+            }
+          ]
+        }
+    },
+
+    nested_path_with_alias: {
+        "UPDATE users u SET age = u.age + 1;" => {
+          "@type": "Stmt::Program",
+          "body": [
+            {
+              "@type": "Stmt::Expression",
+              "expr": {
+                "@type": "Expr::Update",
+                "command": {
+                  "@type": "SqlUpdate",
+                  "assignments": [
+                    {
+                      "@type": "SqlAssignment",
+                      "expr": {
+                        "@type": "Expr::Binary",
+                        "left": {
+                          "@type": "Expr::FieldPath",
+                          "head": {
+                            "@type": "Identifier",
+                            "kind": "IdentifierKind::Symbol",
+                            "name": "u"
+                          },
+                          "tail": [
+                            {
+                              "@type": "Identifier",
+                              "kind": "IdentifierKind::Symbol",
+                              "name": "age"
+                            }
+                          ]
+                        },
+                        "operation": {
+                          "@type": "Add"
+                        },
+                        "right": {
+                          "@type": "Expr::Literal",
+                          "raw": "1",
+                          "value": {
+                            "Num": 1.0
+                          }
+                        }
+                      },
+                      "path": [
+                        {
+                          "@type": "Identifier",
+                          "kind": "IdentifierKind::Symbol",
+                          "name": "age"
+                        }
+                      ]
+                    }
+                  ],
+                  "collection": {
+                    "@type": "SqlCollectionIdentifier",
+                    "alias": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "u"
+                    },
+                    "name": {
+                      "@type": "Identifier",
+                      "kind": "IdentifierKind::Symbol",
+                      "name": "users"
+                    },
+                    "namespace": null
+                  },
+                  "where": null
+                }
+              }
+            }
+          ]
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{StoreId, deserialize, error::EngineError, index, statistics::CollectionStatistics},
+    execution::error::ExecutionError,
+    store::{ScanDirection, Store, error::StoreError},
+};
+
+// Collection metadata is kept next to the documents, under a prefix that
+// no collection key starts with.
+const COLLECTION_PREFIX: &[u8] = b"\0catalog:collection:";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionMetadata {
+    pub namespace: String,
+    pub name: String,
+    // Paths of the fields documents are keyed by. Documents get generated
+    // keys when there is no primary key.
+    #[serde(default)]
+    pub primary_key: Vec<Vec<String>>,
+    #[serde(default)]
+    pub indexes: Vec<IndexMetadata>,
+    // As of the last `ANALYZE`, if any.
+    #[serde(default)]
+    pub statistics: Option<CollectionStatistics>,
+}
+
+impl CollectionMetadata {
+    pub fn index(&self, name: &str) -> Option<&IndexMetadata> {
+        self.indexes.iter().find(|index| index.name == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub name: String,
+    // Paths of the indexed fields, e.g. `["address", "city"]`.
+    pub fields: Vec<Vec<String>>,
+    // Whether two live documents may not have the same indexed values.
+    #[serde(default)]
+    pub unique: bool,
+}
+
+/// Spells a constraint the way it is declared, e.g.
+/// `UNIQUE (name.first, name.last)`.
+pub fn constraint_name(kind: &str, fields: &[Vec<String>]) -> String {
+    let fields = fields
+        .iter()
+        .map(|path| path.join("."))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{kind} ({fields})")
+}
+
+impl From<&StoreId> for CollectionMetadata {
+    fn from(sid: &StoreId) -> Self {
+        CollectionMetadata {
+            namespace: sid.namespace.clone(),
+            name: sid.name.clone(),
+            primary_key: vec![],
+            indexes: vec![],
+            statistics: None,
+        }
+    }
+}
+
+/// Owns the store, along with the metadata of the collections in it. The
+/// metadata is persisted in the store and kept in memory, so that looking a
+/// collection up does not cost a read.
+pub struct Catalog<S: for<'a> Store<'a>> {
+    pub(super) store: S,
+    collections: BTreeMap<Vec<u8>, CollectionMetadata>,
+}
+
+fn collection_key(sid: &StoreId) -> Vec<u8> {
+    [COLLECTION_PREFIX, &sid.prefix()].concat()
+}
+
+impl<S: for<'a> Store<'a>> Catalog<S> {
+    pub fn open(store: S) -> Result<Self, StoreError> {
+        let mut collections = BTreeMap::new();
+
+        for entry in store.scan_prefix(COLLECTION_PREFIX, ScanDirection::Forward) {
+            let (key, value) = entry?;
+            collections.insert(
+                key[COLLECTION_PREFIX.len()..].to_vec(),
+                deserialize(&value)?,
+            );
+        }
+
+        Ok(Catalog { store, collections })
+    }
+
+    pub fn collection(&self, sid: &StoreId) -> Result<&CollectionMetadata, ExecutionError> {
+        self.collections.get(&sid.prefix()).ok_or_else(|| {
+            ExecutionError::Engine(EngineError::CollectionNotFound {
+                collection: sid.to_string(),
+            })
+        })
+    }
+
+    pub fn collections(&self) -> impl Iterator<Item = &CollectionMetadata> {
+        self.collections.values()
+    }
+
+    pub fn create_collection(&mut self, sid: &StoreId) -> Result<(), ExecutionError> {
+        self.create_collection_with(CollectionMetadata::from(sid))
+    }
+
+    /// Creates a collection along with its keys and indexes.
+    pub fn create_collection_with(
+        &mut self,
+        metadata: CollectionMetadata,
+    ) -> Result<(), ExecutionError> {
+        let sid = StoreId::new(Some(&metadata.namespace), &metadata.name);
+        if self.collections.contains_key(&sid.prefix()) {
+            return Err(ExecutionError::Engine(
+                EngineError::CollectionAlreadyExists {
+                    collection: sid.to_string(),
+                },
+            ));
+        }
+
+        self.store.set(
+            &collection_key(&sid),
+            bson::serialize_to_vec(&metadata).unwrap(),
+        )?;
+        self.collections.insert(sid.prefix(), metadata);
+        Ok(())
+    }
+
+    /// Records a new index of the collection. Filling it is up to the caller.
+    pub fn create_index(
+        &mut self,
+        sid: &StoreId,
+        index: IndexMetadata,
+    ) -> Result<(), ExecutionError> {
+        let mut metadata = self.collection(sid)?.clone();
+        if metadata.index(&index.name).is_some() {
+            return Err(ExecutionError::Engine(EngineError::IndexAlreadyExists {
+                index: index.name,
+                collection: sid.to_string(),
+            }));
+        }
+
+        metadata.indexes.push(index);
+        self.store.set(
+            &collection_key(sid),
+            bson::serialize_to_vec(&metadata).unwrap(),
+        )?;
+        self.collections.insert(sid.prefix(), metadata);
+        Ok(())
+    }
+
+    /// Replaces the statistics of the collection.
+    pub fn set_statistics(
+        &mut self,
+        sid: &StoreId,
+        statistics: CollectionStatistics,
+    ) -> Result<(), ExecutionError> {
+        let mut metadata = self.collection(sid)?.clone();
+        metadata.statistics = Some(statistics);
+        self.store.set(
+            &collection_key(sid),
+            bson::serialize_to_vec(&metadata).unwrap(),
+        )?;
+        self.collections.insert(sid.prefix(), metadata);
+        Ok(())
+    }
+
+    /// Removes the collection along with every version of its documents
+    /// and index entries.
+    pub fn drop_collection(&mut self, sid: &StoreId) -> Result<(), ExecutionError> {
+        self.collection(sid)?;
+
+        // The metadata goes first, so that an interrupted drop leaves
+        // unreachable documents behind rather than a half empty collection.
+        self.store.delete(&collection_key(sid))?;
+        self.collections.remove(&sid.prefix());
+
+        let keys = [sid.prefix(), index::collection_prefix(sid)]
+            .iter()
+            .flat_map(|prefix| self.store.scan_prefix(prefix, ScanDirection::Forward))
+            .map(|entry| entry.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        for key in keys {
+            self.store.delete(&key)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory::MemoryStore;
+
+    #[test]
+    fn test_collections_survive_reopen() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        catalog
+            .create_collection(&StoreId::new(Some("db"), "users"))
+            .unwrap();
+        catalog
+            .create_collection(&StoreId::new(None, "books"))
+            .unwrap();
+
+        let catalog = Catalog::open(catalog.store).unwrap();
+        let names: Vec<_> = catalog
+            .collections()
+            .map(|c| format!("{}.{}", c.namespace, c.name))
+            .collect();
+        assert_eq!(names, vec!["db.users", "default.books"]);
+    }
+
+    #[test]
+    fn test_damaged_metadata_fails_to_open() {
+        let mut store = MemoryStore::new();
+        store
+            .set(
+                &collection_key(&StoreId::new(None, "users")),
+                b"not a document".to_vec(),
+            )
+            .unwrap();
+
+        assert!(matches!(
+            Catalog::open(store),
+            Err(StoreError::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_create_existing_collection_fails() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        let sid = StoreId::new(None, "users");
+        catalog.create_collection(&sid).unwrap();
+        assert_eq!(
+            catalog.create_collection(&sid),
+            Err(ExecutionError::Engine(
+                EngineError::CollectionAlreadyExists {
+                    collection: "default.users".to_string()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_indexes_are_kept_in_collection_metadata() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        let sid = StoreId::new(None, "users");
+        catalog.create_collection(&sid).unwrap();
+        let by_city = IndexMetadata {
+            name: "by_city".to_string(),
+            fields: vec![vec!["address".to_string(), "city".to_string()]],
+            unique: false,
+        };
+        catalog.create_index(&sid, by_city.clone()).unwrap();
+
+        assert_eq!(
+            catalog.create_index(&sid, by_city.clone()),
+            Err(ExecutionError::Engine(EngineError::IndexAlreadyExists {
+                index: "by_city".to_string(),
+                collection: "default.users".to_string()
+            }))
+        );
+        let catalog = Catalog::open(catalog.store).unwrap();
+        assert_eq!(
+            catalog.collection(&sid).unwrap().index("by_city"),
+            Some(&by_city)
+        );
+    }
+
+    #[test]
+    fn test_primary_key_is_kept_in_collection_metadata() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        let sid = StoreId::new(None, "users");
+        let mut metadata = CollectionMetadata::from(&sid);
+        metadata.primary_key = vec![vec!["name".to_string()], vec!["id".to_string()]];
+        catalog.create_collection_with(metadata.clone()).unwrap();
+
+        let catalog = Catalog::open(catalog.store).unwrap();
+        assert_eq!(catalog.collection(&sid).unwrap(), &metadata);
+        assert_eq!(
+            constraint_name("PRIMARY KEY", &metadata.primary_key),
+            "PRIMARY KEY (name, id)"
+        );
+    }
+
+    #[test]
+    fn test_statistics_are_kept_in_collection_metadata() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        let sid = StoreId::new(None, "users");
+        catalog.create_collection(&sid).unwrap();
+        let statistics = CollectionStatistics {
+            rows: 3,
+            ..Default::default()
+        };
+        catalog.set_statistics(&sid, statistics.clone()).unwrap();
+
+        let catalog = Catalog::open(catalog.store).unwrap();
+        assert_eq!(
+            catalog.collection(&sid).unwrap().statistics,
+            Some(statistics)
+        );
+    }
+
+    #[test]
+    fn test_drop_collection_removes_only_its_documents() {
+        let mut catalog = Catalog::open(MemoryStore::new()).unwrap();
+        let users = StoreId::new(None, "users");
+        let users_archive = StoreId::new(None, "users_archive");
+        catalog.create_collection(&users).unwrap();
+        catalog.create_collection(&users_archive).unwrap();
+        catalog
+            .store
+            .set(&[users.prefix(), b"a".to_vec()].concat(), vec![1])
+            .unwrap();
+        catalog
+            .store
+            .set(&[users_archive.prefix(), b"a".to_vec()].concat(), vec![2])
+            .unwrap();
+        catalog
+            .store
+            .set(&index::index_prefix(&users, "by_name"), vec![3])
+            .unwrap();
+
+        catalog.drop_collection(&users).unwrap();
+
+        assert!(catalog.collection(&users).is_err());
+        assert!(catalog.collection(&users_archive).is_ok());
+        let keys: Vec<_> = catalog.store.scan().map(|e| e.unwrap().0).collect();
+        assert_eq!(
+            keys,
+            vec![
+                collection_key(&users_archive),
+                [users_archive.prefix(), b"a".to_vec()].concat()
+            ]
+        );
+        assert_eq!(
+            catalog.drop_collection(&users),
+            Err(ExecutionError::Engine(EngineError::CollectionNotFound {
+                collection: "default.users".to_string()
+            }))
+        );
+    }
+}
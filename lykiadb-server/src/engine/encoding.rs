@@ -0,0 +1,354 @@
+//! An order-preserving ("memcomparable") encoding of scalar values and of
+//! tuples of them. Encodings compare byte by byte the way the values compare
+//! with [`RV::total_cmp`], so that ranges of values are ranges of keys.
+//!
+//! Every encoded value starts with a tag for its type and delimits itself,
+//! so a tuple is the concatenation of its values, and a tuple sorts before
+//! the longer tuples it is a prefix of.
+
+use bson::Decimal128;
+
+use crate::value::RV;
+
+// Type tags, in the order of `RV::total_cmp`. Numbers of all
+// representations share a tag, so that they compare by value.
+const UNDEFINED: u8 = 0x01;
+const NULL: u8 = 0x02;
+pub(super) const BOOL: u8 = 0x03;
+pub(super) const NUMBER: u8 = 0x04;
+pub(super) const STRING: u8 = 0x05;
+const DATETIME: u8 = 0x06;
+
+// Strings are terminated, and their zero bytes escaped, so that a string
+// sorts before any longer string it is a prefix of.
+const ESCAPE: u8 = 0xFF;
+const TERMINATOR: u8 = 0x01;
+
+// Equal numbers of different representations are ordered by
+// representation, like `RV::total_cmp` does.
+const INT32: u8 = 0;
+const INT64: u8 = 1;
+const DOUBLE: u8 = 2;
+const DECIMAL: u8 = 3;
+
+/// Encodes a scalar value. Objects, arrays and the like have no encoding.
+pub fn encode_scalar(value: &RV) -> Option<Vec<u8>> {
+    let mut encoded = vec![];
+    encode_into(value, &mut encoded)?;
+    Some(encoded)
+}
+
+/// Encodes the values one after the other. Returns `None` when any of them
+/// is not a scalar.
+pub fn encode_tuple(values: &[RV]) -> Option<Vec<u8>> {
+    let mut encoded = vec![];
+    for value in values {
+        encode_into(value, &mut encoded)?;
+    }
+    Some(encoded)
+}
+
+pub fn encode_str(s: &str) -> Vec<u8> {
+    let mut encoded = vec![];
+    encode_str_into(s, &mut encoded);
+    encoded
+}
+
+fn encode_into(value: &RV, out: &mut Vec<u8>) -> Option<()> {
+    match value {
+        RV::Undefined => out.push(UNDEFINED),
+        RV::Null => out.push(NULL),
+        RV::Bool(b) => out.extend([BOOL, *b as u8]),
+        // Numbers are ordered by their closest double first, and by their
+        // exact value within the same double.
+        RV::Int32(n) => encode_number(*n as f64, INT32, &[], out),
+        RV::Int64(n) => encode_number(*n as f64, INT64, &(*n as u64 ^ 1 << 63).to_be_bytes(), out),
+        RV::Double(n) => encode_number(*n, DOUBLE, &[], out),
+        RV::Decimal128(d) => {
+            let approx = d.to_string().parse::<f64>().unwrap_or(f64::NAN);
+            encode_number(approx, DECIMAL, &encode_decimal(d), out)
+        }
+        RV::Str(s) => encode_str_into(s, out),
+        RV::DateTime(dt) => {
+            out.push(DATETIME);
+            out.extend((dt.timestamp_millis() as u64 ^ 1 << 63).to_be_bytes());
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+fn encode_str_into(s: &str, out: &mut Vec<u8>) {
+    out.push(STRING);
+    for byte in s.as_bytes() {
+        out.push(*byte);
+        if *byte == 0 {
+            out.push(ESCAPE);
+        }
+    }
+    out.extend([0, TERMINATOR]);
+}
+
+fn encode_number(approx: f64, representation: u8, exact: &[u8], out: &mut Vec<u8>) {
+    // Zero and NaN are stored without their sign and payload, as they
+    // equal their counterparts.
+    let approx = if approx == 0.0 {
+        0.0
+    } else if approx.is_nan() {
+        f64::NAN
+    } else {
+        approx
+    };
+    let bits = approx.to_bits();
+    let bits = if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | 1 << 63
+    };
+    out.push(NUMBER);
+    out.extend(bits.to_be_bytes());
+    out.push(representation);
+    out.extend(exact);
+}
+
+// Decimals sort by class first, then by magnitude: the adjusted exponent,
+// followed by the significant digits. Negative numbers have their
+// magnitude inverted, so that larger ones sort first.
+const DECIMAL_NEGATIVE_INFINITY: u8 = 0;
+const DECIMAL_NEGATIVE: u8 = 1;
+const DECIMAL_ZERO: u8 = 2;
+const DECIMAL_POSITIVE: u8 = 3;
+const DECIMAL_INFINITY: u8 = 4;
+const DECIMAL_NAN: u8 = 5;
+
+const DECIMAL_EXPONENT_BIAS: i32 = 6176;
+const DECIMAL_MAX_COEFFICIENT: u128 = 10u128.pow(34) - 1;
+
+fn encode_decimal(d: &Decimal128) -> Vec<u8> {
+    // The layout of IEEE 754 decimal128, in its binary integer form.
+    let bits = u128::from_le_bytes(d.bytes());
+    let negative = bits >> 127 == 1;
+    if (bits >> 123) & 0xF == 0xF {
+        return if (bits >> 122) & 1 == 1 {
+            vec![DECIMAL_NAN]
+        } else if negative {
+            vec![DECIMAL_NEGATIVE_INFINITY]
+        } else {
+            vec![DECIMAL_INFINITY]
+        };
+    }
+    // Coefficients that do not fit the 113 bits are out of range, and
+    // count as zero.
+    if (bits >> 125) & 0b11 == 0b11 {
+        return vec![DECIMAL_ZERO];
+    }
+    let exponent = ((bits >> 113) & 0x3FFF) as i32 - DECIMAL_EXPONENT_BIAS;
+    let coefficient = bits & ((1 << 113) - 1);
+    if coefficient == 0 || coefficient > DECIMAL_MAX_COEFFICIENT {
+        return vec![DECIMAL_ZERO];
+    }
+
+    // The value is 0.d1d2...dn * 10^adjusted, with no trailing zeros.
+    let digits = coefficient.to_string();
+    let adjusted = exponent + digits.len() as i32;
+    let mut magnitude = (adjusted as i16 as u16 ^ 1 << 15).to_be_bytes().to_vec();
+    magnitude.extend(digits.trim_end_matches('0').bytes());
+    magnitude.push(0);
+
+    if negative {
+        [
+            vec![DECIMAL_NEGATIVE],
+            magnitude.iter().map(|b| !b).collect(),
+        ]
+        .concat()
+    } else {
+        [vec![DECIMAL_POSITIVE], magnitude].concat()
+    }
+}
+
+/// Spells bytes in lowercase hex, which sorts the same way as the bytes.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::Ordering, sync::Arc};
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn decimal(coefficient: i64, exponent: i32) -> RV<'static> {
+        RV::Decimal128(format!("{coefficient}E{exponent}").parse().unwrap())
+    }
+
+    // Doubles without negative zero and NaN, which are folded into their
+    // counterparts.
+    fn double() -> impl Strategy<Value = f64> {
+        prop_oneof![
+            any::<f64>().prop_filter("folded", |n| !n.is_nan() && *n != 0.0),
+            Just(0.0),
+            Just(f64::INFINITY),
+            Just(f64::NEG_INFINITY),
+            (-1000i64..1000).prop_map(|n| n as f64),
+        ]
+    }
+
+    fn scalar() -> impl Strategy<Value = RV<'static>> {
+        prop_oneof![
+            Just(RV::Undefined),
+            Just(RV::Null),
+            any::<bool>().prop_map(RV::Bool),
+            any::<i32>().prop_map(RV::Int32),
+            any::<i64>().prop_map(RV::Int64),
+            (-1000i32..1000).prop_map(RV::Int32),
+            (-1000i64..1000).prop_map(RV::Int64),
+            double().prop_map(RV::Double),
+            ".*".prop_map(|s| RV::Str(Arc::new(s))),
+            "[a\0b]{0,4}".prop_map(|s| RV::Str(Arc::new(s))),
+            any::<i64>().prop_map(|ms| RV::DateTime(bson::DateTime::from_millis(ms))),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn test_scalar_order_matches_total_order(a in scalar(), b in scalar()) {
+            prop_assert_eq!(
+                encode_scalar(&a).unwrap().cmp(&encode_scalar(&b).unwrap()),
+                a.total_cmp(&b)
+            );
+        }
+
+        #[test]
+        fn test_tuple_order_is_lexicographic(
+            a in prop::collection::vec(scalar(), 0..4),
+            b in prop::collection::vec(scalar(), 0..4),
+        ) {
+            let expected = a
+                .iter()
+                .zip(&b)
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ord| ord.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len()));
+            prop_assert_eq!(
+                encode_tuple(&a).unwrap().cmp(&encode_tuple(&b).unwrap()),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_decimal_order_matches_numeric_order(
+            a in (-10i64.pow(15)..10i64.pow(15), -10i32..10),
+            b in (-10i64.pow(15)..10i64.pow(15), -10i32..10),
+        ) {
+            // Both sides scaled to the smaller exponent, which fits an i128.
+            let scale = a.1.min(b.1);
+            let exact = |(coefficient, exponent): (i64, i32)| {
+                coefficient as i128 * 10i128.pow((exponent - scale) as u32)
+            };
+            prop_assert_eq!(
+                encode_scalar(&decimal(a.0, a.1)).cmp(&encode_scalar(&decimal(b.0, b.1))),
+                exact(a).cmp(&exact(b))
+            );
+        }
+
+        #[test]
+        fn test_hex_preserves_order(
+            a in prop::collection::vec(any::<u8>(), 0..8),
+            b in prop::collection::vec(any::<u8>(), 0..8),
+        ) {
+            prop_assert_eq!(to_hex(&a).cmp(&to_hex(&b)), a.cmp(&b));
+        }
+    }
+
+    #[test]
+    fn test_encoding_preserves_order() {
+        let ordered = [
+            RV::Double(f64::NEG_INFINITY),
+            RV::Double(-2.5),
+            RV::Double(-1.0),
+            RV::Double(0.0),
+            RV::Double(1e-300),
+            RV::Double(3.0),
+            RV::Double(f64::INFINITY),
+        ];
+        for pair in ordered.windows(2) {
+            assert!(
+                encode_scalar(&pair[0]) < encode_scalar(&pair[1]),
+                "{pair:?}"
+            );
+        }
+
+        let ordered =
+            ["", "\0", "\0\0", "a", "a\0", "ab", "b"].map(|s| RV::Str(Arc::new(s.to_string())));
+        for pair in ordered.windows(2) {
+            assert!(
+                encode_scalar(&pair[0]) < encode_scalar(&pair[1]),
+                "{pair:?}"
+            );
+        }
+
+        assert_eq!(
+            encode_scalar(&RV::Double(-0.0)),
+            encode_scalar(&RV::Double(0.0))
+        );
+        assert!(encode_scalar(&RV::Bool(false)) < encode_scalar(&RV::Bool(true)));
+        assert_eq!(encode_scalar(&RV::Array(Default::default())), None);
+    }
+
+    #[test]
+    fn test_equal_values_share_an_encoding() {
+        assert_eq!(
+            encode_scalar(&RV::Double(-0.0)),
+            encode_scalar(&RV::Double(0.0))
+        );
+        assert_eq!(
+            encode_scalar(&RV::Double(-f64::NAN)),
+            encode_scalar(&RV::Double(f64::NAN))
+        );
+        assert_eq!(
+            encode_scalar(&decimal(10, 0)),
+            encode_scalar(&decimal(1, 1))
+        );
+        assert_eq!(
+            encode_scalar(&decimal(0, 5)),
+            encode_scalar(&decimal(-0, -3))
+        );
+    }
+
+    #[test]
+    fn test_numbers_compare_across_representations() {
+        let ordered = [
+            RV::Decimal128("-Infinity".parse().unwrap()),
+            RV::Double(-1e300),
+            decimal(-15, -1),
+            RV::Int64(-1),
+            RV::Int32(0),
+            RV::Int64(0),
+            RV::Double(0.5),
+            decimal(5, -1),
+            RV::Int32(1),
+            decimal(101, -2),
+            RV::Int64(i64::MAX - 1),
+            RV::Int64(i64::MAX),
+            RV::Decimal128("Infinity".parse().unwrap()),
+        ];
+        for pair in ordered.windows(2) {
+            assert_eq!(
+                encode_scalar(&pair[0]).cmp(&encode_scalar(&pair[1])),
+                Ordering::Less,
+                "{pair:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_scalars_have_no_encoding() {
+        assert_eq!(encode_scalar(&RV::Array(Default::default())), None);
+        assert_eq!(
+            encode_tuple(&[RV::Null, RV::Object(Default::default())]),
+            None
+        );
+    }
+}
@@ -1,18 +1,54 @@
 use lykiadb_common::error::InputError;
+use lykiadb_lang::ast::Span;
 use serde::{Deserialize, Serialize};
 
 #[derive(thiserror::Error, PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum EngineError {
     #[error("Only objects can be inserted to the collections")]
     InvalidValue,
+    #[error("Document keys cannot contain null characters")]
+    InvalidKey,
+    #[error("Could not serialize access due to a concurrent update")]
+    SerializationFailure,
+    #[error("Collection '{collection}' does not exist")]
+    CollectionNotFound { collection: String },
+    #[error("Collection '{collection}' already exists")]
+    CollectionAlreadyExists { collection: String },
+    #[error("Index '{index}' already exists on collection '{collection}'")]
+    IndexAlreadyExists { index: String, collection: String },
+    #[error("Index '{index}' does not exist on collection '{collection}'")]
+    IndexNotFound { index: String, collection: String },
+    // The span is left empty by the engine and filled in with the one of
+    // the offending statement.
+    #[error("Document violates the {constraint} constraint")]
+    ConstraintViolation { constraint: String, span: Span },
 }
 
 impl From<EngineError> for InputError {
     fn from(value: EngineError) -> Self {
         let hint = match &value {
             EngineError::InvalidValue => "Ensure the value is a valid object",
+            EngineError::InvalidKey => "Remove the null characters from the key",
+            EngineError::SerializationFailure => {
+                "Another transaction changed the same document, retry the transaction"
+            }
+            EngineError::CollectionNotFound { .. } => {
+                "Create the collection with CREATE COLLECTION first"
+            }
+            EngineError::CollectionAlreadyExists { .. } => {
+                "Choose another name, or drop the existing collection first"
+            }
+            EngineError::IndexAlreadyExists { .. } => "Choose another name for the index",
+            EngineError::IndexNotFound { .. } => "Create the index with CREATE INDEX first",
+            EngineError::ConstraintViolation { .. } => {
+                "Primary keys must be present and unique, and unique fields must not repeat"
+            }
         };
 
-        InputError::new(&value.to_string(), hint, None)
+        let span = match &value {
+            EngineError::ConstraintViolation { span, .. } => Some((*span).into()),
+            _ => None,
+        };
+        InputError::new(&value.to_string(), hint, span)
     }
 }
@@ -0,0 +1,260 @@
+use std::ops::Bound;
+
+use crate::{
+    engine::{
+        StoreId,
+        catalog::IndexMetadata,
+        encoding::{BOOL, NUMBER, STRING, encode_scalar, encode_str},
+    },
+    store::{KeyBounds, successor},
+    value::RV,
+};
+
+// Index entries are kept next to the documents, under a prefix that no
+// collection key starts with.
+const INDEX_PREFIX: &[u8] = b"\0index:";
+
+// Values that are neither equal nor ordered to anything, e.g. objects. Sorts
+// after the values of all types.
+const OTHER: u8 = 0xFF;
+
+// Types whose values the comparison operators coerce to one another, e.g.
+// `"30" = 30` and `true = 1`.
+const COERCIBLE: [u8; 3] = [BOOL, NUMBER, STRING];
+
+/// The prefix of the entries of all indexes of a collection.
+pub(super) fn collection_prefix(sid: &StoreId) -> Vec<u8> {
+    [INDEX_PREFIX, &sid.prefix()].concat()
+}
+
+pub(super) fn index_prefix(sid: &StoreId, index: &str) -> Vec<u8> {
+    [collection_prefix(sid), encode_str(index)].concat()
+}
+
+/// The key of the entry `document` has in `index`. Entries sort by the
+/// indexed values first and by the document key last, so each document has
+/// an entry of its own.
+pub(super) fn entry_key(
+    sid: &StoreId,
+    index: &IndexMetadata,
+    document: &RV,
+    doc_key: &str,
+) -> Vec<u8> {
+    let mut key = index_prefix(sid, &index.name);
+    for path in &index.fields {
+        let value = field_value(document, path);
+        key.extend(encode_scalar(&value).unwrap_or_else(|| vec![OTHER]));
+    }
+    key.extend(doc_key.as_bytes());
+    key
+}
+
+/// The prefix of the entries of the documents that have the same indexed
+/// values as `document`. Returns `None` when any of them is null, missing or
+/// compares to nothing, as such documents are never duplicates of others.
+pub(super) fn values_prefix(
+    sid: &StoreId,
+    index: &IndexMetadata,
+    document: &RV,
+) -> Option<Vec<u8>> {
+    let mut prefix = index_prefix(sid, &index.name);
+    for path in &index.fields {
+        match field_value(document, path) {
+            RV::Undefined | RV::Null => return None,
+            value => prefix.extend(encode_scalar(&value)?),
+        }
+    }
+    Some(prefix)
+}
+
+pub(super) fn field_value<'v>(document: &RV<'v>, path: &[String]) -> RV<'v> {
+    let mut value = document.clone();
+    for name in path {
+        value = match value {
+            RV::Object(obj) => obj.get(name).unwrap_or(RV::Undefined),
+            _ => RV::Undefined,
+        };
+    }
+    value
+}
+
+/// A range of values of the first indexed field. Values of other types than
+/// the bounds are never in the range, even when the comparison operators
+/// would coerce them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyRange {
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+}
+
+impl KeyRange {
+    /// Returns `None` when no value can be in the range.
+    pub fn new(lower: Bound<&RV>, upper: Bound<&RV>) -> Option<Self> {
+        let encode_bound = |bound: Bound<&RV>| -> Option<Bound<Vec<u8>>> {
+            Some(match bound {
+                Bound::Included(value) => Bound::Included(encode_scalar(value)?),
+                Bound::Excluded(value) => Bound::Excluded(encode_scalar(value)?),
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        };
+        let (lower, upper) = (encode_bound(lower)?, encode_bound(upper)?);
+
+        let tag = |bound: &Bound<Vec<u8>>| match bound {
+            Bound::Included(key) | Bound::Excluded(key) => Some(key[0]),
+            Bound::Unbounded => None,
+        };
+        let tag = match (tag(&lower), tag(&upper)) {
+            (Some(lower), Some(upper)) if lower != upper => return None,
+            (Some(tag), _) | (_, Some(tag)) => tag,
+            (None, None) => return Some(KeyRange { lower, upper }),
+        };
+
+        // Open ends stop at the values of the other types.
+        Some(KeyRange {
+            lower: match lower {
+                Bound::Unbounded => Bound::Included(vec![tag]),
+                bound => bound,
+            },
+            upper: match upper {
+                Bound::Unbounded => Bound::Excluded(vec![tag + 1]),
+                bound => bound,
+            },
+        })
+    }
+
+    pub fn point(value: &RV) -> Option<Self> {
+        Self::new(Bound::Included(value), Bound::Included(value))
+    }
+
+    /// The ranges to read for all the values that may compare to the bounds
+    /// the way the language does. The values of the types the bounds are
+    /// coerced to are read whole, and bounds of different types read the
+    /// whole index, so what is read has to be filtered with the comparison.
+    pub fn covering(lower: Bound<&RV>, upper: Bound<&RV>) -> Vec<Self> {
+        let tag = |bound: Bound<&RV>| match bound {
+            Bound::Included(value) | Bound::Excluded(value) => {
+                encode_scalar(value).map(|key| key[0])
+            }
+            Bound::Unbounded => None,
+        };
+        let Some(range) = Self::new(lower, upper) else {
+            return match (tag(lower), tag(upper)) {
+                (Some(_), Some(_)) => vec![KeyRange {
+                    lower: Bound::Unbounded,
+                    upper: Bound::Unbounded,
+                }],
+                _ => vec![],
+            };
+        };
+
+        let mut ranges = vec![range];
+        if let Some(tag) = tag(lower).or(tag(upper))
+            && COERCIBLE.contains(&tag)
+        {
+            ranges.extend(
+                COERCIBLE
+                    .into_iter()
+                    .filter(|other| *other != tag)
+                    .map(|other| KeyRange {
+                        lower: Bound::Included(vec![other]),
+                        upper: Bound::Excluded(vec![other + 1]),
+                    }),
+            );
+        }
+        ranges
+    }
+
+    /// The bounds of the entries in the range, among the entries under
+    /// `prefix`. The bounds only cover the first field, so the entries that
+    /// start with a bound are equal to it.
+    pub(super) fn bounds(&self, prefix: &[u8]) -> KeyBounds {
+        let key = |bound: &[u8]| [prefix, bound].concat();
+        let after = |bound: &[u8]| successor(&key(bound));
+        let lower = match &self.lower {
+            Bound::Included(bound) => Bound::Included(key(bound)),
+            Bound::Excluded(bound) => after(bound).map_or(Bound::Unbounded, Bound::Included),
+            Bound::Unbounded => Bound::Included(prefix.to_vec()),
+        };
+        let upper = match &self.upper {
+            Bound::Included(bound) => after(bound).map_or(Bound::Unbounded, Bound::Excluded),
+            Bound::Excluded(bound) => Bound::Excluded(key(bound)),
+            Bound::Unbounded => successor(prefix).map_or(Bound::Unbounded, Bound::Excluded),
+        };
+        (lower, upper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::RangeBounds, sync::Arc};
+
+    use super::*;
+
+    fn str(s: &str) -> RV<'static> {
+        RV::Str(Arc::new(s.to_string()))
+    }
+
+    impl KeyRange {
+        fn contains(&self, entry: &[u8]) -> bool {
+            self.bounds(b"").contains(&entry.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_key_range_contains_only_its_type() {
+        let entry = |value: &RV| [encode_scalar(value).unwrap(), b"doc".to_vec()].concat();
+
+        let below_3 = KeyRange::new(Bound::Unbounded, Bound::Excluded(&RV::Double(3.0))).unwrap();
+        assert!(below_3.contains(&entry(&RV::Double(-10.0))));
+        assert!(!below_3.contains(&entry(&RV::Double(3.0))));
+        assert!(!below_3.contains(&entry(&RV::Null)));
+        assert!(!below_3.contains(&entry(&str("1"))));
+
+        let from_b = KeyRange::new(Bound::Included(&str("b")), Bound::Unbounded).unwrap();
+        assert!(from_b.contains(&entry(&str("b"))));
+        assert!(from_b.contains(&entry(&str("ba"))));
+        assert!(!from_b.contains(&entry(&str("a"))));
+        assert!(!from_b.contains(&entry(&RV::Double(1.0))));
+
+        let point = KeyRange::point(&str("b")).unwrap();
+        assert!(point.contains(&entry(&str("b"))));
+        assert!(!point.contains(&entry(&str("ba"))));
+
+        assert_eq!(
+            KeyRange::new(
+                Bound::Included(&str("a")),
+                Bound::Included(&RV::Double(1.0))
+            ),
+            None
+        );
+        assert_eq!(KeyRange::point(&RV::Array(Default::default())), None);
+    }
+
+    #[test]
+    fn test_covering_ranges_include_coerced_values() {
+        let entry = |value: &RV| [encode_scalar(value).unwrap(), b"doc".to_vec()].concat();
+        let covers = |ranges: &[KeyRange], value: &RV| {
+            ranges.iter().any(|range| range.contains(&entry(value)))
+        };
+
+        let above_20 = KeyRange::covering(Bound::Excluded(&RV::Double(20.0)), Bound::Unbounded);
+        assert!(covers(&above_20, &RV::Double(30.0)));
+        assert!(!covers(&above_20, &RV::Double(10.0)));
+        assert!(covers(&above_20, &str("30")));
+        assert!(covers(&above_20, &RV::Bool(true)));
+        assert!(!covers(&above_20, &RV::Null));
+
+        let null = KeyRange::covering(Bound::Included(&RV::Null), Bound::Included(&RV::Null));
+        assert_eq!(null, vec![KeyRange::point(&RV::Null).unwrap()]);
+
+        let mixed = KeyRange::covering(
+            Bound::Included(&RV::Double(1.0)),
+            Bound::Included(&str("5")),
+        );
+        assert!(covers(&mixed, &RV::Null));
+        assert!(covers(&mixed, &str("3")));
+
+        let array = RV::Array(Default::default());
+        assert!(KeyRange::covering(Bound::Included(&array), Bound::Included(&array)).is_empty());
+    }
+}
@@ -1,31 +1,139 @@
+pub mod catalog;
+pub mod encoding;
 pub mod error;
+pub mod index;
+pub mod mvcc;
+pub mod statistics;
 
-use std::iter::Filter;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    ops::Bound,
+};
+
+use lykiadb_lang::ast::{Span, sql::SqlCollectionIdentifier};
+use serde::Deserialize;
 
 use crate::{
-    engine::error::EngineError,
+    engine::{
+        catalog::{Catalog, CollectionMetadata, IndexMetadata, constraint_name},
+        encoding::{encode_str, encode_tuple, to_hex},
+        error::EngineError,
+        index::{KeyRange, entry_key, field_value, index_prefix, values_prefix},
+        mvcc::{
+            ACTIVE_PREFIX, NEXT_VERSION_KEY, Snapshot, Transaction, Version, VisibleScan,
+            WRITE_PREFIX, active_key, decode_value, decode_version, encode_value, encode_version,
+            split_version_key, version_key, versions_prefix, write_key, write_prefix,
+        },
+        statistics::CollectionStatistics,
+    },
     execution::error::ExecutionError,
     store::{
-        IteratorItem, Store,
-        memory::{MemoryScanIterator, MemoryStore},
+        IteratorItem, KeyBounds, ScanDirection, Store, boxed::BoxedStore, error::StoreError,
+        memory::MemoryStore,
     },
     value::RV,
 };
 
-pub struct StoreId(String);
+pub(crate) const DEFAULT_NAMESPACE: &str = "default";
+
+pub struct StoreId {
+    namespace: String,
+    name: String,
+}
+
+impl StoreId {
+    pub fn new(namespace: Option<&str>, name: &str) -> Self {
+        StoreId {
+            namespace: namespace.unwrap_or(DEFAULT_NAMESPACE).to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// The prefix of the keys of the documents in the collection. The
+    /// names are encoded so that no prefix is the start of another, e.g.
+    /// the ones of `ns1` and `ns10`.
+    fn prefix(&self) -> Vec<u8> {
+        [encode_str(&self.namespace), encode_str(&self.name)].concat()
+    }
+}
+
+impl Display for StoreId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.namespace, self.name)
+    }
+}
 
-pub struct Catalog<S: for<'a> Store<'a>> {
-    store: S,
+impl From<&SqlCollectionIdentifier> for StoreId {
+    fn from(collection: &SqlCollectionIdentifier) -> Self {
+        StoreId::new(
+            collection.namespace.as_ref().map(|ns| ns.name.as_str()),
+            &collection.name.name,
+        )
+    }
 }
 
 pub struct Engine<S: for<'a> Store<'a>> {
     catalog: Catalog<S>,
+    next_version: Version,
+    // The snapshots of the running transactions, by their versions.
+    active: BTreeMap<Version, Snapshot>,
+}
+
+// Object ids are roughly ordered by creation time, so collection scans
+// return freshly inserted documents after the older ones.
+fn generate_key() -> String {
+    bson::oid::ObjectId::new().to_hex()
+}
+
+// The key of a document in a collection with a primary key: its values,
+// encoded so that the keys sort like the values do, and spelled in hex.
+// Returns `None` when a value is missing, null or not a scalar.
+fn primary_key(fields: &[Vec<String>], document: &RV) -> Option<String> {
+    let values = fields
+        .iter()
+        .map(|path| match field_value(document, path) {
+            RV::Undefined | RV::Null => None,
+            value => Some(value),
+        })
+        .collect::<Option<Vec<RV>>>()?;
+    encode_tuple(&values).map(|key| to_hex(&key))
+}
+
+fn compare_lower_bounds(a: &Bound<Vec<u8>>, b: &Bound<Vec<u8>>) -> Ordering {
+    match (a, b) {
+        (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+        (Bound::Unbounded, _) => Ordering::Less,
+        (_, Bound::Unbounded) => Ordering::Greater,
+        (Bound::Included(a) | Bound::Excluded(a), Bound::Included(b) | Bound::Excluded(b)) => {
+            a.cmp(b)
+        }
+    }
 }
 
-fn encode_key(sid: &StoreId, key: &str) -> Vec<u8> {
-    let mut k = sid.0.clone();
-    k.push_str(key);
-    k.into_bytes()
+fn violation(constraint: String) -> ExecutionError {
+    ExecutionError::Engine(EngineError::ConstraintViolation {
+        constraint,
+        span: Span::default(),
+    })
+}
+
+// Documents and metadata are read back from stores that can be damaged, so
+// a value that does not decode fails the read instead of the server.
+fn deserialize<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, StoreError> {
+    bson::deserialize_from_slice(bytes).map_err(|err| StoreError::InvalidData {
+        message: err.to_string(),
+    })
+}
+
+// Versions are appended to document keys after a zero byte, so keys that
+// contain one could be taken for a version of another key.
+fn encode_key(sid: &StoreId, key: &str) -> Result<Vec<u8>, ExecutionError> {
+    if key.contains('\0') {
+        return Err(ExecutionError::Engine(EngineError::InvalidKey));
+    }
+    Ok([sid.prefix(), key.as_bytes().to_vec()].concat())
 }
 
 impl Default for Engine<MemoryStore> {
@@ -36,68 +144,610 @@ impl Default for Engine<MemoryStore> {
 
 impl Engine<MemoryStore> {
     pub fn new() -> Self {
-        Engine {
-            catalog: Catalog {
-                store: MemoryStore::new(),
-            },
-        }
+        Self::with_store(MemoryStore::new()).unwrap()
     }
 }
 
-impl Engine<MemoryStore> {
-    pub fn get(&'_ self, sid: &StoreId, key: &str) -> Option<RV<'_>> {
-        let encoded_key = encode_key(sid, key);
+impl Engine<BoxedStore> {
+    /// Builds an engine on top of a store of any backend, so that the
+    /// backend can be picked at runtime without the engine type changing.
+    pub fn boxed(
+        store: impl for<'a> Store<'a> + Send + Sync + 'static,
+    ) -> Result<Self, ExecutionError> {
+        Self::with_store(BoxedStore::new(store))
+    }
+}
+
+impl<S: for<'a> Store<'a>> Engine<S> {
+    /// Builds an engine on top of the given store, e.g. a
+    /// [`DiskStore`](crate::store::disk::DiskStore) opened in the data
+    /// directory. Transactions that were still running when the store was
+    /// last used are rolled back.
+    pub fn with_store(store: S) -> Result<Self, ExecutionError> {
+        let mut engine = Engine {
+            catalog: Catalog::open(store)?,
+            next_version: 1,
+            active: BTreeMap::new(),
+        };
+        engine.recover()?;
+        Ok(engine)
+    }
+
+    fn recover(&mut self) -> Result<(), ExecutionError> {
+        if let Some(next) = self.catalog.store.get(NEXT_VERSION_KEY)? {
+            self.next_version = decode_version(&next);
+        }
+
+        for key in self.keys_with_prefix(ACTIVE_PREFIX)? {
+            let version = decode_version(&key[ACTIVE_PREFIX.len()..]);
+            self.rollback_version(version)?;
+        }
+
+        // What is left belongs to committed transactions.
+        for key in self.keys_with_prefix(WRITE_PREFIX)? {
+            self.catalog.store.delete(&key)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn catalog(&self) -> &Catalog<S> {
+        &self.catalog
+    }
+
+    /// Collections are created and dropped right away, regardless of the
+    /// transactions in progress.
+    pub fn create_collection(&mut self, sid: &StoreId) -> Result<(), ExecutionError> {
+        self.catalog.create_collection(sid)
+    }
+
+    pub fn create_collection_with(
+        &mut self,
+        metadata: CollectionMetadata,
+    ) -> Result<(), ExecutionError> {
+        self.catalog.create_collection_with(metadata)
+    }
+
+    pub fn drop_collection(&mut self, sid: &StoreId) -> Result<(), ExecutionError> {
+        self.catalog.drop_collection(sid)
+    }
+
+    /// Gathers the statistics of the documents committed so far, and keeps
+    /// them in the catalog for the planner to use.
+    pub fn analyze(&mut self, sid: &StoreId) -> Result<(), ExecutionError> {
+        let documents = self
+            .scan_documents(sid)?
+            .map(|entry| entry.map(|(_, document)| document))
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        self.catalog
+            .set_statistics(sid, CollectionStatistics::gather(documents))
+    }
+
+    /// Creates an index and fills it right away. Every version of the
+    /// documents gets an entry with the same version, so that the index
+    /// agrees with the documents in all snapshots, including the ones of the
+    /// transactions in progress.
+    pub fn create_index(
+        &mut self,
+        sid: &StoreId,
+        index: IndexMetadata,
+    ) -> Result<(), ExecutionError> {
+        self.catalog.create_index(sid, index.clone())?;
+
+        let prefix_len = sid.prefix().len();
+        let versions = self
+            .scan_prefix(sid.prefix())
+            .collect::<Result<Vec<_>, StoreError>>()?;
+
+        // The entry of the previous version of the same document.
+        let mut previous: Option<(Vec<u8>, Option<Vec<u8>>)> = None;
+        for (versioned_key, value) in versions {
+            let Some((key, version)) = split_version_key(&versioned_key) else {
+                continue;
+            };
+            let doc_key = String::from_utf8_lossy(&key[prefix_len..]).into_owned();
+            let entry = decode_value(value)
+                .map(|document| {
+                    deserialize::<RV>(&document)
+                        .map(|document| entry_key(sid, &index, &document, &doc_key))
+                })
+                .transpose()?;
+
+            let previous_entry = match previous.take() {
+                Some((previous_key, entry)) if previous_key == key => entry,
+                _ => None,
+            };
+            if previous_entry != entry {
+                if let Some(previous_entry) = &previous_entry {
+                    self.backfill(previous_entry, version, None)?;
+                }
+                if let Some(entry) = &entry {
+                    self.backfill(entry, version, Some(doc_key.into_bytes()))?;
+                }
+            }
+            previous = Some((key.to_vec(), entry));
+        }
+
+        Ok(())
+    }
+
+    fn backfill(
+        &mut self,
+        key: &[u8],
+        version: Version,
+        doc_key: Option<Vec<u8>>,
+    ) -> Result<(), ExecutionError> {
+        if self.active.contains_key(&version) {
+            self.catalog.store.set(&write_key(version, key), vec![])?;
+        }
+        self.catalog
+            .store
+            .set(&version_key(key, version), encode_value(doc_key))?;
+        Ok(())
+    }
+
+    /// Starts a transaction. Its reads see the documents committed so far,
+    /// and its writes stay invisible to others until it commits.
+    pub fn begin(&mut self) -> Result<Transaction, ExecutionError> {
+        let version = self.next_version;
+        self.catalog
+            .store
+            .set(NEXT_VERSION_KEY, encode_version(version + 1).to_vec())?;
+        self.next_version += 1;
+        self.catalog.store.set(&active_key(version), vec![])?;
+
+        let txn = Transaction::new(version, self.active.keys().copied().collect());
+        self.active.insert(version, txn.snapshot().clone());
+        Ok(txn)
+    }
+
+    pub fn commit(&mut self, txn: &Transaction) -> Result<(), ExecutionError> {
+        let version = txn.version();
+        // The transaction counts as committed once it is no longer marked as
+        // active, the write markers are only needed for a rollback.
+        self.catalog.store.delete(&active_key(version))?;
+        self.active.remove(&version);
+        let prefix_len = write_prefix(version).len();
+        for key in self.keys_with_prefix(&write_prefix(version))? {
+            self.catalog.store.delete(&key)?;
+            self.prune(&key[prefix_len..])?;
+        }
+        Ok(())
+    }
+
+    // Removes the versions of `key` that no snapshot reads anymore. A version
+    // every running transaction sees is also seen by the snapshots taken
+    // later, so it hides the versions before it from all of them. Versions
+    // some transaction cannot see are kept, as that transaction has to fail
+    // if it writes the key. A tombstone left as the only version goes too,
+    // since reading it is the same as reading nothing.
+    fn prune(&mut self, key: &[u8]) -> Result<(), ExecutionError> {
+        let mut versions = vec![];
+        for entry in self.scan_prefix(versions_prefix(key)) {
+            let (versioned_key, value) = entry?;
+            if let Some((doc_key, version)) = split_version_key(&versioned_key)
+                && doc_key == key
+            {
+                versions.push((version, value));
+            }
+        }
+
+        let seen_by_all = |version: &Version| {
+            !self.active.contains_key(version)
+                && self
+                    .active
+                    .values()
+                    .all(|snapshot| snapshot.is_visible(*version))
+        };
+        let Some(latest) = versions
+            .iter()
+            .rposition(|(version, _)| seen_by_all(version))
+        else {
+            return Ok(());
+        };
+        let mut hidden: Vec<Version> = versions[..latest]
+            .iter()
+            .map(|(version, _)| *version)
+            .filter(seen_by_all)
+            .collect();
+
+        let kept = latest - hidden.len();
+        versions.truncate(latest + 1);
+        let (version, value) = versions.pop().unwrap();
+        if kept == 0 && decode_value(value).is_none() {
+            hidden.push(version);
+        }
+
+        for version in hidden {
+            self.catalog.store.delete(&version_key(key, version))?;
+        }
+        Ok(())
+    }
+
+    pub fn rollback(&mut self, txn: &Transaction) -> Result<(), ExecutionError> {
+        self.rollback_version(txn.version())
+    }
+
+    fn rollback_version(&mut self, version: Version) -> Result<(), ExecutionError> {
+        let prefix_len = write_prefix(version).len();
+        for key in self.keys_with_prefix(&write_prefix(version))? {
+            self.catalog
+                .store
+                .delete(&version_key(&key[prefix_len..], version))?;
+            self.catalog.store.delete(&key)?;
+        }
+        // Removed last, so that an interrupted rollback is picked up again
+        // on recovery.
+        self.catalog.store.delete(&active_key(version))?;
+        self.active.remove(&version);
+        Ok(())
+    }
+
+    /// Runs `f` in a transaction of its own, which is committed if `f`
+    /// succeeds and rolled back otherwise.
+    pub fn autocommit<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self, &Transaction) -> Result<T, ExecutionError>,
+    ) -> Result<T, ExecutionError> {
+        let txn = self.begin()?;
+        match f(self, &txn) {
+            Ok(value) => {
+                self.commit(&txn)?;
+                Ok(value)
+            }
+            Err(err) => {
+                self.rollback(&txn)?;
+                Err(err)
+            }
+        }
+    }
+
+    /// A snapshot of everything committed so far. Only the snapshots of
+    /// running transactions keep the versions they see from being pruned,
+    /// so this one is meant for reads made before the engine changes.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.next_version - 1, self.active.keys().copied().collect())
+    }
+
+    fn scan_prefix(&'_ self, prefix: Vec<u8>) -> impl Iterator<Item = IteratorItem> + '_ {
+        self.catalog
+            .store
+            .scan_prefix(&prefix, ScanDirection::Forward)
+    }
+
+    fn keys_with_prefix(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, StoreError> {
+        self.scan_prefix(prefix.to_vec())
+            .map(|res| res.map(|(k, _)| k))
+            .collect()
+    }
+
+    fn read(&self, snapshot: &Snapshot, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut latest = None;
+        for entry in self.scan_prefix(versions_prefix(key)) {
+            let (versioned_key, value) = entry?;
+            if let Some((doc_key, version)) = split_version_key(&versioned_key)
+                && doc_key == key
+                && snapshot.is_visible(version)
+            {
+                latest = Some(value);
+            }
+        }
+        Ok(latest.and_then(decode_value))
+    }
+
+    fn write(
+        &mut self,
+        txn: &Transaction,
+        key: &[u8],
+        document: Option<Vec<u8>>,
+    ) -> Result<(), ExecutionError> {
+        // A version the transaction cannot see was written by someone that
+        // committed after it began, or has not committed yet.
+        for entry in self.scan_prefix(versions_prefix(key)) {
+            let (versioned_key, _) = entry?;
+            if let Some((doc_key, version)) = split_version_key(&versioned_key)
+                && doc_key == key
+                && version != txn.version()
+                && !txn.snapshot().is_visible(version)
+            {
+                return Err(ExecutionError::Engine(EngineError::SerializationFailure));
+            }
+        }
+
+        self.catalog
+            .store
+            .set(&write_key(txn.version(), key), vec![])?;
         self.catalog
             .store
-            .get(&encoded_key)
-            .map(|value| bson::deserialize_from_slice(&value).unwrap())
+            .set(&version_key(key, txn.version()), encode_value(document))?;
+        Ok(())
+    }
+
+    pub fn get<'v>(&self, sid: &StoreId, key: &str) -> Result<Option<RV<'v>>, ExecutionError> {
+        self.get_in(&self.snapshot(), sid, key)
+    }
+    pub fn get_in<'v>(
+        &self,
+        snapshot: &Snapshot,
+        sid: &StoreId,
+        key: &str,
+    ) -> Result<Option<RV<'v>>, ExecutionError> {
+        self.catalog.collection(sid)?;
+        let encoded_key = encode_key(sid, key)?;
+        Ok(self
+            .read(snapshot, &encoded_key)?
+            .map(|value| deserialize(&value))
+            .transpose()?)
     }
     pub fn set(&mut self, sid: &StoreId, key: &str, value: RV<'_>) -> Result<(), ExecutionError> {
+        self.autocommit(|engine, txn| engine.set_in(txn, sid, key, value))
+    }
+    pub fn set_in(
+        &mut self,
+        txn: &Transaction,
+        sid: &StoreId,
+        key: &str,
+        value: RV<'_>,
+    ) -> Result<(), ExecutionError> {
+        let metadata = self.catalog.collection(sid)?.clone();
+        let encoded_key = encode_key(sid, key)?;
         if !value.is_object() {
             return Err(ExecutionError::Engine(EngineError::InvalidValue));
         }
+        if !metadata.primary_key.is_empty()
+            && primary_key(&metadata.primary_key, &value).as_deref() != Some(key)
+        {
+            return Err(violation(constraint_name(
+                "PRIMARY KEY",
+                &metadata.primary_key,
+            )));
+        }
 
-        let encoded_key = encode_key(sid, key);
-        self.catalog
-            .store
-            .set(&encoded_key, bson::serialize_to_vec(&value).unwrap());
+        self.check_unique(txn, sid, &metadata.indexes, key, &value)?;
+        self.update_index_entries(txn, sid, key, Some(&value))?;
+        self.write(
+            txn,
+            &encoded_key,
+            Some(bson::serialize_to_vec(&value).unwrap()),
+        )
+    }
+    pub fn insert(&mut self, sid: &StoreId, value: RV<'_>) -> Result<String, ExecutionError> {
+        self.autocommit(|engine, txn| engine.insert_in(txn, sid, value))
+    }
+    pub fn insert_in(
+        &mut self,
+        txn: &Transaction,
+        sid: &StoreId,
+        value: RV<'_>,
+    ) -> Result<String, ExecutionError> {
+        let fields = &self.catalog.collection(sid)?.primary_key;
+        if fields.is_empty() {
+            let key = generate_key();
+            self.set_in(txn, sid, &key, value)?;
+            return Ok(key);
+        }
 
+        if !value.is_object() {
+            return Err(ExecutionError::Engine(EngineError::InvalidValue));
+        }
+        let constraint = constraint_name("PRIMARY KEY", fields);
+        let Some(key) = primary_key(fields, &value) else {
+            return Err(violation(constraint));
+        };
+        // A concurrent insert of the same key is caught when writing, as
+        // the transaction cannot see the version it wrote.
+        if self.get_in(txn.snapshot(), sid, &key)?.is_some() {
+            return Err(violation(constraint));
+        }
+        self.set_in(txn, sid, &key, value)?;
+        Ok(key)
+    }
+    /// Replaces a document. In collections with a primary key, a document
+    /// whose key fields changed moves to its new key.
+    pub fn update_in(
+        &mut self,
+        txn: &Transaction,
+        sid: &StoreId,
+        key: &str,
+        value: RV<'_>,
+    ) -> Result<(), ExecutionError> {
+        let fields = &self.catalog.collection(sid)?.primary_key;
+        if !fields.is_empty()
+            && let Some(new_key) = primary_key(fields, &value)
+            && new_key != key
+        {
+            self.delete_in(txn, sid, key)?;
+            self.insert_in(txn, sid, value)?;
+            return Ok(());
+        }
+        self.set_in(txn, sid, key, value)
+    }
+    pub fn delete(&mut self, sid: &StoreId, key: &str) -> Result<(), ExecutionError> {
+        self.autocommit(|engine, txn| engine.delete_in(txn, sid, key))
+    }
+    pub fn delete_in(
+        &mut self,
+        txn: &Transaction,
+        sid: &StoreId,
+        key: &str,
+    ) -> Result<(), ExecutionError> {
+        self.catalog.collection(sid)?;
+        let encoded_key = encode_key(sid, key)?;
+        self.update_index_entries(txn, sid, key, None)?;
+        self.write(txn, &encoded_key, None)
+    }
+
+    // Checks that no other live document has the values of `document` in
+    // a unique index. Entries the transaction cannot see might turn out to
+    // be duplicates once their transactions commit, so they are treated as
+    // conflicts.
+    fn check_unique(
+        &self,
+        txn: &Transaction,
+        sid: &StoreId,
+        indexes: &[IndexMetadata],
+        key: &str,
+        document: &RV<'_>,
+    ) -> Result<(), ExecutionError> {
+        for index in indexes.iter().filter(|index| index.unique) {
+            let Some(prefix) = values_prefix(sid, index, document) else {
+                continue;
+            };
+            let own_entry = [prefix.as_slice(), key.as_bytes()].concat();
+
+            for entry in self.scan_prefix(prefix.clone()) {
+                let (versioned_key, _) = entry?;
+                if let Some((entry_key, version)) = split_version_key(&versioned_key)
+                    && entry_key != own_entry
+                    && version != txn.version()
+                    && !txn.snapshot().is_visible(version)
+                {
+                    return Err(ExecutionError::Engine(EngineError::SerializationFailure));
+                }
+            }
+            for entry in VisibleScan::new(self.scan_prefix(prefix), txn.snapshot().clone()) {
+                let (entry_key, _) = entry?;
+                if entry_key != own_entry {
+                    return Err(violation(constraint_name("UNIQUE", &index.fields)));
+                }
+            }
+        }
         Ok(())
     }
-    pub fn delete(&mut self, sid: &StoreId, key: &str) {
-        let encoded_key = encode_key(sid, key);
-        self.catalog.store.delete(&encoded_key);
+
+    // Index entries are written in the transaction of the document, so they
+    // are visible to the same snapshots.
+    fn update_index_entries(
+        &mut self,
+        txn: &Transaction,
+        sid: &StoreId,
+        key: &str,
+        document: Option<&RV<'_>>,
+    ) -> Result<(), ExecutionError> {
+        let indexes = self.catalog.collection(sid)?.indexes.clone();
+        if indexes.is_empty() {
+            return Ok(());
+        }
+
+        let previous = self.get_in(txn.snapshot(), sid, key)?;
+        for index in &indexes {
+            let old_entry = previous
+                .as_ref()
+                .map(|document| entry_key(sid, index, document, key));
+            let new_entry = document.map(|document| entry_key(sid, index, document, key));
+            if old_entry == new_entry {
+                continue;
+            }
+            if let Some(old_entry) = old_entry {
+                self.write(txn, &old_entry, None)?;
+            }
+            if let Some(new_entry) = new_entry {
+                self.write(txn, &new_entry, Some(key.as_bytes().to_vec()))?;
+            }
+        }
+        Ok(())
     }
     pub fn scan(
         &'_ self,
         sid: &StoreId,
-    ) -> Filter<MemoryScanIterator<'_>, impl FnMut(&IteratorItem) -> bool + '_> {
-        let prefix: Vec<u8> = sid.0.clone().into_bytes();
-        self.catalog.store.scan().filter(move |res| {
-            if let Ok((k, _)) = res {
-                k.starts_with(&prefix)
-            } else {
-                false
+    ) -> Result<impl Iterator<Item = IteratorItem> + '_, ExecutionError> {
+        self.scan_in(self.snapshot(), sid)
+    }
+    pub fn scan_in(
+        &'_ self,
+        snapshot: Snapshot,
+        sid: &StoreId,
+    ) -> Result<impl Iterator<Item = IteratorItem> + '_, ExecutionError> {
+        self.catalog.collection(sid)?;
+        Ok(VisibleScan::new(self.scan_prefix(sid.prefix()), snapshot))
+    }
+    /// Reads the documents whose first indexed field falls in any of the
+    /// ranges, in the order of the index.
+    pub fn scan_index_in<'v>(
+        &self,
+        snapshot: Snapshot,
+        sid: &StoreId,
+        index: &str,
+        ranges: &[KeyRange],
+    ) -> Result<Vec<(String, RV<'v>)>, ExecutionError> {
+        if self.catalog.collection(sid)?.index(index).is_none() {
+            return Err(ExecutionError::Engine(EngineError::IndexNotFound {
+                index: index.to_string(),
+                collection: sid.to_string(),
+            }));
+        }
+
+        // Ranges are read in the order of their lower bounds, so that the
+        // documents come out in the order of the index.
+        let prefix = index_prefix(sid, index);
+        let mut bounds: Vec<KeyBounds> = ranges.iter().map(|range| range.bounds(&prefix)).collect();
+        bounds.sort_by(|(a, _), (b, _)| compare_lower_bounds(a, b));
+
+        let mut seen = HashSet::new();
+        let mut documents = vec![];
+        for bounds in bounds {
+            let entries = self.catalog.store.range(bounds, ScanDirection::Forward);
+            for entry in VisibleScan::new(entries, snapshot.clone()) {
+                let (_, doc_key) = entry?;
+                let doc_key = String::from_utf8_lossy(&doc_key).into_owned();
+                // Overlapping ranges, like the ones of `IN [1, 1]`, must not
+                // return a document twice.
+                if !seen.insert(doc_key.clone()) {
+                    continue;
+                }
+                if let Some(document) = self.get_in(&snapshot, sid, &doc_key)? {
+                    documents.push((doc_key, document));
+                }
             }
-        })
+        }
+        Ok(documents)
+    }
+    pub fn scan_documents<'v>(
+        &'_ self,
+        sid: &StoreId,
+    ) -> Result<impl Iterator<Item = Result<(String, RV<'v>), StoreError>> + '_, ExecutionError>
+    {
+        self.scan_documents_in(self.snapshot(), sid)
+    }
+    pub fn scan_documents_in<'v>(
+        &'_ self,
+        snapshot: Snapshot,
+        sid: &StoreId,
+    ) -> Result<impl Iterator<Item = Result<(String, RV<'v>), StoreError>> + '_, ExecutionError>
+    {
+        let prefix_len = sid.prefix().len();
+        Ok(self.scan_in(snapshot, sid)?.map(move |res| {
+            res.and_then(|(key, value)| {
+                Ok((
+                    String::from_utf8_lossy(&key[prefix_len..]).into_owned(),
+                    deserialize(&value)?,
+                ))
+            })
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+    };
 
     use super::*;
     use crate::{
-        engine::error::EngineError, execution::error::ExecutionError, value::object::RVObject,
+        engine::error::EngineError, execution::error::ExecutionError, store::disk::DiskStore,
+        value::object::RVObject,
     };
 
     fn make_engine() -> Engine<MemoryStore> {
         Engine::new()
     }
 
-    fn make_sid(name: &str) -> StoreId {
-        StoreId(name.to_string())
+    fn make_collection<S: for<'a> Store<'a>>(engine: &mut Engine<S>, name: &str) -> StoreId {
+        let sid = StoreId::new(None, name);
+        engine.create_collection(&sid).unwrap();
+        sid
     }
 
     fn make_object(fields: &[(&str, RV<'static>)]) -> RV<'static> {
@@ -110,19 +760,19 @@ mod tests {
 
     #[test]
     fn test_get_missing_key_returns_none() {
-        let engine = make_engine();
-        let sid = make_sid("ns:");
-        assert!(engine.get(&sid, "nonexistent").is_none());
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        assert!(engine.get(&sid, "nonexistent").unwrap().is_none());
     }
 
     #[test]
     fn test_set_and_get_returns_object_with_correct_fields() {
         let mut engine = make_engine();
-        let sid = make_sid("ns:");
+        let sid = make_collection(&mut engine, "ns");
         engine
             .set(&sid, "doc1", make_object(&[("x", RV::Int32(42))]))
             .unwrap();
-        let rv = engine.get(&sid, "doc1").unwrap();
+        let rv = engine.get(&sid, "doc1").unwrap().unwrap();
         assert!(rv.is_object());
         let obj = rv.extract_object().unwrap();
         assert!(matches!(obj.get("x"), Some(RV::Int32(42))));
@@ -131,7 +781,7 @@ mod tests {
     #[test]
     fn test_set_non_object_returns_invalid_value_error() {
         let mut engine = make_engine();
-        let sid = make_sid("ns:");
+        let sid = make_collection(&mut engine, "ns");
         let non_objects: &[RV<'static>] = &[
             RV::Null,
             RV::Bool(false),
@@ -148,22 +798,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_keys_with_null_characters_are_rejected() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        let invalid = Err(ExecutionError::Engine(EngineError::InvalidKey));
+
+        assert_eq!(
+            engine.set(&sid, "a\0b", make_object(&[("v", RV::Int32(1))])),
+            invalid
+        );
+        assert_eq!(engine.get(&sid, "a\0b").map(|_| ()), invalid);
+        assert_eq!(engine.delete(&sid, "a\0b"), invalid);
+        assert_eq!(engine.scan(&sid).unwrap().count(), 0);
+    }
+
     #[test]
     fn test_delete_removes_document() {
         let mut engine = make_engine();
-        let sid = make_sid("ns:");
+        let sid = make_collection(&mut engine, "ns");
         engine
             .set(&sid, "doc1", make_object(&[("flag", RV::Bool(true))]))
             .unwrap();
-        assert!(engine.get(&sid, "doc1").is_some());
-        engine.delete(&sid, "doc1");
-        assert!(engine.get(&sid, "doc1").is_none());
+        assert!(engine.get(&sid, "doc1").unwrap().is_some());
+        engine.delete(&sid, "doc1").unwrap();
+        assert!(engine.get(&sid, "doc1").unwrap().is_none());
     }
 
     #[test]
     fn test_scan_returns_docs_in_sorted_key_order() {
         let mut engine = make_engine();
-        let sid = make_sid("ns:");
+        let sid = make_collection(&mut engine, "ns");
         // Insert in non-alphabetical order; BTreeMap will return them sorted
         for key in ["gamma", "alpha", "beta"] {
             engine
@@ -174,9 +839,10 @@ mod tests {
                 )
                 .unwrap();
         }
-        let prefix_len = sid.0.len();
+        let prefix_len = sid.prefix().len();
         let keys: Vec<String> = engine
             .scan(&sid)
+            .unwrap()
             .map(|r| {
                 let (k, _) = r.unwrap();
                 String::from_utf8(k[prefix_len..].to_vec()).unwrap()
@@ -188,8 +854,8 @@ mod tests {
     #[test]
     fn test_scan_does_not_return_other_namespace_docs() {
         let mut engine = make_engine();
-        let sid1 = make_sid("ns1:");
-        let sid2 = make_sid("ns2:");
+        let sid1 = make_collection(&mut engine, "ns1");
+        let sid2 = make_collection(&mut engine, "ns2");
         engine
             .set(&sid1, "a", make_object(&[("id", RV::Int32(1))]))
             .unwrap();
@@ -199,7 +865,691 @@ mod tests {
         engine
             .set(&sid2, "c", make_object(&[("id", RV::Int32(3))]))
             .unwrap();
-        assert_eq!(engine.scan(&sid1).count(), 2);
-        assert_eq!(engine.scan(&sid2).count(), 1);
+        assert_eq!(engine.scan(&sid1).unwrap().count(), 2);
+        assert_eq!(engine.scan(&sid2).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_scan_documents_decodes_keys_and_documents() {
+        let mut engine = make_engine();
+        let sid = StoreId::new(Some("db"), "users");
+        engine.create_collection(&sid).unwrap();
+        engine
+            .set(&sid, "a", make_object(&[("id", RV::Int32(1))]))
+            .unwrap();
+        engine
+            .set(&sid, "b", make_object(&[("id", RV::Int32(2))]))
+            .unwrap();
+        let ids: Vec<RV> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().1.extract_object().unwrap().get("id").unwrap())
+            .collect();
+        assert!(matches!(ids.as_slice(), [RV::Int32(1), RV::Int32(2)]));
+        let keys: Vec<String> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_store_id_defaults_namespace() {
+        assert_eq!(
+            StoreId::new(None, "users").prefix(),
+            StoreId::new(Some("default"), "users").prefix()
+        );
+        assert_ne!(
+            StoreId::new(None, "users").prefix(),
+            StoreId::new(Some("db"), "users").prefix()
+        );
+    }
+
+    #[test]
+    fn test_store_id_prefixes_do_not_overlap() {
+        let prefixes = [
+            StoreId::new(Some("ns1"), "users").prefix(),
+            StoreId::new(Some("ns10"), "users").prefix(),
+            StoreId::new(Some("ns1"), "users1").prefix(),
+            StoreId::new(Some("ns"), "1users").prefix(),
+        ];
+        for a in &prefixes {
+            for b in &prefixes {
+                assert!(a == b || !a.starts_with(b), "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_generates_distinct_ordered_keys() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        let first = engine
+            .insert(&sid, make_object(&[("id", RV::Int32(1))]))
+            .unwrap();
+        let second = engine
+            .insert(&sid, make_object(&[("id", RV::Int32(2))]))
+            .unwrap();
+        assert_ne!(first, second);
+        assert!(engine.get(&sid, &first).unwrap().is_some());
+        assert!(engine.get(&sid, &second).unwrap().is_some());
+        let ids: Vec<RV> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().1.extract_object().unwrap().get("id").unwrap())
+            .collect();
+        assert!(matches!(ids.as_slice(), [RV::Int32(1), RV::Int32(2)]));
+    }
+
+    #[test]
+    fn test_analyze_only_sees_committed_documents() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        for id in [1, 2, 2] {
+            engine
+                .insert(&sid, make_object(&[("id", RV::Int32(id))]))
+                .unwrap();
+        }
+        let txn = engine.begin().unwrap();
+        engine
+            .insert_in(&txn, &sid, make_object(&[("id", RV::Int32(3))]))
+            .unwrap();
+
+        engine.analyze(&sid).unwrap();
+
+        let statistics = engine
+            .catalog()
+            .collection(&sid)
+            .unwrap()
+            .statistics
+            .clone();
+        let statistics = statistics.unwrap();
+        assert_eq!(statistics.rows, 3);
+        assert_eq!(statistics.fields["id"].distinct, 2);
+    }
+
+    #[test]
+    fn test_insert_non_object_returns_invalid_value_error() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        assert_eq!(
+            engine.insert(&sid, RV::Int32(1)),
+            Err(ExecutionError::Engine(EngineError::InvalidValue))
+        );
+        assert_eq!(engine.scan(&sid).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_disk_backed_engine_keeps_documents_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let sid = StoreId::new(None, "users");
+
+        let key = {
+            let mut engine = Engine::with_store(DiskStore::open(dir.path()).unwrap()).unwrap();
+            engine.create_collection(&sid).unwrap();
+            let key = engine
+                .insert(&sid, make_object(&[("id", RV::Int32(1))]))
+                .unwrap();
+            engine
+                .insert(&sid, make_object(&[("id", RV::Int32(2))]))
+                .unwrap();
+            engine.delete(&sid, &key).unwrap();
+            key
+        };
+
+        let engine = Engine::with_store(DiskStore::open(dir.path()).unwrap()).unwrap();
+        assert!(engine.get(&sid, &key).unwrap().is_none());
+        let ids: Vec<RV> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().1.extract_object().unwrap().get("id").unwrap())
+            .collect();
+        assert!(matches!(ids.as_slice(), [RV::Int32(2)]));
+    }
+
+    // Fails every write while `failing` is set.
+    struct FaultyStore {
+        inner: MemoryStore,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl FaultyStore {
+        fn check(&self) -> Result<(), StoreError> {
+            if self.failing.load(AtomicOrdering::SeqCst) {
+                return Err(StoreError::Io {
+                    message: "injected fault".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a> Store<'a> for FaultyStore {
+        type ScanIterator = <MemoryStore as Store<'a>>::ScanIterator;
+
+        fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+            self.inner.get(key)
+        }
+
+        fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError> {
+            self.check()?;
+            self.inner.set(key, value)
+        }
+
+        fn delete(&mut self, key: &[u8]) -> Result<(), StoreError> {
+            self.check()?;
+            self.inner.delete(key)
+        }
+
+        fn range(&'a self, bounds: KeyBounds, direction: ScanDirection) -> Self::ScanIterator {
+            self.inner.range(bounds, direction)
+        }
+    }
+
+    #[test]
+    fn test_boxed_engine_runs_on_a_custom_store() {
+        let failing = Arc::new(AtomicBool::new(false));
+        let mut engine = Engine::boxed(FaultyStore {
+            inner: MemoryStore::new(),
+            failing: failing.clone(),
+        })
+        .unwrap();
+        let sid = make_collection(&mut engine, "users");
+
+        failing.store(true, AtomicOrdering::SeqCst);
+        let err = engine
+            .insert(&sid, make_object(&[("id", RV::Int32(1))]))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::Store(StoreError::Io { ref message }) if message == "injected fault"
+        ));
+
+        failing.store(false, AtomicOrdering::SeqCst);
+        engine
+            .insert(&sid, make_object(&[("id", RV::Int32(2))]))
+            .unwrap();
+        let ids: Vec<RV> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().1.extract_object().unwrap().get("id").unwrap())
+            .collect();
+        assert!(matches!(ids.as_slice(), [RV::Int32(2)]));
+    }
+
+    #[test]
+    fn test_damaged_documents_fail_the_read() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine
+            .set(&sid, "doc", make_object(&[("v", RV::Int32(1))]))
+            .unwrap();
+
+        let versions = engine.keys_with_prefix(&sid.prefix()).unwrap();
+        engine
+            .catalog
+            .store
+            .set(&versions[0], encode_value(Some(b"garbage".to_vec())))
+            .unwrap();
+
+        let is_invalid = |result: Result<_, ExecutionError>| {
+            matches!(
+                result,
+                Err(ExecutionError::Store(StoreError::InvalidData { .. }))
+            )
+        };
+        assert!(is_invalid(engine.get(&sid, "doc").map(|_| ())));
+        assert!(is_invalid(
+            engine
+                .scan_documents(&sid)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .map(|_| ())
+                .map_err(ExecutionError::from)
+        ));
+        assert!(is_invalid(engine.create_index(&sid, by_v())));
+    }
+
+    #[test]
+    fn test_uncommitted_writes_are_invisible_to_others() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Int32(1))]))
+            .unwrap();
+
+        let writer = engine.begin().unwrap();
+        engine
+            .set_in(&writer, &sid, "a", make_object(&[("v", RV::Int32(2))]))
+            .unwrap();
+        engine
+            .set_in(&writer, &sid, "b", make_object(&[("v", RV::Int32(3))]))
+            .unwrap();
+
+        let v = |rv: Option<RV<'static>>| rv.unwrap().extract_object().unwrap().get("v");
+        assert!(matches!(
+            v(engine.get(&sid, "a").unwrap()),
+            Some(RV::Int32(1))
+        ));
+        assert!(engine.get(&sid, "b").unwrap().is_none());
+        assert!(matches!(
+            v(engine.get_in(writer.snapshot(), &sid, "a").unwrap()),
+            Some(RV::Int32(2))
+        ));
+
+        engine.commit(&writer).unwrap();
+        assert!(matches!(
+            v(engine.get(&sid, "a").unwrap()),
+            Some(RV::Int32(2))
+        ));
+        assert_eq!(engine.scan(&sid).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_does_not_see_later_commits() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+
+        let reader = engine.begin().unwrap();
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Int32(1))]))
+            .unwrap();
+        engine.delete(&sid, "a").unwrap();
+        engine
+            .set(&sid, "b", make_object(&[("v", RV::Int32(2))]))
+            .unwrap();
+
+        assert_eq!(
+            engine
+                .scan_in(reader.snapshot().clone(), &sid)
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(engine.scan(&sid).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_conflicting_writers_fail_with_serialization_error() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Int32(1))]))
+            .unwrap();
+
+        let first = engine.begin().unwrap();
+        let second = engine.begin().unwrap();
+        engine.delete_in(&first, &sid, "a").unwrap();
+        assert_eq!(
+            engine.set_in(&second, &sid, "a", make_object(&[("v", RV::Int32(2))])),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+
+        // The conflict outlives the commit of the first writer, since the
+        // second one began before it.
+        engine.commit(&first).unwrap();
+        assert_eq!(
+            engine.delete_in(&second, &sid, "a"),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+        engine.rollback(&second).unwrap();
+
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Int32(3))]))
+            .unwrap();
+        assert!(engine.get(&sid, "a").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_rollback_removes_written_versions() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+
+        let txn = engine.begin().unwrap();
+        engine
+            .insert_in(&txn, &sid, make_object(&[("v", RV::Int32(1))]))
+            .unwrap();
+        engine.rollback(&txn).unwrap();
+
+        assert_eq!(engine.scan(&sid).unwrap().count(), 0);
+        // Only the version counter and the collection itself are left.
+        assert_eq!(engine.catalog.store.scan().count(), 2);
+    }
+
+    #[test]
+    fn test_reopen_rolls_back_unfinished_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let sid = StoreId::new(None, "users");
+
+        {
+            let mut engine = Engine::with_store(DiskStore::open(dir.path()).unwrap()).unwrap();
+            engine.create_collection(&sid).unwrap();
+            let committed = engine.begin().unwrap();
+            engine
+                .set_in(&committed, &sid, "a", make_object(&[("id", RV::Int32(1))]))
+                .unwrap();
+            engine.commit(&committed).unwrap();
+
+            let unfinished = engine.begin().unwrap();
+            engine
+                .set_in(&unfinished, &sid, "b", make_object(&[("id", RV::Int32(2))]))
+                .unwrap();
+            engine.delete_in(&unfinished, &sid, "a").unwrap();
+        }
+
+        let mut engine = Engine::with_store(DiskStore::open(dir.path()).unwrap()).unwrap();
+        let keys: Vec<String> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(keys, vec!["a"]);
+
+        // Versions keep growing across restarts.
+        let txn = engine.begin().unwrap();
+        assert_eq!(txn.version(), 3);
+    }
+
+    #[test]
+    fn test_unknown_collection_is_rejected() {
+        let mut engine = make_engine();
+        let sid = StoreId::new(Some("db"), "users");
+        let not_found = Err(ExecutionError::Engine(EngineError::CollectionNotFound {
+            collection: "db.users".to_string(),
+        }));
+
+        assert_eq!(
+            engine.insert(&sid, make_object(&[("id", RV::Int32(1))])),
+            not_found.clone().map(|_: ()| String::new())
+        );
+        assert!(engine.scan(&sid).is_err());
+        assert!(engine.get(&sid, "a").is_err());
+        assert_eq!(engine.delete(&sid, "a"), not_found);
+    }
+
+    fn index_keys<S: for<'a> Store<'a>>(
+        engine: &Engine<S>,
+        snapshot: &Snapshot,
+        sid: &StoreId,
+        value: i32,
+    ) -> Vec<String> {
+        let range = KeyRange::point(&RV::Double(value as f64)).unwrap();
+        engine
+            .scan_index_in(snapshot.clone(), sid, "by_v", &[range])
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    fn by_v() -> IndexMetadata {
+        IndexMetadata {
+            name: "by_v".to_string(),
+            fields: vec![vec!["v".to_string()]],
+            unique: false,
+        }
+    }
+
+    fn stored_keys<S: for<'a> Store<'a>>(engine: &Engine<S>) -> usize {
+        engine.catalog.store.scan().count()
+    }
+
+    #[test]
+    fn test_repeated_updates_do_not_grow_the_store() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine.create_index(&sid, by_v()).unwrap();
+        // The first transaction stores the next version to hand out.
+        engine.autocommit(|_, _| Ok(())).unwrap();
+        let empty = stored_keys(&engine);
+
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(0.0))]))
+            .unwrap();
+        let stored = stored_keys(&engine);
+        for i in 1..20 {
+            engine
+                .set(&sid, "a", make_object(&[("v", RV::Double(i as f64))]))
+                .unwrap();
+            assert_eq!(stored_keys(&engine), stored);
+        }
+        assert_eq!(index_keys(&engine, &engine.snapshot(), &sid, 19), vec!["a"]);
+
+        engine.delete(&sid, "a").unwrap();
+        assert_eq!(stored_keys(&engine), empty);
+    }
+
+    #[test]
+    fn test_running_transactions_keep_the_versions_they_see() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(0.0))]))
+            .unwrap();
+        let stored = stored_keys(&engine);
+
+        let reader = engine.begin().unwrap();
+        for i in 1..5 {
+            engine
+                .set(&sid, "a", make_object(&[("v", RV::Double(i as f64))]))
+                .unwrap();
+        }
+        let document = engine
+            .get_in(reader.snapshot(), &sid, "a")
+            .unwrap()
+            .unwrap();
+        let document = document.extract_object().unwrap();
+        assert!(matches!(document.get("v"), Some(RV::Double(0.0))));
+        assert!(stored_keys(&engine) > stored);
+
+        // The versions the reader cannot see still conflict with its writes.
+        assert_eq!(
+            engine.delete_in(&reader, &sid, "a"),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+        engine.rollback(&reader).unwrap();
+
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(5.0))]))
+            .unwrap();
+        assert_eq!(stored_keys(&engine), stored);
+    }
+
+    #[test]
+    fn test_index_entries_follow_snapshots() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine.create_index(&sid, by_v()).unwrap();
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(1.0))]))
+            .unwrap();
+
+        let reader = engine.begin().unwrap();
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(2.0))]))
+            .unwrap();
+        engine
+            .set(&sid, "b", make_object(&[("v", RV::Double(1.0))]))
+            .unwrap();
+
+        assert_eq!(index_keys(&engine, reader.snapshot(), &sid, 1), vec!["a"]);
+        assert_eq!(index_keys(&engine, &engine.snapshot(), &sid, 1), vec!["b"]);
+        assert_eq!(index_keys(&engine, &engine.snapshot(), &sid, 2), vec!["a"]);
+
+        engine.delete(&sid, "a").unwrap();
+        assert!(index_keys(&engine, &engine.snapshot(), &sid, 2).is_empty());
+    }
+
+    #[test]
+    fn test_create_index_covers_transactions_in_progress() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "ns");
+        engine
+            .set(&sid, "a", make_object(&[("v", RV::Double(1.0))]))
+            .unwrap();
+
+        let writer = engine.begin().unwrap();
+        engine
+            .set_in(&writer, &sid, "a", make_object(&[("v", RV::Double(2.0))]))
+            .unwrap();
+        engine.create_index(&sid, by_v()).unwrap();
+
+        assert_eq!(index_keys(&engine, &engine.snapshot(), &sid, 1), vec!["a"]);
+        assert_eq!(
+            index_keys(&engine, writer.snapshot(), &sid, 1),
+            Vec::<String>::new()
+        );
+        assert_eq!(index_keys(&engine, writer.snapshot(), &sid, 2), vec!["a"]);
+
+        // The entries written for the transaction go away with it.
+        engine.rollback(&writer).unwrap();
+        assert!(index_keys(&engine, writer.snapshot(), &sid, 2).is_empty());
+        assert_eq!(index_keys(&engine, writer.snapshot(), &sid, 1), vec!["a"]);
+    }
+
+    fn keyed_collection(engine: &mut Engine<MemoryStore>) -> StoreId {
+        let sid = StoreId::new(None, "users");
+        let mut metadata = CollectionMetadata::from(&sid);
+        metadata.primary_key = vec![vec!["id".to_string()]];
+        metadata.indexes.push(IndexMetadata {
+            name: "users_v_key".to_string(),
+            fields: vec![vec!["v".to_string()]],
+            unique: true,
+        });
+        engine.create_collection_with(metadata).unwrap();
+        sid
+    }
+
+    fn id_key(id: f64) -> String {
+        to_hex(&encode_tuple(&[RV::Double(id)]).unwrap())
+    }
+
+    fn is_violation(result: Result<impl std::fmt::Debug, ExecutionError>) -> bool {
+        matches!(
+            result,
+            Err(ExecutionError::Engine(
+                EngineError::ConstraintViolation { .. }
+            ))
+        )
+    }
+
+    #[test]
+    fn test_primary_key_is_the_document_key() {
+        let mut engine = make_engine();
+        let sid = keyed_collection(&mut engine);
+
+        let key = engine
+            .insert(&sid, make_object(&[("id", RV::Double(1.0))]))
+            .unwrap();
+        assert_eq!(key, id_key(1.0));
+        assert!(is_violation(
+            engine.insert(&sid, make_object(&[("id", RV::Double(1.0))]))
+        ));
+        assert!(is_violation(engine.insert(&sid, make_object(&[]))));
+
+        // Keys sort like the values do.
+        engine
+            .insert(&sid, make_object(&[("id", RV::Double(-10.0))]))
+            .unwrap();
+        engine
+            .insert(&sid, make_object(&[("id", RV::Double(10.0))]))
+            .unwrap();
+        let ids: Vec<_> = engine
+            .scan_documents(&sid)
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .1
+                    .extract_object()
+                    .unwrap()
+                    .get("id")
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![RV::Double(-10.0), RV::Double(1.0), RV::Double(10.0)]
+        );
+
+        // The key fields of a document can only change through an update,
+        // which moves the document.
+        assert!(is_violation(engine.set(
+            &sid,
+            &id_key(1.0),
+            make_object(&[("id", RV::Double(2.0))])
+        )));
+        let txn = engine.begin().unwrap();
+        engine
+            .update_in(
+                &txn,
+                &sid,
+                &id_key(1.0),
+                make_object(&[("id", RV::Double(2.0))]),
+            )
+            .unwrap();
+        engine.commit(&txn).unwrap();
+        assert!(engine.get(&sid, &id_key(1.0)).unwrap().is_none());
+        assert!(engine.get(&sid, &id_key(2.0)).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_concurrent_duplicates_conflict() {
+        let mut engine = make_engine();
+        let sid = keyed_collection(&mut engine);
+
+        let first = engine.begin().unwrap();
+        let second = engine.begin().unwrap();
+        engine
+            .insert_in(&first, &sid, make_object(&[("id", RV::Double(1.0))]))
+            .unwrap();
+        assert_eq!(
+            engine.insert_in(&second, &sid, make_object(&[("id", RV::Double(1.0))])),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+
+        let v = || RV::Str(Arc::new("v".to_string()));
+        engine
+            .insert_in(
+                &first,
+                &sid,
+                make_object(&[("id", RV::Double(2.0)), ("v", v())]),
+            )
+            .unwrap();
+        assert_eq!(
+            engine.insert_in(
+                &second,
+                &sid,
+                make_object(&[("id", RV::Double(3.0)), ("v", v())])
+            ),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+        engine.rollback(&second).unwrap();
+        engine.commit(&first).unwrap();
+
+        assert!(is_violation(engine.insert(
+            &sid,
+            make_object(&[("id", RV::Double(3.0)), ("v", v())])
+        )));
+        engine.delete(&sid, &id_key(2.0)).unwrap();
+        engine
+            .insert(&sid, make_object(&[("id", RV::Double(3.0)), ("v", v())]))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_drop_collection_removes_documents() {
+        let mut engine = make_engine();
+        let sid = make_collection(&mut engine, "users");
+        engine
+            .insert(&sid, make_object(&[("id", RV::Int32(1))]))
+            .unwrap();
+
+        engine.drop_collection(&sid).unwrap();
+        assert!(engine.scan(&sid).is_err());
+
+        engine.create_collection(&sid).unwrap();
+        assert_eq!(engine.scan(&sid).unwrap().count(), 0);
     }
 }
@@ -0,0 +1,224 @@
+use std::{collections::BTreeSet, iter::Peekable};
+
+use crate::store::IteratorItem;
+
+/// Every transaction is identified by the version it writes with. Versions
+/// are handed out in increasing order, so a larger version always belongs to
+/// a transaction that began later.
+pub type Version = u64;
+
+// Bookkeeping keys. Collection keys always start with a namespace, so the
+// leading zero byte keeps these apart from documents.
+pub(super) const NEXT_VERSION_KEY: &[u8] = b"\0mvcc:next";
+pub(super) const ACTIVE_PREFIX: &[u8] = b"\0mvcc:active:";
+pub(super) const WRITE_PREFIX: &[u8] = b"\0mvcc:write:";
+
+// Separates a document key from its version. The engine rejects document
+// keys that contain it.
+const VERSION_SEPARATOR: u8 = 0;
+const VERSION_LEN: usize = 8;
+
+const TOMBSTONE: u8 = 0;
+const DOCUMENT: u8 = 1;
+
+/// The set of versions a reader is allowed to see.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    version: Version,
+    // Transactions that were still running when the snapshot was taken.
+    invisible: BTreeSet<Version>,
+}
+
+impl Snapshot {
+    pub(super) fn new(version: Version, invisible: BTreeSet<Version>) -> Self {
+        Snapshot { version, invisible }
+    }
+
+    pub fn is_visible(&self, version: Version) -> bool {
+        version <= self.version && !self.invisible.contains(&version)
+    }
+}
+
+/// A running transaction. It sees the versions committed before it began,
+/// along with its own writes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    snapshot: Snapshot,
+}
+
+impl Transaction {
+    pub(super) fn new(version: Version, active: BTreeSet<Version>) -> Self {
+        Transaction {
+            snapshot: Snapshot::new(version, active),
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.snapshot.version
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+}
+
+pub(super) fn encode_version(version: Version) -> [u8; VERSION_LEN] {
+    version.to_be_bytes()
+}
+
+pub(super) fn decode_version(bytes: &[u8]) -> Version {
+    Version::from_be_bytes(bytes.try_into().unwrap())
+}
+
+pub(super) fn active_key(version: Version) -> Vec<u8> {
+    [ACTIVE_PREFIX, &encode_version(version)].concat()
+}
+
+pub(super) fn write_prefix(version: Version) -> Vec<u8> {
+    [WRITE_PREFIX, &encode_version(version)].concat()
+}
+
+pub(super) fn write_key(version: Version, key: &[u8]) -> Vec<u8> {
+    [&write_prefix(version), key].concat()
+}
+
+/// The prefix shared by all versions of `key`.
+pub(super) fn versions_prefix(key: &[u8]) -> Vec<u8> {
+    [key, &[VERSION_SEPARATOR]].concat()
+}
+
+pub(super) fn version_key(key: &[u8], version: Version) -> Vec<u8> {
+    [&versions_prefix(key), &encode_version(version)[..]].concat()
+}
+
+/// Splits a versioned key into the document key and the version.
+pub(super) fn split_version_key(key: &[u8]) -> Option<(&[u8], Version)> {
+    let split = key.len().checked_sub(VERSION_LEN + 1)?;
+    if key[split] != VERSION_SEPARATOR {
+        return None;
+    }
+    Some((&key[..split], decode_version(&key[split + 1..])))
+}
+
+/// Deletions are stored as tombstones, so that readers of older snapshots
+/// still find the previous version.
+pub(super) fn encode_value(document: Option<Vec<u8>>) -> Vec<u8> {
+    match document {
+        Some(document) => [&[DOCUMENT], &document[..]].concat(),
+        None => vec![TOMBSTONE],
+    }
+}
+
+pub(super) fn decode_value(mut value: Vec<u8>) -> Option<Vec<u8>> {
+    if value.first() == Some(&DOCUMENT) {
+        Some(value.split_off(1))
+    } else {
+        None
+    }
+}
+
+/// Turns a scan over versioned keys into a scan over the documents visible
+/// to a snapshot. The versions of a document are adjacent and ordered from
+/// the oldest to the newest, so the last visible one wins.
+pub(super) struct VisibleScan<I: Iterator<Item = IteratorItem>> {
+    versions: Peekable<I>,
+    snapshot: Snapshot,
+}
+
+impl<I: Iterator<Item = IteratorItem>> VisibleScan<I> {
+    pub(super) fn new(versions: I, snapshot: Snapshot) -> Self {
+        VisibleScan {
+            versions: versions.peekable(),
+            snapshot,
+        }
+    }
+}
+
+impl<I: Iterator<Item = IteratorItem>> Iterator for VisibleScan<I> {
+    type Item = IteratorItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = match self.versions.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err)),
+            };
+            let Some((doc_key, version)) = split_version_key(&key) else {
+                continue;
+            };
+
+            let mut latest = self.snapshot.is_visible(version).then_some(value);
+            while let Some(Ok((next_key, _))) = self.versions.peek() {
+                match split_version_key(next_key) {
+                    Some((next_doc_key, version)) if next_doc_key == doc_key => {
+                        let visible = self.snapshot.is_visible(version);
+                        let (_, value) = self.versions.next().unwrap().unwrap();
+                        if visible {
+                            latest = Some(value);
+                        }
+                    }
+                    _ => break,
+                }
+            }
+
+            if let Some(document) = latest.and_then(decode_value) {
+                return Some(Ok((doc_key.to_vec(), document)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_hides_later_and_running_transactions() {
+        let snapshot = Snapshot::new(5, BTreeSet::from([3]));
+        assert!(snapshot.is_visible(1));
+        assert!(!snapshot.is_visible(3));
+        assert!(snapshot.is_visible(5));
+        assert!(!snapshot.is_visible(6));
+    }
+
+    #[test]
+    fn test_version_keys_round_trip_and_sort_by_version() {
+        let older = version_key(b"ns:doc", 2);
+        let newer = version_key(b"ns:doc", 256);
+        assert!(older < newer);
+        assert!(version_key(b"ns:doc", Version::MAX) < version_key(b"ns:doc1", 0));
+        assert_eq!(split_version_key(&newer), Some((&b"ns:doc"[..], 256)));
+        assert_eq!(split_version_key(b"short"), None);
+    }
+
+    #[test]
+    fn test_visible_scan_picks_latest_visible_version() {
+        let entries = vec![
+            (version_key(b"a", 1), encode_value(Some(b"a1".to_vec()))),
+            (version_key(b"a", 3), encode_value(Some(b"a3".to_vec()))),
+            (version_key(b"b", 1), encode_value(Some(b"b1".to_vec()))),
+            (version_key(b"b", 2), encode_value(None)),
+            (version_key(b"c", 4), encode_value(Some(b"c4".to_vec()))),
+        ];
+        let scan = |snapshot| {
+            VisibleScan::new(entries.clone().into_iter().map(Ok), snapshot)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            scan(Snapshot::new(4, BTreeSet::from([3]))),
+            vec![
+                (b"a".to_vec(), b"a1".to_vec()),
+                (b"c".to_vec(), b"c4".to_vec())
+            ]
+        );
+        assert_eq!(
+            scan(Snapshot::new(1, BTreeSet::new())),
+            vec![
+                (b"a".to_vec(), b"a1".to_vec()),
+                (b"b".to_vec(), b"b1".to_vec())
+            ]
+        );
+    }
+}
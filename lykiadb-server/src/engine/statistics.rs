@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::encoding::encode_scalar, value::RV};
+
+// Histograms have at most this many buckets.
+const BUCKETS: usize = 16;
+
+/// What `ANALYZE` learned about the documents of a collection, for the
+/// planner to estimate how many rows its plans return.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionStatistics {
+    pub rows: u64,
+    // Statistics of the top-level fields, by name.
+    pub fields: BTreeMap<String, FieldStatistics>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldStatistics {
+    // Documents without the field, or with a null value for it.
+    pub nulls: u64,
+    // Number of different scalar values.
+    pub distinct: u64,
+    pub histogram: Histogram,
+}
+
+/// An equi-depth histogram of the scalar values of a field. The bounds are
+/// in the order-preserving key encoding: the first one is the smallest
+/// value, and each of the others closes a bucket that holds the same share
+/// of the values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bounds: Vec<Vec<u8>>,
+}
+
+impl CollectionStatistics {
+    pub fn gather<'v>(documents: impl IntoIterator<Item = RV<'v>>) -> Self {
+        let mut rows = 0;
+        let mut present: BTreeMap<String, u64> = BTreeMap::new();
+        let mut values: BTreeMap<String, Vec<Vec<u8>>> = BTreeMap::new();
+
+        for document in documents {
+            rows += 1;
+            let RV::Object(document) = document else {
+                continue;
+            };
+            for (field, value) in document.iter() {
+                if matches!(value, RV::Null | RV::Undefined) {
+                    continue;
+                }
+                *present.entry(field.clone()).or_default() += 1;
+                if let Some(encoded) = encode_scalar(&value) {
+                    values.entry(field).or_default().push(encoded);
+                }
+            }
+        }
+
+        let fields = present
+            .into_iter()
+            .map(|(field, present)| {
+                let mut values = values.remove(&field).unwrap_or_default();
+                values.sort();
+                let statistics = FieldStatistics {
+                    nulls: rows - present,
+                    distinct: values.iter().collect::<HashSet<_>>().len() as u64,
+                    histogram: Histogram::from_sorted(&values),
+                };
+                (field, statistics)
+            })
+            .collect();
+
+        CollectionStatistics { rows, fields }
+    }
+}
+
+impl Histogram {
+    fn from_sorted(values: &[Vec<u8>]) -> Self {
+        let Some(first) = values.first() else {
+            return Histogram::default();
+        };
+        let buckets = BUCKETS.min(values.len());
+        let mut bounds = vec![first.clone()];
+        bounds.extend(
+            (1..=buckets)
+                .map(|bucket| values[(bucket * values.len()).div_ceil(buckets) - 1].clone()),
+        );
+        Histogram { bounds }
+    }
+
+    /// The estimated share of the values that sort before `value`, or
+    /// `None` when the histogram is empty.
+    pub fn fraction_below(&self, value: &[u8]) -> Option<f64> {
+        let (first, last) = (self.bounds.first()?, self.bounds.last()?);
+        if value <= first.as_slice() {
+            return Some(0.0);
+        }
+        if value > last.as_slice() {
+            return Some(1.0);
+        }
+        // The bucket closed by the first bound that is not below the value
+        // holds it, and is assumed to be half below it.
+        let closing = self
+            .bounds
+            .partition_point(|bound| bound.as_slice() < value);
+        let buckets = (self.bounds.len() - 1) as f64;
+        Some((closing as f64 - 0.5) / buckets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::value::object::RVObject;
+
+    fn document(fields: &[(&str, RV<'static>)]) -> RV<'static> {
+        let map: IndexMap<String, RV> = fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+        RV::Object(RVObject::from_map(map))
+    }
+
+    fn encoded(n: f64) -> Vec<u8> {
+        encode_scalar(&RV::Double(n)).unwrap()
+    }
+
+    #[test]
+    fn test_gather_counts_rows_nulls_and_distinct_values() {
+        let statistics = CollectionStatistics::gather((0..10).map(|i| {
+            if i < 3 {
+                document(&[("id", RV::Double(i as f64))])
+            } else {
+                document(&[
+                    ("id", RV::Double(i as f64)),
+                    ("group", RV::Double((i % 2) as f64)),
+                ])
+            }
+        }));
+
+        assert_eq!(statistics.rows, 10);
+        assert_eq!(statistics.fields["id"].nulls, 0);
+        assert_eq!(statistics.fields["id"].distinct, 10);
+        assert_eq!(statistics.fields["group"].nulls, 3);
+        assert_eq!(statistics.fields["group"].distinct, 2);
+    }
+
+    #[test]
+    fn test_histogram_has_equal_depth_buckets() {
+        let statistics = CollectionStatistics::gather(
+            (1..=160).map(|i| document(&[("n", RV::Double(i as f64))])),
+        );
+        let histogram = &statistics.fields["n"].histogram;
+
+        assert_eq!(histogram.bounds.len(), BUCKETS + 1);
+        assert_eq!(histogram.bounds[0], encoded(1.0));
+        assert_eq!(histogram.bounds[1], encoded(10.0));
+        assert_eq!(histogram.bounds[BUCKETS], encoded(160.0));
+
+        assert_eq!(histogram.fraction_below(&encoded(0.0)), Some(0.0));
+        assert_eq!(histogram.fraction_below(&encoded(1000.0)), Some(1.0));
+        let half = histogram.fraction_below(&encoded(80.5)).unwrap();
+        assert!((half - 0.5).abs() < 0.05, "{half}");
+    }
+
+    #[test]
+    fn test_histogram_of_few_values() {
+        let statistics = CollectionStatistics::gather(
+            [1.0, 1.0, 2.0].map(|n| document(&[("n", RV::Double(n))])),
+        );
+        let histogram = &statistics.fields["n"].histogram;
+
+        assert_eq!(
+            histogram.bounds,
+            vec![encoded(1.0), encoded(1.0), encoded(1.0), encoded(2.0)]
+        );
+        assert_eq!(Histogram::default().fraction_below(&encoded(1.0)), None);
+    }
+}
@@ -0,0 +1,133 @@
+use lykiadb_common::memory::{Shared, alloc_shared};
+
+use crate::{
+    engine::Engine,
+    execution::{error::ExecutionError, session::Session},
+    store::{Store, boxed::BoxedStore, memory::MemoryStore},
+};
+
+/// A database that any number of sessions use at once, e.g. the ones of the
+/// connections to a server. Clones are handles to the same database, and
+/// can be sent to other threads.
+#[derive(Clone)]
+pub struct Database {
+    engine: Shared<Engine<BoxedStore>>,
+}
+
+impl Database {
+    pub fn open(
+        store: impl for<'a> Store<'a> + Send + Sync + 'static,
+    ) -> Result<Self, ExecutionError> {
+        Ok(Database {
+            engine: alloc_shared(Engine::boxed(store)?),
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self::open(MemoryStore::new()).unwrap()
+    }
+
+    /// Starts a session on the database. Each session runs its own
+    /// transactions, and sees what the others have committed.
+    pub fn session<'v>(&self, keep_alive: bool) -> Session<'v> {
+        Session::with_engine(keep_alive, self.engine.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use lykiadb_common::memory::alloc_shared;
+
+    use super::*;
+    use crate::{engine::error::EngineError, interpreter::output::Output, value::RV};
+
+    fn run<'v>(session: &mut Session<'v>, source: &str) -> Result<RV<'v>, ExecutionError> {
+        session.interpret(source, alloc_shared(Output::new()))
+    }
+
+    fn count_users(session: &mut Session) -> usize {
+        match run(session, "select u.name as name from users u;").unwrap() {
+            RV::Array(rows) => rows.len(),
+            other => panic!("Expected rows, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_sessions_see_each_others_writes() {
+        let db = Database::in_memory();
+        let mut first = db.session(false);
+        let mut second = db.session(false);
+
+        run(&mut first, "CREATE COLLECTION users;").unwrap();
+        run(&mut first, "insert into users values ({name: \"alice\"});").unwrap();
+        assert_eq!(count_users(&mut second), 1);
+
+        run(&mut second, "insert into users values ({name: \"bob\"});").unwrap();
+        assert_eq!(count_users(&mut first), 2);
+    }
+
+    #[test]
+    fn test_sessions_write_from_many_threads() {
+        let db = Database::in_memory();
+        run(&mut db.session(false), "CREATE COLLECTION users;").unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let db = db.clone();
+                thread::spawn(move || {
+                    let mut session = db.session(false);
+                    for i in 0..25 {
+                        let insert =
+                            format!("insert into users values ({{name: \"{writer}-{i}\"}});");
+                        run(&mut session, &insert).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(count_users(&mut db.session(false)), 200);
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let db = Database::in_memory();
+        let mut session = db.session(false);
+        run(&mut session, "CREATE COLLECTION counters;").unwrap();
+        run(&mut session, "insert into counters values ({n: 0});").unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let db = db.clone();
+                thread::spawn(move || {
+                    let mut session = db.session(false);
+                    for _ in 0..50 {
+                        // Increments that lose a write conflict are retried.
+                        while let Err(err) = run(&mut session, "update counters x set n = x.n + 1;")
+                        {
+                            assert_eq!(
+                                err,
+                                ExecutionError::Engine(EngineError::SerializationFailure)
+                            );
+                        }
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let RV::Array(rows) = run(&mut session, "select x.n as n from counters x;").unwrap() else {
+            panic!("Expected rows");
+        };
+        let RV::Object(counter) = rows.get(0) else {
+            panic!("Expected a counter, got {rows:?}");
+        };
+        assert_eq!(counter.get("n"), Some(RV::Double(400.0)));
+    }
+}
@@ -1,25 +1,39 @@
 use std::sync::Arc;
 
+use crate::engine::StoreId;
+use crate::engine::catalog::{CollectionMetadata, IndexMetadata};
 use crate::execution::state::ProgramState;
+use crate::execution::transaction::TransactionManager;
 use crate::interpreter::HaltReason;
 use crate::interpreter::environment::{EnvironmentFrame, EnvironmentOrigin};
 use crate::interpreter::error::InterpretError;
 use crate::query::QueryEngine;
 use crate::query::context::QueryExecutionContext;
 use crate::value::RV;
-use lykiadb_lang::ast::Span;
 use lykiadb_lang::ast::expr::Expr;
+use lykiadb_lang::ast::sql::{SqlCollectionIdentifier, SqlConstraint};
+use lykiadb_lang::ast::stmt::TransactionAction;
+use lykiadb_lang::ast::{Identifier, Span};
 
 pub fn dispatch_query_explain<'sess>(
     expr: &Expr,
+    analyze: bool,
     span: &Span,
     state: ProgramState<'sess>,
 ) -> Result<RV<'sess>, HaltReason<'sess>> {
-    if matches!(expr, Expr::Select { .. }) {
-        let exec_ctx = QueryExecutionContext::new(state.clone());
+    if matches!(
+        expr,
+        Expr::Select { .. } | Expr::Insert { .. } | Expr::Update { .. } | Expr::Delete { .. }
+    ) {
+        // EXPLAIN ANALYZE runs the query, so it gets a frame of its own too.
+        let exec_ctx = query_context(&state);
         let mut query_engine = QueryEngine::new();
-        let plan = &query_engine.explain(expr, &exec_ctx)?;
-        Err(HaltReason::Return(plan.to_object()))
+        let explained = if analyze {
+            query_engine.explain_analyze(expr, &exec_ctx)?
+        } else {
+            query_engine.explain(expr, &exec_ctx)?.to_object()
+        };
+        Err(HaltReason::Return(explained))
     } else {
         Err(HaltReason::Error(
             InterpretError::InvalidExplainTarget { span: *span }.into(),
@@ -27,21 +41,127 @@ pub fn dispatch_query_explain<'sess>(
     }
 }
 
+pub fn dispatch_create_collection<'sess>(
+    collection: &SqlCollectionIdentifier,
+    constraints: &[SqlConstraint],
+    state: &ProgramState<'sess>,
+) -> Result<(), HaltReason<'sess>> {
+    let mut metadata = CollectionMetadata::from(&StoreId::from(collection));
+    for constraint in constraints {
+        match constraint {
+            SqlConstraint::PrimaryKey { fields } => metadata.primary_key = field_paths(fields),
+            SqlConstraint::Unique { fields } => {
+                let fields = field_paths(fields);
+                if metadata
+                    .indexes
+                    .iter()
+                    .any(|index| index.unique && index.fields == fields)
+                {
+                    continue;
+                }
+                // Unique constraints are kept by indexes named after the
+                // fields, e.g. `users_email_key`.
+                let paths: Vec<String> = fields.iter().map(|path| path.join("_")).collect();
+                let base = format!("{}_{}_key", metadata.name, paths.join("_"));
+                let mut name = base.clone();
+                for n in 1.. {
+                    if metadata.index(&name).is_none() {
+                        break;
+                    }
+                    name = format!("{base}{n}");
+                }
+                metadata.indexes.push(IndexMetadata {
+                    name,
+                    fields,
+                    unique: true,
+                });
+            }
+        }
+    }
+
+    let mut engine = state.engine.write().unwrap();
+    engine
+        .create_collection_with(metadata)
+        .map_err(HaltReason::Error)
+}
+
+fn field_paths(fields: &[Vec<Identifier>]) -> Vec<Vec<String>> {
+    fields
+        .iter()
+        .map(|path| path.iter().map(|field| field.name.clone()).collect())
+        .collect()
+}
+
+pub fn dispatch_create_index<'sess>(
+    name: &Identifier,
+    collection: &SqlCollectionIdentifier,
+    fields: &[Vec<Identifier>],
+    state: &ProgramState<'sess>,
+) -> Result<(), HaltReason<'sess>> {
+    let index = IndexMetadata {
+        name: name.name.clone(),
+        fields: field_paths(fields),
+        unique: false,
+    };
+    let mut engine = state.engine.write().unwrap();
+    engine
+        .create_index(&StoreId::from(collection), index)
+        .map_err(HaltReason::Error)
+}
+
+pub fn dispatch_drop_collection<'sess>(
+    collection: &SqlCollectionIdentifier,
+    state: &ProgramState<'sess>,
+) -> Result<(), HaltReason<'sess>> {
+    let mut engine = state.engine.write().unwrap();
+    engine
+        .drop_collection(&StoreId::from(collection))
+        .map_err(HaltReason::Error)
+}
+
+pub fn dispatch_analyze<'sess>(
+    collection: &SqlCollectionIdentifier,
+    state: &ProgramState<'sess>,
+) -> Result<(), HaltReason<'sess>> {
+    let mut engine = state.engine.write().unwrap();
+    engine
+        .analyze(&StoreId::from(collection))
+        .map_err(HaltReason::Error)
+}
+
+pub fn dispatch_transaction<'sess>(
+    action: &TransactionAction,
+    span: &Span,
+    state: &ProgramState<'sess>,
+) -> Result<(), HaltReason<'sess>> {
+    let mut transactions = state.transactions.write().unwrap();
+    let mut engine = state.engine.write().unwrap();
+    let dispatch = match action {
+        TransactionAction::Begin => TransactionManager::begin,
+        TransactionAction::Commit => TransactionManager::commit,
+        TransactionAction::Rollback => TransactionManager::rollback,
+    };
+    dispatch(&mut transactions, &mut engine, *span).map_err(HaltReason::Error)
+}
+
 pub fn dispatch_query_execute<'sess>(
     expr: &Expr,
     _span: &Span,
     state: &ProgramState<'sess>,
 ) -> Result<RV<'sess>, HaltReason<'sess>> {
+    // Create a query execution context and execute the query using the query engine.
+    let exec_ctx = query_context(state);
+    let mut query_engine = QueryEngine::new();
+    let result = query_engine.execute(expr, &exec_ctx)?;
+    Ok(result)
+}
+
+fn query_context<'sess>(state: &ProgramState<'sess>) -> QueryExecutionContext<'sess> {
     // Insert a query environment frame for the execution of the query,
     // so that variables defined within the query can be stored and
     // accessed without affecting the outer environment.
     let query_env = EnvironmentFrame::new(Some(Arc::clone(&state.env)), EnvironmentOrigin::Query);
     let mut cloned = state.clone();
     cloned.env = Arc::new(query_env);
-
-    // Create a query execution context and execute the query using the query engine.
-    let exec_ctx = QueryExecutionContext::new(cloned);
-    let mut query_engine = QueryEngine::new();
-    let result = query_engine.execute(expr, &exec_ctx)?;
-    Ok(result)
+    QueryExecutionContext::new(cloned)
 }
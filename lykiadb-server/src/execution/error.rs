@@ -4,6 +4,7 @@ use crate::{
     engine::error::EngineError,
     interpreter::{environment::EnvironmentError, error::InterpretError},
     query::plan::error::PlannerError,
+    store::error::StoreError,
 };
 
 use lykiadb_common::error::InputError;
@@ -17,6 +18,7 @@ pub enum ExecutionError {
     Environment(EnvironmentError),
     Plan(PlannerError),
     Engine(EngineError),
+    Store(StoreError),
 }
 
 impl Display for ExecutionError {
@@ -33,6 +35,7 @@ impl ExecutionError {
             ExecutionError::Plan(planner_error) => planner_error.into(),
             ExecutionError::Environment(env_error) => env_error.into(),
             ExecutionError::Engine(engine_error) => engine_error.into(),
+            ExecutionError::Store(store_error) => store_error.into(),
         }
     }
 }
@@ -48,3 +51,9 @@ impl From<InterpretError> for ExecutionError {
         ExecutionError::Interpret(err)
     }
 }
+
+impl From<StoreError> for ExecutionError {
+    fn from(err: StoreError) -> Self {
+        ExecutionError::Store(err)
+    }
+}
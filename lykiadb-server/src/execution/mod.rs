@@ -1,5 +1,7 @@
+pub mod database;
 pub mod dispatching;
 pub mod error;
 pub mod global;
 pub mod session;
 pub mod state;
+pub mod transaction;
@@ -1,9 +1,11 @@
 use crate::{
-    execution::{error::ExecutionError, state::ProgramState},
+    engine::Engine,
+    execution::{error::ExecutionError, state::ProgramState, transaction::TransactionManager},
     interpreter::{Interpreter, output::Output},
+    store::{boxed::BoxedStore, memory::MemoryStore},
 };
 use lykiadb_common::memory::{Shared, alloc_shared};
-use tracing::info;
+use tracing::{error, info};
 
 use std::sync::Arc;
 
@@ -15,14 +17,27 @@ pub struct Session<'v> {
     keep_alive: bool,
     source_processor: SourceProcessor,
     program_state: Option<ProgramState<'v>>,
+    engine: Shared<Engine<BoxedStore>>,
+    transactions: Shared<TransactionManager>,
 }
 
 impl<'v> Session<'v> {
     pub fn new(keep_alive: bool) -> Session<'v> {
+        Self::with_engine(
+            keep_alive,
+            alloc_shared(Engine::boxed(MemoryStore::new()).unwrap()),
+        )
+    }
+
+    /// Creates a session on an engine that other sessions might share. Each
+    /// session runs its own transactions.
+    pub fn with_engine(keep_alive: bool, engine: Shared<Engine<BoxedStore>>) -> Session<'v> {
         Session {
             keep_alive,
             source_processor: SourceProcessor::new(),
             program_state: None,
+            engine,
+            transactions: alloc_shared(TransactionManager::new()),
         }
     }
 
@@ -38,7 +53,13 @@ impl<'v> Session<'v> {
         {
             self.program_state = Some(state.fork(out, program));
         } else {
-            self.program_state = Some(ProgramState::new(out, program, true));
+            self.program_state = Some(ProgramState::new(
+                out,
+                program,
+                Arc::clone(&self.engine),
+                Arc::clone(&self.transactions),
+                true,
+            ));
         }
 
         let mut interpreter = Interpreter::from_state(self.program_state.as_ref().unwrap());
@@ -54,6 +75,22 @@ impl<'v> Session<'v> {
     }
 }
 
+// A session that goes away, e.g. along with the connection of a client,
+// leaves no transaction open behind it, as the writes of one keep other
+// sessions from writing the same documents.
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        let (Ok(mut transactions), Ok(mut engine)) =
+            (self.transactions.write(), self.engine.write())
+        else {
+            return;
+        };
+        if let Err(err) = transactions.abandon(&mut engine) {
+            error!("Failed to roll back the transaction of a closed session: {err}");
+        }
+    }
+}
+
 pub struct SessionTester<'v> {
     out: Shared<Output<'v>>,
     session: Session<'v>,
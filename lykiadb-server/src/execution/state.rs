@@ -1,7 +1,10 @@
+use crate::engine::Engine;
 use crate::execution::global::GLOBAL_INTERNER;
+use crate::execution::transaction::TransactionManager;
 use crate::interpreter::environment::{EnvironmentFrame, EnvironmentOrigin};
 use crate::interpreter::output::Output;
 use crate::libs::stdlib::stdlib;
+use crate::store::boxed::BoxedStore;
 use lykiadb_common::memory::Shared;
 use std::sync::Arc;
 
@@ -12,6 +15,9 @@ pub struct ProgramState<'sess> {
     pub env: Arc<EnvironmentFrame<'sess>>,
     // Output
     pub output: Shared<Output<'sess>>,
+    // Storage
+    pub engine: Shared<Engine<BoxedStore>>,
+    pub transactions: Shared<TransactionManager>,
     // Static fields:
     pub program: Arc<Program>,
 }
@@ -20,6 +26,8 @@ impl<'sess> ProgramState<'sess> {
     pub fn new(
         output: Shared<Output<'sess>>,
         program: Arc<Program>,
+        engine: Shared<Engine<BoxedStore>>,
+        transactions: Shared<TransactionManager>,
         with_stdlib: bool,
     ) -> ProgramState<'sess> {
         let root_env = Arc::new(EnvironmentFrame::new(None, EnvironmentOrigin::Root));
@@ -36,6 +44,8 @@ impl<'sess> ProgramState<'sess> {
             root_env: root_env.clone(),
             program,
             output,
+            engine,
+            transactions,
         }
     }
 
@@ -49,6 +59,8 @@ impl<'sess> ProgramState<'sess> {
             root_env: Arc::clone(&self.root_env),
             program,
             output,
+            engine: Arc::clone(&self.engine),
+            transactions: Arc::clone(&self.transactions),
         }
     }
 }
@@ -58,11 +70,14 @@ pub mod test_utils {
     use lykiadb_common::memory::alloc_shared;
 
     use super::*;
+    use crate::store::memory::MemoryStore;
 
     pub fn create_empty_state<'sess>() -> ProgramState<'sess> {
         ProgramState::new(
             alloc_shared(Output::new()),
             Arc::new(Program::empty()),
+            alloc_shared(Engine::boxed(MemoryStore::new()).unwrap()),
+            alloc_shared(TransactionManager::new()),
             true,
         )
     }
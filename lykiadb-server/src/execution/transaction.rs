@@ -0,0 +1,235 @@
+use lykiadb_lang::ast::Span;
+
+use crate::{
+    engine::{
+        Engine,
+        error::EngineError,
+        mvcc::{Snapshot, Transaction},
+    },
+    execution::error::ExecutionError,
+    interpreter::error::InterpretError,
+    store::Store,
+};
+
+/// Keeps track of the transaction a session opened with `BEGIN`. Statements
+/// that run outside of it are committed on their own.
+#[derive(Default)]
+pub struct TransactionManager {
+    current: Option<Transaction>,
+}
+
+impl TransactionManager {
+    pub fn new() -> Self {
+        TransactionManager { current: None }
+    }
+
+    pub fn begin<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+        span: Span,
+    ) -> Result<(), ExecutionError> {
+        if self.current.is_some() {
+            return Err(InterpretError::TransactionAlreadyOpen { span }.into());
+        }
+        self.current = Some(engine.begin()?);
+        Ok(())
+    }
+
+    pub fn commit<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+        span: Span,
+    ) -> Result<(), ExecutionError> {
+        let Some(txn) = self.current.take() else {
+            return Err(InterpretError::NoOpenTransaction { span }.into());
+        };
+        engine.commit(&txn)
+    }
+
+    pub fn rollback<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+        span: Span,
+    ) -> Result<(), ExecutionError> {
+        let Some(txn) = self.current.take() else {
+            return Err(InterpretError::NoOpenTransaction { span }.into());
+        };
+        engine.rollback(&txn)
+    }
+
+    /// Rolls back the open transaction, if any, e.g. when the session that
+    /// opened it goes away.
+    pub fn abandon<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+    ) -> Result<(), ExecutionError> {
+        match self.current.take() {
+            Some(txn) => engine.rollback(&txn),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a transaction for a single statement, unless the session has one
+    /// open already. Returns whether it did.
+    pub fn begin_statement<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+    ) -> Result<bool, ExecutionError> {
+        if self.current.is_some() {
+            return Ok(false);
+        }
+        self.current = Some(engine.begin()?);
+        Ok(true)
+    }
+
+    /// Commits the transaction `begin_statement` opened if the statement
+    /// succeeded, and rolls it back otherwise. A statement that lost a write
+    /// conflict has no transaction left to end.
+    pub fn end_statement<S: for<'a> Store<'a>>(
+        &mut self,
+        engine: &mut Engine<S>,
+        succeeded: bool,
+    ) -> Result<(), ExecutionError> {
+        match self.current.take() {
+            Some(txn) if succeeded => engine.commit(&txn),
+            Some(txn) => engine.rollback(&txn),
+            None => Ok(()),
+        }
+    }
+
+    /// The snapshot reads should see: the one of the open transaction, or
+    /// the latest committed state otherwise.
+    pub fn snapshot<S: for<'a> Store<'a>>(&self, engine: &Engine<S>) -> Snapshot {
+        match &self.current {
+            Some(txn) => txn.snapshot().clone(),
+            None => engine.snapshot(),
+        }
+    }
+
+    /// Runs the writes of a statement in the open transaction. A transaction
+    /// that loses a write conflict is rolled back, as it could not commit
+    /// anyway.
+    pub fn write<S: for<'a> Store<'a>, T>(
+        &mut self,
+        engine: &mut Engine<S>,
+        f: impl FnOnce(&mut Engine<S>, &Transaction) -> Result<T, ExecutionError>,
+    ) -> Result<T, ExecutionError> {
+        let Some(txn) = &self.current else {
+            return engine.autocommit(f);
+        };
+
+        let result = f(engine, txn);
+        if let Err(ExecutionError::Engine(EngineError::SerializationFailure)) = &result {
+            engine.rollback(txn)?;
+            self.current = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lykiadb_common::memory::alloc_shared;
+
+    use crate::{
+        engine::{Engine, error::EngineError},
+        execution::{error::ExecutionError, session::Session},
+        interpreter::output::Output,
+        store::memory::MemoryStore,
+        value::RV,
+    };
+
+    fn run<'v>(session: &mut Session<'v>, source: &str) -> Result<RV<'v>, ExecutionError> {
+        session.interpret(source, alloc_shared(Output::new()))
+    }
+
+    fn count_users(session: &mut Session) -> usize {
+        match run(session, "select u.name as name from users u;").unwrap() {
+            RV::Array(rows) => rows.len(),
+            other => panic!("Expected rows, got {other:?}"),
+        }
+    }
+
+    fn sessions<'v>() -> (Session<'v>, Session<'v>) {
+        let engine = alloc_shared(Engine::boxed(MemoryStore::new()).unwrap());
+        let mut first = Session::with_engine(false, engine.clone());
+        run(&mut first, "CREATE COLLECTION users;").unwrap();
+        (first, Session::with_engine(false, engine))
+    }
+
+    #[test]
+    fn test_writes_are_invisible_to_other_sessions_until_commit() {
+        let (mut first, mut second) = sessions();
+
+        run(&mut first, "BEGIN;").unwrap();
+        run(&mut first, "insert into users values ({name: \"alice\"});").unwrap();
+        assert_eq!(count_users(&mut first), 1);
+        assert_eq!(count_users(&mut second), 0);
+
+        run(&mut first, "COMMIT;").unwrap();
+        assert_eq!(count_users(&mut second), 1);
+    }
+
+    #[test]
+    fn test_reads_stay_on_the_snapshot_taken_at_begin() {
+        let (mut first, mut second) = sessions();
+        run(&mut first, "insert into users values ({name: \"alice\"});").unwrap();
+
+        run(&mut second, "BEGIN;").unwrap();
+        run(&mut first, "insert into users values ({name: \"bob\"});").unwrap();
+        run(&mut first, "delete from users u where u.name = \"alice\";").unwrap();
+        assert_eq!(count_users(&mut first), 1);
+        assert_eq!(count_users(&mut second), 1);
+        let names = run(&mut second, "select u.name as name from users u;").unwrap();
+        assert!(names.to_string().contains("alice"));
+
+        run(&mut second, "COMMIT;").unwrap();
+        let names = run(&mut second, "select u.name as name from users u;").unwrap();
+        assert!(names.to_string().contains("bob"));
+    }
+
+    #[test]
+    fn test_conflicting_writer_fails_and_is_rolled_back() {
+        let (mut first, mut second) = sessions();
+        run(
+            &mut first,
+            "insert into users values ({name: \"alice\", age: 30});",
+        )
+        .unwrap();
+
+        run(&mut first, "BEGIN;").unwrap();
+        run(&mut second, "BEGIN;").unwrap();
+        run(&mut second, "insert into users values ({name: \"bob\"});").unwrap();
+        run(&mut first, "update users u set age = 31;").unwrap();
+
+        assert_eq!(
+            run(&mut second, "update users u set age = 32;"),
+            Err(ExecutionError::Engine(EngineError::SerializationFailure))
+        );
+        // The failed transaction is gone, along with everything it wrote.
+        assert!(run(&mut second, "COMMIT;").is_err());
+        run(&mut first, "COMMIT;").unwrap();
+        assert_eq!(count_users(&mut second), 1);
+
+        let ages = run(&mut second, "select u.age as age from users u;").unwrap();
+        assert!(ages.to_string().contains("31"));
+    }
+
+    #[test]
+    fn test_dropped_session_rolls_back_its_transaction() {
+        let (mut first, mut second) = sessions();
+        run(
+            &mut first,
+            "insert into users values ({name: \"alice\", age: 30});",
+        )
+        .unwrap();
+
+        run(&mut first, "BEGIN;").unwrap();
+        run(&mut first, "update users u set age = 31;").unwrap();
+        drop(first);
+
+        run(&mut second, "update users u set age = 32;").unwrap();
+        let ages = run(&mut second, "select u.age as age from users u;").unwrap();
+        assert!(ages.to_string().contains("32"));
+    }
+}
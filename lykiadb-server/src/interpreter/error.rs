@@ -10,7 +10,7 @@ pub enum InterpretError {
     UnexpectedStatement { span: Span },
     #[error("Property '{property}' not found at {span:?}")]
     PropertyNotFound { span: Span, property: String },
-    #[error("Only select expressions can be explained.")]
+    #[error("Only SELECT, INSERT, UPDATE and DELETE expressions can be explained.")]
     InvalidExplainTarget { span: Span },
     #[error(
         "Make sure that subject and tested boundaries are of the same type (allowed types: str, datetime, number-like)."
@@ -20,6 +20,12 @@ pub enum InterpretError {
     InvalidPropertyAccess { span: Span, value_str: String },
     #[error("Argument type mismatch. Expected {expected:?}")]
     InvalidArgumentType { span: Span, expected: String },
+    #[error("Scalar subquery returned more than one row.")]
+    ScalarSubqueryMultipleRows { span: Span },
+    #[error("There is already a transaction in progress.")]
+    TransactionAlreadyOpen { span: Span },
+    #[error("There is no transaction in progress.")]
+    NoOpenTransaction { span: Span },
 }
 
 impl From<InterpretError> for InputError {
@@ -36,9 +42,10 @@ impl From<InterpretError> for InputError {
             InterpretError::PropertyNotFound { span, .. } => {
                 ("Verify the property name exists on the object", *span)
             }
-            InterpretError::InvalidExplainTarget { span, .. } => {
-                ("Try replacing this with a SELECT expression", *span)
-            }
+            InterpretError::InvalidExplainTarget { span, .. } => (
+                "Try replacing this with a SELECT, INSERT, UPDATE or DELETE expression",
+                *span,
+            ),
             InterpretError::InvalidRangeBoundaries { span } => (
                 "Make sure that subject and tested boundaries are of the same type (allowed types: str, datetime, number-like).",
                 *span,
@@ -52,6 +59,17 @@ impl From<InterpretError> for InputError {
             InterpretError::InvalidArgumentType { span, .. } => {
                 ("Check that the argument matches the expected types", *span)
             }
+            InterpretError::ScalarSubqueryMultipleRows { span } => (
+                "Narrow the subquery down to a single row, e.g. with LIMIT 1",
+                *span,
+            ),
+            InterpretError::TransactionAlreadyOpen { span } => (
+                "Commit or roll back the current transaction before starting a new one",
+                *span,
+            ),
+            InterpretError::NoOpenTransaction { span } => {
+                ("Start a transaction with BEGIN first", *span)
+            }
         };
 
         InputError::new(&value.to_string(), hint, Some(sp.into()))
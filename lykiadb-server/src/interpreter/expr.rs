@@ -54,7 +54,18 @@ impl<'sess> ExprEngine {
         state: &ProgramState<'sess>,
     ) -> Result<RV<'sess>, HaltReason<'sess>> {
         let left_eval = self.eval(lexpr, state)?;
-        let right_eval = self.eval(rexpr, state)?;
+        let right_eval = match rexpr.as_subquery() {
+            // `x IN (SELECT ...)` tests against the first column of each row.
+            Some(subquery)
+                if self.is_query(state) && matches!(operation, BinaryOp::In | BinaryOp::NotIn) =>
+            {
+                let rows = self.eval_subquery(subquery, state)?;
+                RV::Array(RVArray::from_vec(
+                    rows.iter().map(|row| first_column(&row)).collect(),
+                ))
+            }
+            _ => self.eval(rexpr, state)?,
+        };
 
         Ok(eval_binary(left_eval, right_eval, operation))
     }
@@ -90,6 +101,41 @@ impl<'sess> ExprEngine {
         state.env.origin == EnvironmentOrigin::Query
     }
 
+    // Returns the rows of a subquery. Within a query, the subquery is run by
+    // the executor beforehand and its rows are looked up by the sign of the
+    // expression.
+    fn eval_subquery(
+        &self,
+        e: &Expr,
+        state: &ProgramState<'sess>,
+    ) -> Result<RVArray<'sess>, HaltReason<'sess>> {
+        let rows = match self.eval_variable(&e.sign(), e, state) {
+            Ok(rows) if self.is_query(state) => rows,
+            _ => dispatch_query_execute(e, &e.get_span(), state)?,
+        };
+
+        Ok(match rows {
+            RV::Array(rows) => rows,
+            other => RVArray::from_vec(vec![other]),
+        })
+    }
+
+    fn eval_scalar_subquery(
+        &self,
+        e: &Expr,
+        state: &ProgramState<'sess>,
+    ) -> Result<RV<'sess>, HaltReason<'sess>> {
+        let rows = self.eval_subquery(e, state)?;
+
+        match rows.len() {
+            0 => Ok(RV::Null),
+            1 => Ok(first_column(&rows.get(0))),
+            _ => Err(HaltReason::Error(
+                InterpretError::ScalarSubqueryMultipleRows { span: e.get_span() }.into(),
+            )),
+        }
+    }
+
     fn eval_variable(
         &self,
         name: &str,
@@ -141,6 +187,14 @@ impl<'sess> ExprEngine {
         match e {
             Expr::Literal { value, .. } => self.eval_literal(value, state),
             Expr::Variable { name, .. } => self.eval_variable(&name.name, e, state),
+            Expr::Unary {
+                operation: UnaryOp::Exists,
+                expr,
+                ..
+            } => {
+                let subquery = expr.as_subquery().unwrap_or(expr);
+                Ok(RV::Bool(!self.eval_subquery(subquery, state)?.is_empty()))
+            }
             Expr::Unary {
                 operation, expr, ..
             } => self.eval_unary(operation, expr, state),
@@ -359,6 +413,7 @@ impl<'sess> ExprEngine {
                     ))
                 }
             }
+            Expr::Select { .. } if self.is_query(state) => self.eval_scalar_subquery(e, state),
             Expr::Select { span, .. }
             | Expr::Insert { span, .. }
             | Expr::Update { span, .. }
@@ -366,3 +421,16 @@ impl<'sess> ExprEngine {
         }
     }
 }
+
+// A subquery row is an object keyed by its projected fields. Scalar and
+// `IN` subqueries use its first field.
+fn first_column<'v>(row: &RV<'v>) -> RV<'v> {
+    match row {
+        RV::Object(fields) => fields
+            .iter()
+            .next()
+            .map(|(_, value)| value)
+            .unwrap_or(RV::Undefined),
+        other => other.clone(),
+    }
+}
@@ -1,4 +1,7 @@
-use crate::execution::dispatching::dispatch_query_explain;
+use crate::execution::dispatching::{
+    dispatch_analyze, dispatch_create_collection, dispatch_create_index, dispatch_drop_collection,
+    dispatch_query_explain, dispatch_transaction,
+};
 use crate::execution::error::ExecutionError;
 use crate::execution::global::intern_string;
 use crate::execution::state::ProgramState;
@@ -149,8 +152,36 @@ impl<'sess> Interpreter<'sess> {
                 }
                 return Err(HaltReason::Return(RV::Undefined));
             }
-            Stmt::Explain { expr, span } => {
-                return dispatch_query_explain(expr, span, self.state.clone());
+            Stmt::Explain {
+                expr,
+                analyze,
+                span,
+            } => {
+                return dispatch_query_explain(expr, *analyze, span, self.state.clone());
+            }
+            Stmt::CreateCollection {
+                collection,
+                constraints,
+                ..
+            } => {
+                dispatch_create_collection(collection, constraints, &self.state)?;
+            }
+            Stmt::CreateIndex {
+                name,
+                collection,
+                fields,
+                ..
+            } => {
+                dispatch_create_index(name, collection, fields, &self.state)?;
+            }
+            Stmt::DropCollection { collection, .. } => {
+                dispatch_drop_collection(collection, &self.state)?;
+            }
+            Stmt::Analyze { collection, .. } => {
+                dispatch_analyze(collection, &self.state)?;
+            }
+            Stmt::Transaction { action, span } => {
+                dispatch_transaction(action, span, &self.state)?;
             }
         }
         Ok(RV::Undefined)
@@ -161,16 +192,24 @@ impl<'sess> Interpreter<'sess> {
 pub mod tests {
     use std::sync::Arc;
 
-    use lykiadb_common::memory::Shared;
+    use lykiadb_common::memory::{Shared, alloc_shared};
     use lykiadb_lang::parser::program::Program;
 
     use crate::{
-        execution::state::ProgramState,
+        engine::Engine,
+        execution::{state::ProgramState, transaction::TransactionManager},
         interpreter::{Interpreter, output::Output},
+        store::memory::MemoryStore,
     };
 
     pub fn create_test_interpreter(out: Shared<Output>) -> Interpreter {
-        let state = ProgramState::new(out, Arc::new(Program::empty()), true);
+        let state = ProgramState::new(
+            out,
+            Arc::new(Program::empty()),
+            alloc_shared(Engine::boxed(MemoryStore::new()).unwrap()),
+            alloc_shared(TransactionManager::new()),
+            true,
+        );
         Interpreter::from_state(&state)
     }
 }
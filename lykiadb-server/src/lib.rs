@@ -41,7 +41,23 @@ macro_rules! assert_plan {
 #[macro_export]
 macro_rules! lykia_native_fn {
     ($builder:expr) => {
-        $crate::value::callable::Function::Native { function: $builder }
+        $crate::value::callable::Function::Native {
+            function: $builder,
+            pure: false,
+        }
+    };
+}
+
+/// A native function whose result only depends on its arguments, and which
+/// has no side effects. Calls with constant arguments are evaluated once,
+/// when the query is planned.
+#[macro_export]
+macro_rules! lykia_pure_fn {
+    ($builder:expr) => {
+        $crate::value::callable::Function::Native {
+            function: $builder,
+            pure: true,
+        }
     };
 }
 
@@ -95,7 +95,10 @@ mod tests {
         }
 
         RVCallable::new(
-            Function::Native { function: test_fn },
+            Function::Native {
+                function: test_fn,
+                pure: false,
+            },
             Datatype::Unit,
             Datatype::Unit,
         )
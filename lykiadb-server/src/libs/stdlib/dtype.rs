@@ -3,7 +3,7 @@ use rustc_hash::FxHashMap;
 
 use crate::{
     interpreter::{HaltReason, Interpreter, error::InterpretError},
-    lykia_module, lykia_native_fn,
+    lykia_module, lykia_pure_fn,
     value::{RV, object::RVObject},
 };
 
@@ -118,11 +118,11 @@ pub fn nt_object_of<'rv>(
 }
 
 lykia_module!(dtype, {
-    of_ => lykia_native_fn!(nt_of),
-    array => lykia_native_fn!(nt_array_of),
-    object => lykia_native_fn!(nt_object_of),
-    callable => lykia_native_fn!(nt_callable_of),
-    tuple => lykia_native_fn!(nt_tuple_of)
+    of_ => lykia_pure_fn!(nt_of),
+    array => lykia_pure_fn!(nt_array_of),
+    object => lykia_pure_fn!(nt_object_of),
+    callable => lykia_pure_fn!(nt_callable_of),
+    tuple => lykia_pure_fn!(nt_tuple_of)
 }, {
     str => RV::Datatype(Datatype::Str),
     double => RV::Datatype(Datatype::Double),
@@ -1,6 +1,6 @@
 use crate::{
     interpreter::{HaltReason, Interpreter, error::InterpretError},
-    lykia_module, lykia_native_fn,
+    lykia_module, lykia_pure_fn,
     value::RV,
 };
 use lykiadb_lang::ast::Span;
@@ -50,8 +50,8 @@ pub fn nt_json_decode<'rv>(
 }
 
 lykia_module!(json, {
-    stringify => lykia_native_fn!(nt_json_encode),
-    parse => lykia_native_fn!(nt_json_decode)
+    stringify => lykia_pure_fn!(nt_json_encode),
+    parse => lykia_pure_fn!(nt_json_decode)
 }, {}, []);
 
 #[cfg(test)]
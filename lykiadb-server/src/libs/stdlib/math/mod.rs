@@ -3,7 +3,7 @@ use crate::{
         avg::AvgAggregator, count::CountAggregator, max::MaxAggregator, min::MinAggregator,
         sum::SumAggregator,
     },
-    lykia_agg_fn, lykia_module, lykia_native_fn,
+    lykia_agg_fn, lykia_module, lykia_pure_fn,
 };
 
 mod avg;
@@ -19,5 +19,5 @@ lykia_module!(math, {
     count => lykia_agg_fn!(count, CountAggregator),
     min => lykia_agg_fn!(min, MinAggregator),
     max => lykia_agg_fn!(max, MaxAggregator),
-    mod => lykia_native_fn!(modulo::nt_modulo)
+    mod => lykia_pure_fn!(modulo::nt_modulo)
 }, {}, [avg, sum, count, min, max, mod]);
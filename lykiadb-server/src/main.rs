@@ -2,8 +2,12 @@ use ::std::time::Instant;
 use lykiadb_common::comm::tcp::TcpConnection;
 use lykiadb_common::comm::{CommunicationError, Message, Request, Response};
 use lykiadb_common::memory::alloc_shared;
+use lykiadb_server::execution::database::Database;
 use lykiadb_server::execution::session::Session;
 use lykiadb_server::interpreter::output::Output;
+use lykiadb_server::store::disk::DiskStore;
+use lykiadb_server::store::memory::MemoryStore;
+use lykiadb_server::store::wal::{SyncPolicy, WalStore};
 use std::io::Error;
 use tokio::net::TcpListener;
 use tokio::net::TcpStream;
@@ -27,11 +31,12 @@ $$$$$$$$\ \$$$$$$$ |$$ | \$$\ $$ |\$$$$$$$ |$$$$$$$  |$$$$$$$  |
 
 struct Server {
     listener: Option<TcpListener>,
+    db: Database,
 }
 
 impl Server {
-    pub fn new() -> Result<Self, Error> {
-        Ok(Server { listener: None })
+    pub fn new(db: Database) -> Result<Self, Error> {
+        Ok(Server { listener: None, db })
     }
 
     pub async fn listen(mut self, addr: &str) -> Result<Self, Error> {
@@ -47,8 +52,9 @@ impl Server {
             let mut stream = TcpListenerStream::new(listener);
             while let Some(socket) = stream.try_next().await? {
                 let peer = socket.peer_addr()?;
+                let db = self.db.clone();
                 tokio::spawn(async move {
-                    let mut session = Connection::new(socket);
+                    let mut session = Connection::new(socket, &db);
                     info!("Client {} connected", peer);
                     session.handle().await;
                     info!("Client {} disconnected", peer);
@@ -65,10 +71,10 @@ pub struct Connection<'v> {
 }
 
 impl<'v> Connection<'v> {
-    pub fn new(stream: TcpStream) -> Self {
+    pub fn new(stream: TcpStream, db: &Database) -> Self {
         Connection {
             conn: TcpConnection::new(stream),
-            session: Session::new(false),
+            session: db.session(false),
         }
     }
 
@@ -107,8 +113,176 @@ impl<'v> Connection<'v> {
     }
 }
 
+// Writes logged to a WAL between two checkpoints, unless set with
+// `--wal-checkpoint <records>`.
+const WAL_CHECKPOINT_EVERY: usize = 10_000;
+
+// Documents are kept in memory, unless a data directory is given with
+// `--data-dir <path>`. In memory, their writes can be logged to a WAL with
+// `--wal <path>` and replayed on the next start. The log is synced as set
+// with `--wal-sync <always|none|records>`, after every write by default.
+fn open_database(mut args: impl Iterator<Item = String>) -> Result<Database, Error> {
+    let mut data_dir = None;
+    let mut wal = None;
+    let mut sync = SyncPolicy::default();
+    let mut checkpoint_every = WAL_CHECKPOINT_EVERY;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::other(format!("Missing value for {arg}")))
+        };
+        match arg.as_str() {
+            "--data-dir" => data_dir = Some(value()?),
+            "--wal" => wal = Some(value()?),
+            "--wal-sync" => sync = value()?.parse().map_err(Error::other)?,
+            "--wal-checkpoint" => {
+                checkpoint_every = match value()?.parse() {
+                    Ok(records) if records > 0 => records,
+                    _ => return Err(Error::other("--wal-checkpoint expects a number of records")),
+                }
+            }
+            _ => return Err(Error::other(format!("Unknown argument: {arg}"))),
+        }
+    }
+
+    let opened = match (data_dir, wal) {
+        (Some(_), Some(_)) => {
+            return Err(Error::other("--data-dir and --wal cannot be used together"));
+        }
+        (Some(dir), None) => {
+            info!("Opening the database in {}", dir);
+            Database::open(DiskStore::open(&dir).map_err(Error::other)?)
+        }
+        (None, Some(path)) => {
+            info!("Logging the writes of the database to {}", path);
+            let store = WalStore::open(MemoryStore::new(), &path, sync)
+                .map_err(Error::other)?
+                .checkpoint_every(checkpoint_every);
+            Database::open(store)
+        }
+        (None, None) => return Ok(Database::in_memory()),
+    };
+    opened.map_err(|e| Error::other(e.to_string()))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     tracing_subscriber::fmt::init();
-    Server::new()?.listen("0.0.0.0:19191").await?.serve().await
+    Server::new(open_database(std::env::args().skip(1))?)?
+        .listen("0.0.0.0:19191")
+        .await?
+        .serve()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::Bson;
+    use lykiadb_common::comm::client::{ClientSession, connect};
+    use lykiadb_server::value::RV;
+
+    use super::*;
+
+    // Starts a server with an in-memory database on a free port, and returns
+    // its address.
+    async fn start_server() -> String {
+        let server = Server::new(Database::in_memory())
+            .unwrap()
+            .listen("127.0.0.1:0")
+            .await
+            .unwrap();
+        let addr = server.listener.as_ref().unwrap().local_addr().unwrap();
+        tokio::spawn(server.serve());
+        addr.to_string()
+    }
+
+    async fn run(client: &mut impl ClientSession, query: &str) -> Bson {
+        match client.execute(query).await.unwrap() {
+            Message::Response(Response::Value(value, _)) => value,
+            other => panic!("Expected a value, got {other:?}"),
+        }
+    }
+
+    async fn count_users(client: &mut impl ClientSession) -> usize {
+        match run(client, "select u.name as name from users u;").await {
+            Bson::Array(rows) => rows.len(),
+            other => panic!("Expected rows, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connections_see_each_others_writes() {
+        let addr = start_server().await;
+        let mut first = connect(&addr).await;
+        let mut second = connect(&addr).await;
+
+        run(&mut first, "CREATE COLLECTION users;").await;
+        run(&mut first, "insert into users values ({name: \"alice\"});").await;
+        assert_eq!(count_users(&mut second).await, 1);
+
+        run(&mut second, "insert into users values ({name: \"bob\"});").await;
+        assert_eq!(count_users(&mut first).await, 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_connections_write_concurrently() {
+        let addr = start_server().await;
+        run(&mut connect(&addr).await, "CREATE COLLECTION users;").await;
+
+        let writer = |id: usize| {
+            let addr = addr.clone();
+            async move {
+                let mut client = connect(&addr).await;
+                for i in 0..25 {
+                    let insert = format!("insert into users values ({{name: \"{id}-{i}\"}});");
+                    run(&mut client, &insert).await;
+                }
+            }
+        };
+        tokio::join!(writer(0), writer(1), writer(2), writer(3));
+
+        assert_eq!(count_users(&mut connect(&addr).await).await, 100);
+    }
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|arg| arg.to_string())
+    }
+
+    #[test]
+    fn test_wal_is_replayed_on_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let wal = dir.path().join("lykia.wal");
+        let wal = wal.to_str().unwrap();
+
+        for name in ["alice", "bob", "carol"] {
+            let db = open_database(args(&["--wal", wal, "--wal-checkpoint", "2"])).unwrap();
+            let mut session = db.session(false);
+            let output = alloc_shared(Output::new());
+            if name == "alice" {
+                session
+                    .interpret("CREATE COLLECTION users;", output.clone())
+                    .unwrap();
+            }
+            let insert = format!("insert into users values ({{name: \"{name}\"}});");
+            session.interpret(&insert, output).unwrap();
+        }
+
+        let db = open_database(args(&["--wal", wal, "--wal-sync", "none"])).unwrap();
+        let rows = db
+            .session(false)
+            .interpret(
+                "select u.name as name from users u;",
+                alloc_shared(Output::new()),
+            )
+            .unwrap();
+        assert!(matches!(rows, RV::Array(rows) if rows.len() == 3));
+    }
+
+    #[test]
+    fn test_rejects_invalid_wal_options() {
+        assert!(open_database(args(&["--wal"])).is_err());
+        assert!(open_database(args(&["--wal", "x.wal", "--wal-sync", "sometimes"])).is_err());
+        assert!(open_database(args(&["--wal", "x.wal", "--wal-checkpoint", "0"])).is_err());
+        assert!(open_database(args(&["--data-dir", "data", "--wal", "x.wal"])).is_err());
+    }
 }
@@ -1,7 +1,16 @@
+use std::sync::Arc;
+
+use crate::engine::Engine;
+use crate::engine::mvcc::{Snapshot, Transaction};
+use crate::execution::error::ExecutionError;
+use crate::execution::global::intern_string;
 use crate::interpreter::HaltReason;
+use crate::interpreter::environment::{EnvironmentFrame, EnvironmentOrigin};
+use crate::store::boxed::BoxedStore;
 use crate::value::RV;
 use crate::value::iterator::ExecutionRow;
 use crate::{execution::state::ProgramState, interpreter::expr::ExprEngine};
+use lykiadb_common::memory::Shared;
 use lykiadb_lang::ast::expr::Expr;
 
 #[derive(Clone)]
@@ -14,16 +23,80 @@ impl<'sess> QueryExecutionContext<'sess> {
         Self { state }
     }
 
+    /// Creates a context for a query nested in this one. The rows pushed to
+    /// this context stay visible to the nested query, while the rows of the
+    /// nested query do not leak out.
+    pub fn nested(&self) -> Self {
+        let mut state = self.state.clone();
+        state.env = Arc::new(EnvironmentFrame::new(
+            Some(Arc::clone(&self.state.env)),
+            EnvironmentOrigin::Query,
+        ));
+        Self { state }
+    }
+
     pub fn eval(&self, e: &Expr) -> Result<RV<'sess>, HaltReason<'sess>> {
         ExprEngine.eval(e, &self.state)
     }
 
+    pub fn engine(&self) -> &Shared<Engine<BoxedStore>> {
+        &self.state.engine
+    }
+
+    /// Runs a read of this query on the snapshot it should see. The engine
+    /// stays locked from taking the snapshot to the end of the read, as a
+    /// commit in between could prune the versions only that snapshot sees.
+    pub fn read<T>(&self, f: impl FnOnce(&Engine<BoxedStore>, Snapshot) -> T) -> T {
+        let transactions = self.state.transactions.read().unwrap();
+        let engine = self.state.engine.read().unwrap();
+        let snapshot = transactions.snapshot(&engine);
+        drop(transactions);
+        f(&engine, snapshot)
+    }
+
+    /// Runs the writes of this query in the session's transaction, or in a
+    /// transaction of their own when the session has none open.
+    pub fn write<T>(
+        &self,
+        f: impl FnOnce(&mut Engine<BoxedStore>, &Transaction) -> Result<T, ExecutionError>,
+    ) -> Result<T, ExecutionError> {
+        let mut transactions = self.state.transactions.write().unwrap();
+        transactions.write(&mut self.state.engine.write().unwrap(), f)
+    }
+
+    /// Runs a statement that writes what it reads, e.g. an UPDATE, in the
+    /// session's transaction, or in one of its own when the session has none
+    /// open. Its reads and writes are then at the same version, so a document
+    /// another session changed in between is a write conflict instead of a
+    /// lost update.
+    pub fn atomically<T>(
+        &self,
+        f: impl FnOnce() -> Result<T, ExecutionError>,
+    ) -> Result<T, ExecutionError> {
+        let opened = self
+            .state
+            .transactions
+            .write()
+            .unwrap()
+            .begin_statement(&mut self.state.engine.write().unwrap())?;
+        let result = f();
+        if opened {
+            let mut transactions = self.state.transactions.write().unwrap();
+            transactions.end_statement(&mut self.state.engine.write().unwrap(), result.is_ok())?;
+        }
+        result
+    }
+
     pub fn push_row(&self, row: &ExecutionRow<'sess>) {
         for (k, v) in row.keys.iter().zip(row.values.iter()) {
             self.state.env.define(*k, v.clone());
         }
     }
 
+    pub fn define(&self, key: &str, value: RV<'sess>) {
+        self.state.env.define(intern_string(key), value);
+    }
+
     pub fn pop_row(&self) {
         self.state.env.reset();
     }
@@ -1,4 +1,4 @@
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     execution::global::GLOBAL_INTERNER,
@@ -7,14 +7,24 @@ use crate::{
         context::QueryExecutionContext,
         plan::{Aggregation, IntermediateExpr},
     },
-    value::{RV, callable::Aggregator, iterator::ExecutionRow},
+    value::{
+        RV,
+        callable::Aggregator,
+        iterator::{ExecutionRow, RowKey},
+    },
 };
 
+struct GroupState<'v> {
+    aggregators: Vec<Box<dyn Aggregator<'v>>>,
+    // Values already fed to each DISTINCT aggregator of the group.
+    seen: Vec<FxHashSet<RowKey<'v>>>,
+}
+
 pub(crate) struct Grouper<'v, 'q> {
     group_exprs: Vec<IntermediateExpr<'v>>,
     aggregations: Vec<Aggregation<'v>>,
     exec_ctx: &'q QueryExecutionContext<'v>,
-    groups: FxHashMap<Vec<RV<'v>>, Vec<Box<dyn Aggregator<'v>>>>,
+    groups: FxHashMap<Vec<RV<'v>>, GroupState<'v>>,
 }
 
 impl<'v, 'q> Grouper<'v, 'q> {
@@ -48,7 +58,17 @@ impl<'v, 'q> Grouper<'v, 'q> {
                 aggregators.push(aggregation.callable.unwrap()())
             }
 
-            self.groups.insert(bucket.clone(), aggregators);
+            self.groups.insert(
+                bucket.clone(),
+                GroupState {
+                    aggregators,
+                    seen: self
+                        .aggregations
+                        .iter()
+                        .map(|_| FxHashSet::default())
+                        .collect(),
+                },
+            );
         }
 
         let bucket_value = self.groups.get_mut(&bucket).unwrap();
@@ -56,7 +76,11 @@ impl<'v, 'q> Grouper<'v, 'q> {
         for (idx, agg) in self.aggregations.iter().enumerate() {
             let val = self.exec_ctx.eval(&agg.args[0])?;
 
-            bucket_value[idx].as_mut().row(&val);
+            if agg.distinct && !bucket_value.seen[idx].insert(RowKey::from(val.clone())) {
+                continue;
+            }
+
+            bucket_value.aggregators[idx].as_mut().row(&val);
         }
 
         Ok(())
@@ -70,7 +94,7 @@ impl<'v, 'q> Grouper<'v, 'q> {
             for (idx, value) in bucket.iter().enumerate() {
                 row.insert(GLOBAL_INTERNER.intern(&format!("col_{idx}")), value.clone());
             }
-            for (idx, value) in agg.iter().enumerate() {
+            for (idx, value) in agg.aggregators.iter().enumerate() {
                 row.insert(
                     GLOBAL_INTERNER.intern(&self.aggregations[idx].call_sign),
                     value.finalize(),
@@ -0,0 +1,295 @@
+use std::sync::Arc;
+
+use interb::Symbol;
+use lykiadb_lang::ast::sql::SqlJoinType;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    query::{
+        context::QueryExecutionContext,
+        plan::{IntermediateExpr, JoinSide},
+    },
+    value::{
+        RV,
+        iterator::{ExecutionRow, RVs},
+    },
+};
+
+fn satisfies<'v>(
+    constraint: &Option<IntermediateExpr<'v>>,
+    row: &ExecutionRow<'v>,
+    exec_ctx: &QueryExecutionContext<'v>,
+) -> bool {
+    match constraint {
+        None => true,
+        Some(IntermediateExpr::Constant(ct)) => ct.to_bool(),
+        Some(IntermediateExpr::Expr { expr }) => {
+            exec_ctx.push_row(row);
+            let evaluated = exec_ctx.eval(expr);
+            exec_ctx.pop_row();
+            matches!(evaluated, Ok(value) if value.to_bool())
+        }
+    }
+}
+
+fn join_rows<'v>(left: &ExecutionRow<'v>, right: &ExecutionRow<'v>) -> ExecutionRow<'v> {
+    let mut joined = ExecutionRow::new();
+    left.copy_to(&mut joined);
+    right.copy_to(&mut joined);
+    joined
+}
+
+fn null_row<'v>(aliases: &[Symbol]) -> ExecutionRow<'v> {
+    let mut row = ExecutionRow::new();
+    for alias in aliases {
+        row.insert(*alias, RV::Null);
+    }
+    row
+}
+
+// Outer joins preserve every row of one side (the left side for LEFT JOIN,
+// the right side for RIGHT JOIN). Preserved rows without any match are
+// emitted once, with the aliases of the other side set to null. Joined rows
+// always keep the left side's fields first.
+pub(crate) fn outer_join<'v, 'q>(
+    left: RVs<'v, 'q>,
+    right: RVs<'v, 'q>,
+    join_type: &SqlJoinType,
+    constraint: &Option<IntermediateExpr<'v>>,
+    left_aliases: &[Symbol],
+    right_aliases: &[Symbol],
+    exec_ctx: &'q QueryExecutionContext<'v>,
+) -> Vec<ExecutionRow<'v>> {
+    let (preserved, other, padding, preserved_is_left): (RVs, RVs, &[Symbol], bool) =
+        match join_type {
+            SqlJoinType::Right => (right, left, left_aliases, false),
+            _ => (left, right, right_aliases, true),
+        };
+
+    let other: Vec<ExecutionRow<'v>> = other.collect();
+    let mut rows = vec![];
+
+    for row in preserved {
+        let mut matched = false;
+
+        for other_row in &other {
+            let joined = if preserved_is_left {
+                join_rows(&row, other_row)
+            } else {
+                join_rows(other_row, &row)
+            };
+
+            if satisfies(constraint, &joined, exec_ctx) {
+                matched = true;
+                rows.push(joined);
+            }
+        }
+
+        if !matched {
+            let nulls = null_row(padding);
+            rows.push(if preserved_is_left {
+                join_rows(&row, &nulls)
+            } else {
+                join_rows(&nulls, &row)
+            });
+        }
+    }
+
+    rows
+}
+
+// How a key value takes part in the hash table. Language equality coerces
+// numeric strings to numbers and compares booleans by truthiness, so values
+// are grouped by what they can be equal to: numbers and numeric strings
+// share buckets, booleans (and datatypes) may be equal to anything and are
+// matched against every row, and values that are never equal to anything
+// (objects, arrays, NaN, ...) never match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum JoinKey {
+    Num(u64),
+    Str(Arc<String>),
+    Null,
+    Undefined,
+}
+
+enum Bucket {
+    Key(Vec<JoinKey>),
+    Any,
+    Never,
+}
+
+fn bucket_of(values: &[RV]) -> Bucket {
+    let mut keys = Vec::with_capacity(values.len());
+    let mut any = false;
+
+    for value in values {
+        let key = match value {
+            RV::Double(n) if n.is_nan() => return Bucket::Never,
+            // -0.0 and 0.0 are equal but have different bits.
+            RV::Double(n) => JoinKey::Num((n + 0.0).to_bits()),
+            RV::Str(s) => match s.parse::<f64>() {
+                Ok(n) if !n.is_nan() => JoinKey::Num((n + 0.0).to_bits()),
+                _ => JoinKey::Str(s.clone()),
+            },
+            RV::Null => JoinKey::Null,
+            RV::Undefined => JoinKey::Undefined,
+            RV::Bool(_) | RV::Datatype(_) => {
+                any = true;
+                continue;
+            }
+            _ => return Bucket::Never,
+        };
+        keys.push(key);
+    }
+
+    if any { Bucket::Any } else { Bucket::Key(keys) }
+}
+
+fn eval_keys<'v>(
+    keys: &[&IntermediateExpr<'v>],
+    row: &ExecutionRow<'v>,
+    exec_ctx: &QueryExecutionContext<'v>,
+) -> Option<Vec<RV<'v>>> {
+    exec_ctx.push_row(row);
+    let values = keys
+        .iter()
+        .map(|key| match key {
+            IntermediateExpr::Constant(ct) => Ok(ct.clone()),
+            IntermediateExpr::Expr { expr } => exec_ctx.eval(expr),
+        })
+        .collect::<Result<Vec<_>, _>>();
+    exec_ctx.pop_row();
+    values.ok()
+}
+
+// Equi-joins build a hash table on the keys of one side, the one the planner
+// picked or else the smaller one, and probe it with the rows of the other.
+// Candidate pairs are confirmed with the language's own equality and the
+// residual predicate, so the result is the same as a nested loop over the
+// constraint, including the row order.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn hash_join<'v, 'q>(
+    left: RVs<'v, 'q>,
+    right: RVs<'v, 'q>,
+    join_type: &SqlJoinType,
+    keys: &[(IntermediateExpr<'v>, IntermediateExpr<'v>)],
+    residual: &Option<IntermediateExpr<'v>>,
+    build: Option<JoinSide>,
+    left_aliases: &[Symbol],
+    right_aliases: &[Symbol],
+    exec_ctx: &'q QueryExecutionContext<'v>,
+) -> Vec<ExecutionRow<'v>> {
+    let left: Vec<ExecutionRow<'v>> = left.collect();
+    let right: Vec<ExecutionRow<'v>> = right.collect();
+
+    let left_key_exprs: Vec<_> = keys.iter().map(|(l, _)| l).collect();
+    let right_key_exprs: Vec<_> = keys.iter().map(|(_, r)| r).collect();
+
+    let left_keys: Vec<_> = left
+        .iter()
+        .map(|row| eval_keys(&left_key_exprs, row, exec_ctx))
+        .collect();
+    let right_keys: Vec<_> = right
+        .iter()
+        .map(|row| eval_keys(&right_key_exprs, row, exec_ctx))
+        .collect();
+
+    let build_is_left = match build {
+        Some(side) => side == JoinSide::Left,
+        None => left.len() <= right.len(),
+    };
+    let (build_keys, probe_keys) = if build_is_left {
+        (&left_keys, &right_keys)
+    } else {
+        (&right_keys, &left_keys)
+    };
+
+    let mut table: FxHashMap<Vec<JoinKey>, Vec<usize>> = FxHashMap::default();
+    let mut wildcards: Vec<usize> = vec![];
+
+    for (idx, values) in build_keys.iter().enumerate() {
+        match values.as_deref().map(bucket_of) {
+            Some(Bucket::Key(key)) => table.entry(key).or_default().push(idx),
+            Some(Bucket::Any) => wildcards.push(idx),
+            Some(Bucket::Never) | None => {}
+        }
+    }
+
+    let all: Vec<usize> = (0..build_keys.len()).collect();
+    let mut pairs: Vec<(Option<usize>, Option<usize>)> = vec![];
+    let mut left_matched = vec![false; left.len()];
+    let mut right_matched = vec![false; right.len()];
+
+    for (probe_idx, values) in probe_keys.iter().enumerate() {
+        let Some(values) = values else {
+            continue;
+        };
+
+        let candidates = match bucket_of(values) {
+            Bucket::Key(key) => table
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .chain(wildcards.iter())
+                .collect::<Vec<_>>(),
+            Bucket::Any => all.iter().collect(),
+            Bucket::Never => continue,
+        };
+
+        for &build_idx in candidates {
+            let (l, r) = if build_is_left {
+                (build_idx, probe_idx)
+            } else {
+                (probe_idx, build_idx)
+            };
+
+            let (Some(lk), Some(rk)) = (&left_keys[l], &right_keys[r]) else {
+                continue;
+            };
+
+            if lk.iter().zip(rk.iter()).any(|(a, b)| a != b) {
+                continue;
+            }
+
+            if residual.is_some() && !satisfies(residual, &join_rows(&left[l], &right[r]), exec_ctx)
+            {
+                continue;
+            }
+
+            left_matched[l] = true;
+            right_matched[r] = true;
+            pairs.push((Some(l), Some(r)));
+        }
+    }
+
+    match join_type {
+        SqlJoinType::Left => pairs.extend(
+            (0..left.len())
+                .filter(|l| !left_matched[*l])
+                .map(|l| (Some(l), None)),
+        ),
+        SqlJoinType::Right => pairs.extend(
+            (0..right.len())
+                .filter(|r| !right_matched[*r])
+                .map(|r| (None, Some(r))),
+        ),
+        _ => {}
+    }
+
+    // Emit rows in the order a nested loop over the preserved side would.
+    if *join_type == SqlJoinType::Right {
+        pairs.sort_by_key(|(l, r)| (*r, *l));
+    } else {
+        pairs.sort();
+    }
+
+    pairs
+        .into_iter()
+        .map(|pair| match pair {
+            (Some(l), Some(r)) => join_rows(&left[l], &right[r]),
+            (Some(l), None) => join_rows(&left[l], &null_row(right_aliases)),
+            (None, Some(r)) => join_rows(&null_row(left_aliases), &right[r]),
+            (None, None) => unreachable!(),
+        })
+        .collect()
+}
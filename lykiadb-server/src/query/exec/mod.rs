@@ -1,27 +1,42 @@
+use std::{ops::Bound, time::Instant};
+
+use interb::Symbol;
 use itertools::Itertools;
+use rustc_hash::FxHashSet;
 
-use lykiadb_lang::ast::sql::SqlProjection;
+use lykiadb_lang::ast::{
+    Identifier,
+    sql::{SqlCompoundOperator, SqlJoinType, SqlOrdering, SqlProjection},
+};
 
 use crate::{
+    engine::{StoreId, error::EngineError, index::KeyRange},
     execution::error::ExecutionError,
     execution::global::GLOBAL_INTERNER,
-    interpreter::HaltReason,
+    interpreter::{HaltReason, error::InterpretError},
     query::{
         context::QueryExecutionContext,
-        exec::aggregation::Grouper,
+        exec::{aggregation::Grouper, profile::Profiler},
         plan::{IntermediateExpr, Node, Plan},
     },
     value::{
         RV,
-        iterator::{ExecutionRow, RVs},
+        array::RVArray,
+        iterator::{ExecutionRow, RVs, RowKey},
+        object::RVObject,
     },
 };
 
 pub mod aggregation;
+mod join;
+pub mod profile;
 
 crate::register_tests!("lykiadb-server/src/query/exec/tests");
 
-pub struct PlanExecutor;
+pub struct PlanExecutor {
+    // Measures the nodes as they run, for EXPLAIN ANALYZE.
+    profiler: Option<Profiler>,
+}
 
 impl<'v, 'q> Default for PlanExecutor {
     fn default() -> Self {
@@ -31,7 +46,13 @@ impl<'v, 'q> Default for PlanExecutor {
 
 impl<'v, 'q> PlanExecutor {
     pub fn new() -> PlanExecutor {
-        PlanExecutor
+        PlanExecutor { profiler: None }
+    }
+
+    pub fn profiled(profiler: Profiler) -> PlanExecutor {
+        PlanExecutor {
+            profiler: Some(profiler),
+        }
     }
 
     pub fn execute_plan(
@@ -45,6 +66,11 @@ impl<'v, 'q> PlanExecutor {
                 // Execute scan plan
                 self.execute_node(root.clone(), exec_ctx)
             }
+            // Mutations read the documents they write before returning, so
+            // both happen in the same transaction.
+            Plan::Insert(root) | Plan::Update(root) | Plan::Delete(root) => {
+                exec_ctx.atomically(|| self.execute_node(root, exec_ctx))
+            }
         }
     }
 
@@ -52,6 +78,30 @@ impl<'v, 'q> PlanExecutor {
         &mut self,
         node: Node<'v>,
         exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<RVs<'v, 'q>, ExecutionError> {
+        let Some(profiler) = self.profiler.clone() else {
+            return self.run_node(node, exec_ctx);
+        };
+
+        // Children run while their parent is being set up, and take the
+        // positions after it, whether or not they all get to run.
+        let position = profiler.position();
+        let size = node.size();
+        profiler.seek(position + 1);
+        let started = Instant::now();
+        let result = self.run_node(node, exec_ctx);
+        profiler.seek(position + size);
+        profiler.record(position, |node| {
+            node.loops += 1;
+            node.elapsed += started.elapsed();
+        });
+        Ok(profiler.measure(position, result?))
+    }
+
+    fn run_node(
+        &mut self,
+        node: Node<'v>,
+        exec_ctx: &'q QueryExecutionContext<'v>,
     ) -> Result<RVs<'v, 'q>, ExecutionError> {
         match node {
             Node::Subquery { source, alias } => {
@@ -75,11 +125,10 @@ impl<'v, 'q> PlanExecutor {
             Node::Filter {
                 source,
                 predicate,
-                subqueries: _,
+                subqueries,
             } => {
                 match predicate {
                     IntermediateExpr::Constant(ct) => {
-                        // TODO(vck): Maybe we can deal with this at compile time?
                         if ct.to_bool() {
                             let cursor = self.execute_node(*source, exec_ctx)?;
                             Ok(cursor)
@@ -89,25 +138,71 @@ impl<'v, 'q> PlanExecutor {
                         }
                     }
                     IntermediateExpr::Expr { expr } => {
+                        // Subqueries are listed after the source of the filter,
+                        // which is placed right after the filter itself.
+                        let profiler = self.profiler.clone();
+                        let mut position = profiler.as_ref().map_or(0, |p| p.position());
+                        position += source.size();
+                        let subqueries = subqueries.into_iter().map(|s| {
+                            let at = position;
+                            position += s.plan.size();
+                            (s, at)
+                        });
+                        let (correlated, uncorrelated): (Vec<_>, Vec<_>) =
+                            subqueries.partition(|(s, _)| s.correlated);
+
+                        // Uncorrelated subqueries do not depend on the row being
+                        // filtered, so they are run once and shared by all rows.
+                        let cached = uncorrelated
+                            .into_iter()
+                            .map(|(s, at)| {
+                                let value = run_subquery(s.plan, exec_ctx, profiler.as_ref(), at)?;
+                                Ok((s.sign, value))
+                            })
+                            .collect::<Result<Vec<_>, ExecutionError>>()?;
+
                         let cursor = self.execute_node(*source, exec_ctx)?;
 
-                        let iter = cursor.filter_map(move |row: ExecutionRow| {
-                            exec_ctx.push_row(&row);
-                            let evaluated = &exec_ctx.eval(&expr);
-                            exec_ctx.pop_row();
-                            if let Ok(value) = evaluated
-                                && value.to_bool()
-                            {
-                                Some(row)
-                            } else {
-                                None
+                        let lazy = correlated.is_empty();
+                        let keep = move |row: &ExecutionRow<'v>| {
+                            exec_ctx.push_row(row);
+                            for (sign, value) in &cached {
+                                exec_ctx.define(sign, value.clone());
                             }
-                        });
+                            let resolved = correlated.iter().try_for_each(|(s, at)| {
+                                let value =
+                                    run_subquery(s.plan.clone(), exec_ctx, profiler.as_ref(), *at)?;
+                                exec_ctx.define(&s.sign, value);
+                                Ok::<_, ExecutionError>(())
+                            });
+                            let evaluated = resolved.map(|()| exec_ctx.eval(&expr));
+                            exec_ctx.pop_row();
+                            Ok::<_, ExecutionError>(evaluated?.is_ok_and(|value| value.to_bool()))
+                        };
+
+                        if lazy {
+                            return Ok(Box::from(
+                                cursor.filter(move |row| keep(row).unwrap_or(false)),
+                            ));
+                        }
 
-                        Ok(Box::from(iter))
+                        // Correlated subqueries are run for every row up front, so
+                        // that an error in one of them fails the query instead of
+                        // dropping the row.
+                        let mut rows = vec![];
+                        for row in cursor {
+                            if keep(&row)? {
+                                rows.push(row);
+                            }
+                        }
+                        Ok(Box::from(rows.into_iter()))
                     }
                 }
             }
+            Node::Distinct { source } => {
+                let cursor = self.execute_node(*source, exec_ctx)?;
+                Ok(dedup(cursor))
+            }
             Node::Projection { source, fields } => {
                 let cursor = self.execute_node(*source, exec_ctx)?;
 
@@ -149,12 +244,12 @@ impl<'v, 'q> PlanExecutor {
 
                 Ok(Box::from(iter))
             }
-            Node::EvalScan { source, filter: _ } => match exec_ctx.eval(&source.expr) {
+            Node::EvalScan { source, filter } => match exec_ctx.eval(&source.expr) {
                 Err(HaltReason::Error(err)) => Err(err),
                 Err(HaltReason::Return(value)) | Ok(value) => {
-                    let alias = source.alias.to_owned();
+                    let alias = source.alias.to_string();
 
-                    let sym_alias = GLOBAL_INTERNER.intern(&alias.to_string());
+                    let sym_alias = GLOBAL_INTERNER.intern(&alias);
 
                     let mapper = move |v: RV<'v>| {
                         let mut env = ExecutionRow::new();
@@ -162,18 +257,16 @@ impl<'v, 'q> PlanExecutor {
                         env
                     };
 
-                    let iter = match value {
-                        RV::Array(arr) => {
-                            let c = arr.collect();
-                            c.into_iter().map(mapper)
-                        }
-                        _ => vec![value]
-                            .into_iter()
-                            .collect::<Vec<_>>()
-                            .into_iter()
-                            .map(mapper),
+                    let values = match value {
+                        RV::Array(arr) => arr.collect(),
+                        _ => vec![value],
                     };
 
+                    let iter = values
+                        .into_iter()
+                        .filter(move |v| passes(&filter, &alias, v, exec_ctx))
+                        .map(mapper);
+
                     Ok(Box::from(iter))
                 }
             },
@@ -200,18 +293,66 @@ impl<'v, 'q> PlanExecutor {
 
                 Ok(Box::from(rows.into_iter()))
             }
+            Node::HashJoin {
+                left,
+                join_type,
+                right,
+                keys,
+                residual,
+                build,
+            } => {
+                let left_aliases = intern_aliases(&left);
+                let right_aliases = intern_aliases(&right);
+
+                let left_cursor = self.execute_node(*left, exec_ctx)?;
+                let right_cursor = self.execute_node(*right, exec_ctx)?;
+
+                let rows = join::hash_join(
+                    left_cursor,
+                    right_cursor,
+                    &join_type,
+                    &keys,
+                    &residual,
+                    build,
+                    &left_aliases,
+                    &right_aliases,
+                    exec_ctx,
+                );
+
+                Ok(Box::from(rows.into_iter()))
+            }
+
             Node::Join {
                 left,
-                join_type: _,
+                join_type,
                 right,
                 constraint,
             } => {
-                if let Some(IntermediateExpr::Constant(ct)) = &constraint {
-                    // TODO(vck): Maybe we can deal with this at compile time?
-                    if !ct.to_bool() {
-                        let empty_iter = Vec::<ExecutionRow<'v>>::new().into_iter();
-                        return Ok(Box::from(empty_iter) as RVs);
-                    }
+                if matches!(join_type, SqlJoinType::Left | SqlJoinType::Right) {
+                    let left_aliases = intern_aliases(&left);
+                    let right_aliases = intern_aliases(&right);
+
+                    let left_cursor = self.execute_node(*left, exec_ctx)?;
+                    let right_cursor = self.execute_node(*right, exec_ctx)?;
+
+                    let rows = join::outer_join(
+                        left_cursor,
+                        right_cursor,
+                        &join_type,
+                        &constraint,
+                        &left_aliases,
+                        &right_aliases,
+                        exec_ctx,
+                    );
+
+                    return Ok(Box::from(rows.into_iter()));
+                }
+
+                if let Some(IntermediateExpr::Constant(ct)) = &constraint
+                    && !ct.to_bool()
+                {
+                    let empty_iter = Vec::<ExecutionRow<'v>>::new().into_iter();
+                    return Ok(Box::from(empty_iter) as RVs);
                 }
 
                 let left_cursor = self.execute_node(*left, exec_ctx)?;
@@ -243,21 +384,416 @@ impl<'v, 'q> PlanExecutor {
 
                 Ok(Box::from(product))
             }
-            Node::Order { source: _, key: _ } => todo!(),
-            Node::Scan {
-                source: _,
-                filter: _,
-            } => todo!(),
+            Node::Order { source, key } => {
+                let cursor = self.execute_node(*source, exec_ctx)?;
+
+                let mut keyed: Vec<(Vec<RV<'v>>, ExecutionRow<'v>)> = cursor
+                    .map(|row: ExecutionRow| {
+                        exec_ctx.push_row(&row);
+                        let values = key
+                            .iter()
+                            .map(|(expr, _)| {
+                                self.eval_intermediate(expr, exec_ctx)
+                                    .unwrap_or(RV::Undefined)
+                            })
+                            .collect();
+                        exec_ctx.pop_row();
+                        (values, row)
+                    })
+                    .collect();
+
+                // sort_by is stable, rows with equal keys keep their order.
+                keyed.sort_by(|(a, _), (b, _)| {
+                    a.iter()
+                        .zip(b.iter())
+                        .zip(key.iter())
+                        .map(|((a, b), (_, ordering))| match ordering {
+                            SqlOrdering::Asc => a.total_cmp(b),
+                            SqlOrdering::Desc => b.total_cmp(a),
+                        })
+                        .find(|ord| ord.is_ne())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+
+                Ok(Box::from(keyed.into_iter().map(|(_, row)| row)))
+            }
+            Node::Scan { source, filter, .. } => {
+                let sid = StoreId::from(&source);
+
+                let alias = source.alias.as_ref().unwrap_or(&source.name).to_string();
+                let sym_alias = GLOBAL_INTERNER.intern(&alias);
+
+                // The engine lock shouldn't outlive the scan, so the documents
+                // are decoded eagerly, and filtered once the lock is released.
+                let documents = exec_ctx.read(|engine, snapshot| {
+                    engine
+                        .scan_documents_in(snapshot, &sid)?
+                        .collect::<Result<Vec<(String, RV<'v>)>, _>>()
+                        .map_err(ExecutionError::from)
+                })?;
+
+                let rows = documents
+                    .into_iter()
+                    .filter(|(_, value)| passes(&filter, &alias, value, exec_ctx))
+                    .map(|(key, value)| {
+                        let mut row = ExecutionRow::new();
+                        row.insert(sym_alias, value);
+                        row.doc_key = Some(key);
+                        row
+                    })
+                    .collect::<Vec<ExecutionRow<'v>>>();
+
+                Ok(Box::from(rows.into_iter()))
+            }
+            Node::IndexScan {
+                source,
+                index,
+                ranges,
+                ..
+            } => {
+                let sid = StoreId::from(&source);
+
+                let alias = source.alias.as_ref().unwrap_or(&source.name);
+                let sym_alias = GLOBAL_INTERNER.intern(&alias.to_string());
+
+                let mut key_ranges = vec![];
+                for range in ranges {
+                    let mut lower = self.eval_bound(&range.lower, exec_ctx)?;
+                    let mut upper = self.eval_bound(&range.upper, exec_ctx)?;
+                    // BETWEEN accepts its bounds in either order.
+                    if let (Bound::Included(l), Bound::Included(u)) = (&lower, &upper)
+                        && l > u
+                    {
+                        std::mem::swap(&mut lower, &mut upper);
+                    }
+                    for key_range in KeyRange::covering(lower.as_ref(), upper.as_ref()) {
+                        if !key_ranges.contains(&key_range) {
+                            key_ranges.push(key_range);
+                        }
+                    }
+                }
+
+                let rows = exec_ctx
+                    .read(|engine, snapshot| {
+                        engine.scan_index_in(snapshot, &sid, &index, &key_ranges)
+                    })?
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let mut row = ExecutionRow::new();
+                        row.insert(sym_alias, value);
+                        row.doc_key = Some(key);
+                        row
+                    })
+                    .collect::<Vec<ExecutionRow<'v>>>();
+
+                Ok(Box::from(rows.into_iter()))
+            }
+            Node::Values { values } => {
+                let mut rows = vec![];
+
+                for value in values {
+                    let evaluated = self.eval_intermediate(&value, exec_ctx)?;
+
+                    let Some(obj) = evaluated.extract_object() else {
+                        return Err(ExecutionError::Engine(EngineError::InvalidValue));
+                    };
+
+                    let mut row = ExecutionRow::new();
+                    for (key, v) in obj.iter() {
+                        row.insert(GLOBAL_INTERNER.intern(&key), v);
+                    }
+                    rows.push(row);
+                }
+
+                Ok(Box::from(rows.into_iter()))
+            }
+            Node::Insert { collection, source } => {
+                let sid = StoreId::from(&collection);
+
+                // Source rows are materialized before taking the write lock,
+                // since the source may be reading the very same collection.
+                let documents: Vec<RV<'v>> = self
+                    .execute_node(*source, exec_ctx)?
+                    .map(|row| row.as_value())
+                    .collect();
+
+                exec_ctx.write(|engine, txn| {
+                    for document in &documents {
+                        engine.insert_in(txn, &sid, document.clone())?;
+                    }
+                    Ok(())
+                })?;
+
+                let mut summary = ExecutionRow::new();
+                summary.insert(
+                    GLOBAL_INTERNER.intern("inserted"),
+                    RV::Int64(documents.len() as i64),
+                );
+
+                Ok(Box::from(vec![summary].into_iter()))
+            }
+            Node::Update {
+                collection,
+                assignments,
+                source,
+            } => {
+                let sid = StoreId::from(&collection);
+
+                let rows: Vec<ExecutionRow<'v>> = self.execute_node(*source, exec_ctx)?.collect();
+
+                // The assigned values are computed upfront, against the
+                // documents as they were before the update.
+                let mut changes = vec![];
+
+                for row in rows {
+                    let Some(key) = row.doc_key.clone() else {
+                        continue;
+                    };
+
+                    exec_ctx.push_row(&row);
+                    let values = assignments
+                        .iter()
+                        .map(|assignment| self.eval_intermediate(&assignment.value, exec_ctx))
+                        .collect::<Result<Vec<RV<'v>>, ExecutionError>>();
+                    exec_ctx.pop_row();
+
+                    changes.push((key, values?));
+                }
+
+                let updated = exec_ctx.write(|engine, txn| {
+                    let mut updated = 0;
+
+                    for (key, values) in changes {
+                        // A fresh copy of the document is read, as objects are
+                        // shared by reference and the assigned values might
+                        // point into the scanned one.
+                        let Some(mut document) = engine.get_in(txn.snapshot(), &sid, &key)? else {
+                            continue;
+                        };
+                        for (assignment, value) in assignments.iter().zip(values) {
+                            assign_path(&mut document, &assignment.path, value)?;
+                        }
+                        engine.update_in(txn, &sid, &key, document)?;
+                        updated += 1;
+                    }
+
+                    Ok(updated)
+                })?;
+
+                let mut summary = ExecutionRow::new();
+                summary.insert(GLOBAL_INTERNER.intern("updated"), RV::Int64(updated));
+
+                Ok(Box::from(vec![summary].into_iter()))
+            }
+            Node::Delete { collection, source } => {
+                let sid = StoreId::from(&collection);
+
+                let keys: Vec<String> = self
+                    .execute_node(*source, exec_ctx)?
+                    .filter_map(|row| row.doc_key)
+                    .collect();
+
+                exec_ctx.write(|engine, txn| {
+                    for key in &keys {
+                        engine.delete_in(txn, &sid, key)?;
+                    }
+                    Ok(())
+                })?;
+
+                let mut summary = ExecutionRow::new();
+                summary.insert(
+                    GLOBAL_INTERNER.intern("deleted"),
+                    RV::Int64(keys.len() as i64),
+                );
+
+                Ok(Box::from(vec![summary].into_iter()))
+            }
             Node::Compound {
-                source: _,
-                operator: _,
-                right: _,
-            } => todo!(),
+                source,
+                operator,
+                right,
+            } => {
+                let left_cursor = self.execute_node(*source, exec_ctx)?;
+                let right_cursor = self.execute_node(*right, exec_ctx)?;
+
+                // UNION ALL doesn't need to see all the rows upfront
+                if operator == SqlCompoundOperator::UnionAll {
+                    return Ok(Box::from(left_cursor.chain(right_cursor)));
+                }
+
+                let rows = combine(&operator, left_cursor, right_cursor);
+
+                Ok(Box::from(rows.into_iter()))
+            }
+            Node::Empty { .. } => {
+                let empty_iter = Vec::<ExecutionRow<'v>>::new().into_iter();
+                Ok(Box::from(empty_iter) as RVs)
+            }
             Node::Nothing => todo!(),
         }
     }
 }
 
+impl<'v> PlanExecutor {
+    fn eval_bound(
+        &self,
+        bound: &Bound<IntermediateExpr<'v>>,
+        exec_ctx: &QueryExecutionContext<'v>,
+    ) -> Result<Bound<RV<'v>>, ExecutionError> {
+        Ok(match bound {
+            Bound::Included(expr) => Bound::Included(self.eval_intermediate(expr, exec_ctx)?),
+            Bound::Excluded(expr) => Bound::Excluded(self.eval_intermediate(expr, exec_ctx)?),
+            Bound::Unbounded => Bound::Unbounded,
+        })
+    }
+
+    fn eval_intermediate(
+        &self,
+        expr: &IntermediateExpr<'v>,
+        exec_ctx: &QueryExecutionContext<'v>,
+    ) -> Result<RV<'v>, ExecutionError> {
+        match expr {
+            IntermediateExpr::Constant(ct) => Ok(ct.clone()),
+            IntermediateExpr::Expr { expr } => match exec_ctx.eval(expr) {
+                Err(HaltReason::Error(err)) => Err(err),
+                Err(HaltReason::Return(v)) | Ok(v) => Ok(v),
+            },
+        }
+    }
+}
+
+// Whether a value read by a scan passes the scan's filter. The value is
+// visible to the filter under the alias of the scan.
+fn passes<'v>(
+    filter: &Option<IntermediateExpr<'v>>,
+    alias: &str,
+    value: &RV<'v>,
+    exec_ctx: &QueryExecutionContext<'v>,
+) -> bool {
+    let Some(filter) = filter else {
+        return true;
+    };
+    exec_ctx.define(alias, value.clone());
+    let evaluated = PlanExecutor::new().eval_intermediate(filter, exec_ctx);
+    exec_ctx.pop_row();
+    evaluated.is_ok_and(|value| value.to_bool())
+}
+
+fn intern_aliases(node: &Node) -> Vec<Symbol> {
+    node.aliases()
+        .iter()
+        .map(|alias| GLOBAL_INTERNER.intern(alias))
+        .collect()
+}
+
+// Runs a subquery in a context nested in `exec_ctx`, so that it can refer
+// to the rows of the enclosing query, and collects its rows into an array.
+// Subqueries of a profiled plan are measured as the nodes at `position`
+// onwards.
+fn run_subquery<'v>(
+    plan: Node<'v>,
+    exec_ctx: &QueryExecutionContext<'v>,
+    profiler: Option<&Profiler>,
+    position: usize,
+) -> Result<RV<'v>, ExecutionError> {
+    let nested = exec_ctx.nested();
+    let run = || {
+        let rows: Vec<RV<'v>> = PlanExecutor {
+            profiler: profiler.cloned(),
+        }
+        .execute_node(plan, &nested)?
+        .map(|row| row.as_value())
+        .collect();
+        Ok(RV::Array(RVArray::from_vec(rows)))
+    };
+    match profiler {
+        Some(profiler) => profiler.at(position, run),
+        None => run(),
+    }
+}
+
+// Rows are compared by their values, positionally. Except UNION ALL,
+// compound operators have set semantics, so the result is free of
+// duplicates.
+//
+// RowKey wraps objects, which are interior mutable, but the keys are
+// never mutated while in the sets.
+#[allow(clippy::mutable_key_type)]
+fn combine<'v, 'q>(
+    operator: &SqlCompoundOperator,
+    left: RVs<'v, 'q>,
+    right: RVs<'v, 'q>,
+) -> Vec<ExecutionRow<'v>> {
+    let mut seen: FxHashSet<RowKey<'v>> = FxHashSet::default();
+
+    match operator {
+        SqlCompoundOperator::UnionAll => left.chain(right).collect(),
+        SqlCompoundOperator::Union => left
+            .chain(right)
+            .filter(|row| seen.insert(row.to_row_key()))
+            .collect(),
+        SqlCompoundOperator::Intersect | SqlCompoundOperator::Except => {
+            let keep = *operator == SqlCompoundOperator::Intersect;
+            let right_keys: FxHashSet<RowKey<'v>> = right.map(|row| row.to_row_key()).collect();
+            left.filter(|row| {
+                let key = row.to_row_key();
+                right_keys.contains(&key) == keep && seen.insert(key)
+            })
+            .collect()
+        }
+    }
+}
+
+// Drops the rows that were already seen, compared the same way as above.
+#[allow(clippy::mutable_key_type)]
+fn dedup<'v: 'q, 'q>(rows: RVs<'v, 'q>) -> RVs<'v, 'q> {
+    let mut seen: FxHashSet<RowKey<'v>> = FxHashSet::default();
+    Box::from(rows.filter(move |row| seen.insert(row.to_row_key())))
+}
+
+// Writes the value to the given path within the document. Missing
+// intermediate fields are created as empty objects.
+fn assign_path<'v>(
+    document: &mut RV<'v>,
+    path: &[Identifier],
+    value: RV<'v>,
+) -> Result<(), ExecutionError> {
+    let (last, parents) = path.split_last().expect("Assignment path can't be empty");
+
+    let mut current = document.clone();
+
+    for field in parents {
+        let RV::Object(mut obj) = current else {
+            return Err(InterpretError::InvalidPropertyAccess {
+                span: field.span,
+                value_str: current.to_string(),
+            }
+            .into());
+        };
+
+        current = match obj.get(&field.name) {
+            Some(inner) => inner,
+            None => {
+                let inner = RV::Object(RVObject::new());
+                obj.insert(field.name.clone(), inner.clone());
+                inner
+            }
+        };
+    }
+
+    let RV::Object(mut obj) = current else {
+        return Err(InterpretError::InvalidPropertyAccess {
+            span: last.span,
+            value_str: current.to_string(),
+        }
+        .into());
+    };
+
+    obj.insert(last.name.clone(), value);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1019,4 +1555,128 @@ mod tests {
 
         Ok(())
     }
+
+    fn create_test_collection(
+        namespace: Option<&str>,
+        name: &str,
+        alias: Option<&str>,
+    ) -> lykiadb_lang::ast::sql::SqlCollectionIdentifier {
+        lykiadb_lang::ast::sql::SqlCollectionIdentifier {
+            namespace: namespace.map(|ns| Identifier::new(ns, IdentifierKind::Symbol)),
+            name: Identifier::new(name, IdentifierKind::Symbol),
+            alias: alias.map(|a| Identifier::new(a, IdentifierKind::Symbol)),
+        }
+    }
+
+    fn create_test_document(id: i32) -> RV<'static> {
+        let mut obj = crate::value::object::RVObject::new();
+        obj.insert("id".to_string(), RV::Int32(id));
+        RV::Object(obj)
+    }
+
+    #[test]
+    fn test_execute_node_scan_reads_collection() -> Result<(), ExecutionError> {
+        let (mut executor, exec_ctx) = create_test_executor();
+
+        {
+            let mut engine = exec_ctx.engine().write().unwrap();
+            let sid = StoreId::new(Some("db"), "users");
+            let other = StoreId::new(None, "users");
+            engine.create_collection(&sid)?;
+            engine.create_collection(&other)?;
+            engine.set(&sid, "a", create_test_document(1))?;
+            engine.set(&sid, "b", create_test_document(2))?;
+            // Documents of other collections must not leak into the scan
+            engine.set(&other, "c", create_test_document(3))?;
+        }
+
+        let node = Node::Scan {
+            source: create_test_collection(Some("db"), "users", Some("u")),
+            filter: None,
+            statistics: None,
+        };
+
+        let rows: Vec<ExecutionRow> = executor.execute_node(node, exec_ctx)?.collect();
+        assert_eq!(rows.len(), 2);
+
+        let symbol = GLOBAL_INTERNER.intern("u");
+        let ids: Vec<RV> = rows
+            .iter()
+            .map(|row| {
+                row.get(&symbol)
+                    .unwrap()
+                    .extract_object()
+                    .unwrap()
+                    .get("id")
+                    .unwrap()
+            })
+            .collect();
+        assert!(matches!(ids.as_slice(), [RV::Int32(1), RV::Int32(2)]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_node_scan_defaults_alias_to_name() -> Result<(), ExecutionError> {
+        let (mut executor, exec_ctx) = create_test_executor();
+
+        {
+            let mut engine = exec_ctx.engine().write().unwrap();
+            let sid = StoreId::new(None, "books");
+            engine.create_collection(&sid)?;
+            engine.set(&sid, "a", create_test_document(1))?;
+        }
+
+        let node = Node::Scan {
+            source: create_test_collection(None, "books", None),
+            filter: None,
+            statistics: None,
+        };
+
+        let rows: Vec<ExecutionRow> = executor.execute_node(node, exec_ctx)?.collect();
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].get(&GLOBAL_INTERNER.intern("books")).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_node_scan_empty_collection() -> Result<(), ExecutionError> {
+        let (mut executor, exec_ctx) = create_test_executor();
+
+        exec_ctx
+            .engine()
+            .write()
+            .unwrap()
+            .create_collection(&StoreId::new(None, "nothing_here"))?;
+
+        let node = Node::Scan {
+            source: create_test_collection(None, "nothing_here", None),
+            filter: None,
+            statistics: None,
+        };
+
+        let rows: Vec<ExecutionRow> = executor.execute_node(node, exec_ctx)?.collect();
+        assert!(rows.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_node_scan_unknown_collection() {
+        let (mut executor, exec_ctx) = create_test_executor();
+
+        let node = Node::Scan {
+            source: create_test_collection(None, "nothing_here", None),
+            filter: None,
+            statistics: None,
+        };
+
+        assert_eq!(
+            executor.execute_node(node, exec_ctx).err(),
+            Some(ExecutionError::Engine(EngineError::CollectionNotFound {
+                collection: "default.nothing_here".to_string()
+            }))
+        );
+    }
 }
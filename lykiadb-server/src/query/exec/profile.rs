@@ -0,0 +1,210 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use lykiadb_common::memory::{Shared, alloc_shared};
+
+use crate::{
+    query::plan::Node,
+    value::iterator::{ExecutionRow, RVs},
+};
+
+/// What a node of a plan did while the plan was run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeProfile {
+    pub rows: u64,
+    // Number of times the node was run, e.g. once per row for the nodes of
+    // a correlated subquery.
+    pub loops: u64,
+    // Time spent in the node and its children.
+    pub elapsed: Duration,
+}
+
+/// Measures the nodes of a plan as it runs, for EXPLAIN ANALYZE. Nodes are
+/// told apart by their position in the plan, in the order EXPLAIN lists
+/// them, which is the order the executor runs them in.
+#[derive(Clone, Debug)]
+pub struct Profiler {
+    nodes: Shared<Vec<NodeProfile>>,
+    // Position of the next node to run.
+    next: Arc<AtomicUsize>,
+}
+
+impl Profiler {
+    pub fn new(root: &Node) -> Self {
+        Profiler {
+            nodes: alloc_shared(vec![NodeProfile::default(); root.size()]),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The measures of the nodes, by position.
+    pub fn finish(&self) -> Vec<NodeProfile> {
+        self.nodes.read().unwrap().clone()
+    }
+
+    pub(super) fn position(&self) -> usize {
+        self.next.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn seek(&self, position: usize) {
+        self.next.store(position, Ordering::Relaxed);
+    }
+
+    /// Runs `f` with the next node placed at `position`, e.g. for a nested
+    /// query that runs apart from the nodes around it.
+    pub(super) fn at<T>(&self, position: usize, f: impl FnOnce() -> T) -> T {
+        let saved = self.position();
+        self.seek(position);
+        let result = f();
+        self.seek(saved);
+        result
+    }
+
+    pub(super) fn record(&self, position: usize, f: impl FnOnce(&mut NodeProfile)) {
+        if let Some(node) = self.nodes.write().unwrap().get_mut(position) {
+            f(node);
+        }
+    }
+
+    /// Counts the rows of the cursor, along with the time spent producing
+    /// them, towards the node at `position`.
+    pub(super) fn measure<'v: 'q, 'q>(&self, position: usize, cursor: RVs<'v, 'q>) -> RVs<'v, 'q> {
+        Box::new(Measured {
+            cursor,
+            position,
+            profiler: self.clone(),
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Measured<'v, 'q> {
+    cursor: RVs<'v, 'q>,
+    position: usize,
+    profiler: Profiler,
+}
+
+impl<'v> Iterator for Measured<'v, '_> {
+    type Item = ExecutionRow<'v>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let started = Instant::now();
+        let row = self.cursor.next();
+        self.profiler.record(self.position, |node| {
+            node.elapsed += started.elapsed();
+            node.rows += u64::from(row.is_some());
+        });
+        row
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lykiadb_common::memory::alloc_shared;
+
+    use crate::{execution::session::Session, interpreter::output::Output, value::RV};
+
+    fn run(source: &str) -> RV<'static> {
+        let mut session = Session::new(false);
+        session
+            .interpret(source, alloc_shared(Output::new()))
+            .unwrap()
+    }
+
+    fn field(node: &RV<'static>, path: &[&str]) -> RV<'static> {
+        path.iter().fold(node.clone(), |node, key| match node {
+            RV::Object(object) => object.get(key).unwrap(),
+            _ => panic!("{key} is not in {node:?}"),
+        })
+    }
+
+    // The rows and loops of the node.
+    fn measures(node: &RV<'static>) -> (i64, i64) {
+        match (field(node, &["actual_rows"]), field(node, &["loops"])) {
+            (RV::Int64(rows), RV::Int64(loops)) => (rows, loops),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_nodes_count_the_rows_they_return() {
+        let plan = run("create collection users;
+            insert into users values ({id: 1, age: 30}, {id: 2, age: 25}, {id: 3, age: 40});
+            EXPLAIN ANALYZE SELECT u.id FROM users u WHERE u.age > 26 ORDER BY u.id LIMIT 1;");
+
+        assert_eq!(
+            field(&plan, &["@type"]),
+            RV::Str("limit".to_string().into())
+        );
+        assert_eq!(measures(&plan), (1, 1));
+        // Rows are pulled from the order as the limit needs them.
+        assert_eq!(measures(&field(&plan, &["source"])), (1, 1));
+        assert_eq!(measures(&field(&plan, &["source", "source"])), (2, 1));
+        assert_eq!(
+            measures(&field(&plan, &["source", "source", "source"])),
+            (2, 1)
+        );
+        assert!(matches!(
+            field(&plan, &["source", "source", "source", "elapsed_ms"]),
+            RV::Double(ms) if ms >= 0.0
+        ));
+    }
+
+    #[test]
+    fn test_correlated_subqueries_loop_once_per_row() {
+        let plan = run("create collection users;
+            create collection orders;
+            insert into users values ({id: 1}, {id: 2}, {id: 3});
+            insert into orders values ({user_id: 1}, {user_id: 1}, {user_id: 3});
+            EXPLAIN ANALYZE SELECT u.id FROM users u
+                WHERE exists (SELECT o.user_id FROM orders o WHERE o.user_id = u.id);");
+
+        let filter = field(&plan, &["source"]);
+        assert_eq!(measures(&filter), (2, 1));
+        assert_eq!(measures(&field(&filter, &["source"])), (3, 1));
+
+        let RV::Array(queries) = field(&filter, &["subqueries", "queries"]) else {
+            panic!("{filter:?}");
+        };
+        let subquery = field(&queries.get(0), &["source"]);
+        assert_eq!(measures(&subquery), (3, 3));
+        assert_eq!(measures(&field(&subquery, &["source", "source"])), (9, 3));
+    }
+
+    #[test]
+    fn test_mutations_are_run_and_measured() {
+        let mut session = Session::new(false);
+        let plan = session
+            .interpret(
+                "create collection users;
+                insert into users values ({age: 10}, {age: 20}, {age: 30});
+                EXPLAIN ANALYZE DELETE FROM users u WHERE u.age > 15;",
+                alloc_shared(Output::new()),
+            )
+            .unwrap();
+
+        assert_eq!(measures(&plan), (1, 1));
+        assert_eq!(measures(&field(&plan, &["source"])), (2, 1));
+
+        let left = session
+            .interpret(
+                "SELECT u.age as age FROM users u;",
+                alloc_shared(Output::new()),
+            )
+            .unwrap();
+        assert!(matches!(left, RV::Array(rows) if rows.len() == 1));
+    }
+
+    #[test]
+    fn test_explain_without_analyze_does_not_measure() {
+        let plan = run("create collection users;
+            EXPLAIN SELECT * FROM users u;");
+
+        assert!(matches!(&plan, RV::Object(object) if !object.contains_key("actual_rows")));
+    }
+}
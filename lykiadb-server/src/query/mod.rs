@@ -1,10 +1,12 @@
-use lykiadb_lang::ast::expr::Expr;
+use lykiadb_lang::ast::{Spanned, expr::Expr};
 
 use crate::{
+    engine::error::EngineError,
+    execution::error::ExecutionError,
     interpreter::HaltReason,
     query::{
         context::QueryExecutionContext,
-        exec::PlanExecutor,
+        exec::{PlanExecutor, profile::Profiler},
         plan::{Plan, planner::Planner},
     },
     value::{RV, array::RVArray, iterator::ExecutionRow},
@@ -39,16 +41,23 @@ impl<'q> QueryEngine {
         exec_ctx: &'q QueryExecutionContext<'v>,
     ) -> Result<RV<'v>, HaltReason<'v>> {
         let plan = self.planner.build(e, exec_ctx)?;
+        let returns_rows = matches!(plan, Plan::Select(_));
         let result = self.executor.execute_plan(plan, exec_ctx);
 
         match result {
-            Err(e) => Err(HaltReason::Error(e)),
-            Ok(cursor) => {
+            Err(error) => Err(halt_at(e, error)),
+            Ok(cursor) if returns_rows => {
                 let intermediate = cursor
                     .map(|row: ExecutionRow| row.as_value())
                     .collect::<Vec<RV>>();
                 Ok(RV::Array(RVArray::from_vec(intermediate)))
             }
+            // Mutations yield a single summary row, such as the number of
+            // affected documents.
+            Ok(mut cursor) => Ok(cursor
+                .next()
+                .map(|row: ExecutionRow| row.as_value())
+                .unwrap_or(RV::Undefined)),
         }
     }
 
@@ -59,4 +68,35 @@ impl<'q> QueryEngine {
     ) -> Result<Plan<'v>, HaltReason<'v>> {
         self.planner.build(e, exec_ctx)
     }
+
+    /// Runs the query, and describes its plan along with what each node of
+    /// the plan did. The rows of the query are dropped, while the changes of
+    /// an INSERT, UPDATE or DELETE are made as usual.
+    pub fn explain_analyze<'v>(
+        &mut self,
+        e: &Expr,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<RV<'v>, HaltReason<'v>> {
+        let plan = self.planner.build(e, exec_ctx)?;
+        let profiler = Profiler::new(plan.root());
+        PlanExecutor::profiled(profiler.clone())
+            .execute_plan(plan.clone(), exec_ctx)
+            .map_err(|error| halt_at(e, error))?
+            .for_each(drop);
+        Ok(plan.to_analyzed_object(&profiler.finish()))
+    }
+}
+
+fn halt_at<'v>(e: &Expr, error: ExecutionError) -> HaltReason<'v> {
+    match error {
+        // The engine does not know which statement wrote the offending
+        // document.
+        ExecutionError::Engine(EngineError::ConstraintViolation { constraint, .. }) => {
+            HaltReason::Error(ExecutionError::Engine(EngineError::ConstraintViolation {
+                constraint,
+                span: e.get_span(),
+            }))
+        }
+        error => HaltReason::Error(error),
+    }
 }
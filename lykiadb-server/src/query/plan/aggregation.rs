@@ -113,9 +113,23 @@ impl<'a, 'v> AggregationCollector<'a, 'v> {
 
 impl<'a, 'v> ExprReducer<Aggregation<'v>, HaltReason<'v>> for AggregationCollector<'a, 'v> {
     fn visit(&mut self, expr: &Expr, visit: ExprVisitorNode) -> Result<bool, HaltReason<'v>> {
-        if let Expr::Call { callee, args, .. } = expr {
+        if let Expr::Call {
+            callee,
+            args,
+            distinct,
+            ..
+        } = expr
+        {
             let callee_val = self.exec_ctx.eval(callee);
 
+            let is_agg = matches!(&callee_val, Ok(RV::Callable(callable)) if callable.is_agg());
+
+            if *distinct && !is_agg {
+                return Err(HaltReason::Error(ExecutionError::Plan(
+                    PlannerError::DistinctNotAllowed(expr.get_span()),
+                )));
+            }
+
             if let Ok(RV::Callable(callable)) = &callee_val
                 && let Function::Agg {
                     function: factory,
@@ -180,6 +194,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -216,6 +231,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -249,6 +265,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -260,6 +277,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![avg_call],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -290,6 +308,7 @@ mod tests {
                 id: 0,
             }),
             args: vec![],
+            distinct: false,
             span: Span::default(),
             id: 0,
         };
@@ -24,6 +24,9 @@ pub enum PlannerError {
 
     #[error("SELECT * with aggregation is not allowed")]
     SelectAllWithAggregationNotAllowed(Span),
+
+    #[error("DISTINCT is only allowed in aggregate arguments")]
+    DistinctNotAllowed(Span),
 }
 
 impl From<PlannerError> for InputError {
@@ -49,6 +52,7 @@ impl From<PlannerError> for InputError {
                 "Specify explicit projections instead of using SELECT *",
                 *span,
             ),
+            PlannerError::DistinctNotAllowed(span) => ("Remove DISTINCT", *span),
         };
 
         InputError::new(&value.to_string(), hint, Some(sp.into()))
@@ -0,0 +1,404 @@
+use std::{collections::HashMap, ops::Bound, sync::Arc};
+
+use lykiadb_lang::ast::{
+    Literal,
+    expr::{BinaryOp, Expr, TernaryOp, UnaryOp},
+    sql::{SqlCompoundOperator, SqlJoinType},
+};
+
+use crate::{
+    engine::{
+        encoding::encode_scalar,
+        statistics::{CollectionStatistics, FieldStatistics},
+    },
+    value::RV,
+};
+
+use super::{IndexRange, IntermediateExpr, Node};
+
+// Shares of the rows a predicate is assumed to keep when the statistics
+// tell nothing about it.
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+// Statistics of the collections a node reads, by alias.
+type Sources<'a> = HashMap<String, &'a CollectionStatistics>;
+
+/// Estimates how many rows the node returns, from the statistics of the
+/// collections it reads. Returns `None` when the node reads anything that
+/// was not analyzed, e.g. an expression or a collection without statistics.
+pub fn estimate_rows(node: &Node) -> Option<f64> {
+    match node {
+        Node::Scan {
+            source,
+            filter,
+            statistics,
+        } => {
+            let statistics = statistics.as_ref()?;
+            let alias = source.alias.as_ref().unwrap_or(&source.name).to_string();
+            let sources = HashMap::from([(alias, statistics)]);
+            let selectivity = filter
+                .as_ref()
+                .map_or(1.0, |filter| selectivity_of(filter, &sources));
+            Some(statistics.rows as f64 * selectivity)
+        }
+        Node::IndexScan {
+            ranges, statistics, ..
+        } => {
+            let statistics = statistics.as_ref()?;
+            Some(statistics.rows as f64 * ranges_selectivity(ranges))
+        }
+        Node::Filter {
+            source, predicate, ..
+        } => {
+            // The filter over an index scan checks the conjunct the index is
+            // read for again, so that conjunct is only counted once. The two
+            // are estimated apart, so the filter is kept from estimating more
+            // rows than its source.
+            let source_rows = estimate_rows(source)?;
+            let rows = match source.as_ref() {
+                Node::IndexScan { statistics, .. } => statistics.as_ref()?.rows as f64,
+                _ => source_rows,
+            };
+            let selectivity = selectivity_of(predicate, &statistics_by_alias(source));
+            Some((rows * selectivity).min(source_rows))
+        }
+        Node::Projection { source, .. }
+        | Node::Order { source, .. }
+        | Node::Distinct { source }
+        | Node::Subquery { source, .. } => estimate_rows(source),
+        Node::Limit { source, limit } => Some(estimate_rows(source)?.min(*limit as f64)),
+        Node::Offset { source, offset } => Some((estimate_rows(source)? - *offset as f64).max(0.0)),
+        Node::Aggregate {
+            source, group_by, ..
+        } => {
+            let rows = estimate_rows(source)?;
+            let sources = statistics_by_alias(source);
+            let groups = group_by.iter().try_fold(1.0, |groups, key| {
+                let IntermediateExpr::Expr { expr } = key else {
+                    return Some(groups);
+                };
+                let column = column_of(expr, &sources)?;
+                Some(groups * column.distinct())
+            });
+            Some(groups.unwrap_or(rows).min(rows).max(rows.min(1.0)))
+        }
+        Node::Join {
+            left,
+            join_type,
+            right,
+            constraint,
+        } => {
+            let (left_rows, right_rows) = (estimate_rows(left)?, estimate_rows(right)?);
+            let selectivity = constraint.as_ref().map_or(1.0, |constraint| {
+                selectivity_of(constraint, &statistics_by_alias(node))
+            });
+            Some(join_rows(left_rows, join_type, right_rows, selectivity))
+        }
+        Node::HashJoin {
+            left,
+            join_type,
+            right,
+            keys,
+            residual,
+            ..
+        } => {
+            let (left_rows, right_rows) = (estimate_rows(left)?, estimate_rows(right)?);
+            let sources = statistics_by_alias(node);
+            let mut selectivity = residual
+                .as_ref()
+                .map_or(1.0, |residual| selectivity_of(residual, &sources));
+            for (left_key, right_key) in keys {
+                selectivity *= match (left_key, right_key) {
+                    (IntermediateExpr::Expr { expr: l }, IntermediateExpr::Expr { expr: r }) => {
+                        comparison(l, BinaryOp::IsEqual, r, &sources)
+                    }
+                    _ => EQUALITY_SELECTIVITY,
+                };
+            }
+            Some(join_rows(left_rows, join_type, right_rows, selectivity))
+        }
+        Node::Compound {
+            source,
+            operator,
+            right,
+        } => {
+            let (left_rows, right_rows) = (estimate_rows(source)?, estimate_rows(right)?);
+            Some(match operator {
+                SqlCompoundOperator::Union | SqlCompoundOperator::UnionAll => {
+                    left_rows + right_rows
+                }
+                SqlCompoundOperator::Intersect => left_rows.min(right_rows),
+                SqlCompoundOperator::Except => left_rows,
+            })
+        }
+        // Only statistics make for estimates, even where the rows are known
+        // without them. Mutations return a summary rather than rows.
+        Node::EvalScan { .. }
+        | Node::Values { .. }
+        | Node::Empty { .. }
+        | Node::Insert { .. }
+        | Node::Update { .. }
+        | Node::Delete { .. }
+        | Node::Nothing => None,
+    }
+}
+
+// Outer joins return every row of their preserved side at least once.
+fn join_rows(left: f64, join_type: &SqlJoinType, right: f64, selectivity: f64) -> f64 {
+    let matched = left * right * selectivity;
+    match join_type {
+        SqlJoinType::Left => matched.max(left),
+        SqlJoinType::Right => matched.max(right),
+        _ => matched,
+    }
+}
+
+/// The statistics of the collections whose rows make up the rows of the
+/// node, by alias.
+pub(super) fn statistics_by_alias<'a>(node: &'a Node) -> Sources<'a> {
+    let mut sources = HashMap::new();
+    collect_sources(node, &mut sources);
+    sources
+}
+
+fn collect_sources<'a>(node: &'a Node, sources: &mut Sources<'a>) {
+    match node {
+        Node::Scan {
+            source,
+            statistics: Some(statistics),
+            ..
+        }
+        | Node::IndexScan {
+            source,
+            statistics: Some(statistics),
+            ..
+        } => {
+            let alias = source.alias.as_ref().unwrap_or(&source.name).to_string();
+            sources.insert(alias, statistics);
+        }
+        Node::Join { left, right, .. } | Node::HashJoin { left, right, .. } => {
+            collect_sources(left, sources);
+            collect_sources(right, sources);
+        }
+        Node::Filter { source, .. }
+        | Node::Limit { source, .. }
+        | Node::Offset { source, .. }
+        | Node::Order { source, .. }
+        | Node::Distinct { source } => collect_sources(source, sources),
+        _ => {}
+    }
+}
+
+fn selectivity_of(predicate: &IntermediateExpr, sources: &Sources) -> f64 {
+    match predicate {
+        IntermediateExpr::Constant(value) => f64::from(u8::from(value.to_bool())),
+        IntermediateExpr::Expr { expr } => selectivity(expr, sources),
+    }
+}
+
+/// The estimated share of the rows the predicate keeps.
+pub(super) fn selectivity(predicate: &Expr, sources: &Sources) -> f64 {
+    let estimate = match predicate {
+        Expr::Logical {
+            left,
+            operation: BinaryOp::And,
+            right,
+            ..
+        } => selectivity(left, sources) * selectivity(right, sources),
+        Expr::Logical { left, right, .. } => {
+            let (left, right) = (selectivity(left, sources), selectivity(right, sources));
+            left + right - left * right
+        }
+        Expr::Grouping { expr, .. } => selectivity(expr, sources),
+        Expr::Unary {
+            operation: UnaryOp::Not,
+            expr,
+            ..
+        } => 1.0 - selectivity(expr, sources),
+        Expr::Binary {
+            left,
+            operation,
+            right,
+            ..
+        } => comparison(left, *operation, right, sources),
+        Expr::Ternary {
+            subject,
+            lower,
+            upper,
+            operation,
+            ..
+        } => {
+            let between = between(subject, lower, upper, sources);
+            match operation {
+                TernaryOp::Between => between,
+                TernaryOp::NotBetween => 1.0 - between,
+            }
+        }
+        Expr::Literal { value, .. } => match scalar_of(value) {
+            Some(value) => f64::from(u8::from(value.to_bool())),
+            None => DEFAULT_SELECTIVITY,
+        },
+        _ => DEFAULT_SELECTIVITY,
+    };
+    estimate.clamp(0.0, 1.0)
+}
+
+fn comparison(left: &Expr, operation: BinaryOp, right: &Expr, sources: &Sources) -> f64 {
+    let default = match operation {
+        BinaryOp::IsEqual | BinaryOp::In => EQUALITY_SELECTIVITY,
+        BinaryOp::IsNotEqual | BinaryOp::NotIn => 1.0 - EQUALITY_SELECTIVITY,
+        BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => {
+            RANGE_SELECTIVITY
+        }
+        _ => DEFAULT_SELECTIVITY,
+    };
+
+    match (column_of(left, sources), column_of(right, sources)) {
+        // Each value of the side with fewer distinct values is assumed to
+        // match one of the other side.
+        (Some(left), Some(right)) if operation == BinaryOp::IsEqual => {
+            left.non_null() * right.non_null() / left.distinct().max(right.distinct()).max(1.0)
+        }
+        (Some(column), None) => column.compare(operation, right).unwrap_or(default),
+        (None, Some(column)) => flipped(operation)
+            .and_then(|operation| column.compare(operation, left))
+            .unwrap_or(default),
+        _ => default,
+    }
+}
+
+fn between(subject: &Expr, lower: &Expr, upper: &Expr, sources: &Sources) -> f64 {
+    let Some(column) = column_of(subject, sources) else {
+        return RANGE_SELECTIVITY;
+    };
+    let below_upper = column.compare(BinaryOp::LessEqual, upper);
+    let below_lower = column.compare(BinaryOp::Less, lower);
+    match (below_upper, below_lower) {
+        (Some(upper), Some(lower)) => upper - lower,
+        _ => RANGE_SELECTIVITY,
+    }
+}
+
+// The comparison with its operands swapped, e.g. `1 < a.x` for `a.x > 1`.
+fn flipped(operation: BinaryOp) -> Option<BinaryOp> {
+    Some(match operation {
+        BinaryOp::IsEqual | BinaryOp::IsNotEqual => operation,
+        BinaryOp::Less => BinaryOp::Greater,
+        BinaryOp::LessEqual => BinaryOp::GreaterEqual,
+        BinaryOp::Greater => BinaryOp::Less,
+        BinaryOp::GreaterEqual => BinaryOp::LessEqual,
+        _ => return None,
+    })
+}
+
+// Point ranges are assumed to match like equalities, and other ranges like
+// comparisons, as the fields of an index are not known here.
+fn ranges_selectivity(ranges: &[IndexRange]) -> f64 {
+    let selectivity: f64 = ranges
+        .iter()
+        .map(|range| match (&range.lower, &range.upper) {
+            (Bound::Included(lower), Bound::Included(upper)) if lower == upper => {
+                EQUALITY_SELECTIVITY
+            }
+            _ => RANGE_SELECTIVITY,
+        })
+        .sum();
+    selectivity.min(1.0)
+}
+
+// A top-level field of an analyzed source, e.g. `u.age`.
+struct Column<'a> {
+    rows: f64,
+    statistics: Option<&'a FieldStatistics>,
+}
+
+fn column_of<'a>(expr: &Expr, sources: &Sources<'a>) -> Option<Column<'a>> {
+    let Expr::FieldPath { head, tail, .. } = expr else {
+        return None;
+    };
+    let [field] = tail.as_slice() else {
+        return None;
+    };
+    let statistics = sources.get(&head.name)?;
+    Some(Column {
+        rows: statistics.rows as f64,
+        // Fields that no document has were not recorded.
+        statistics: statistics.fields.get(&field.name),
+    })
+}
+
+impl Column<'_> {
+    // Share of the rows with a value for the field.
+    fn non_null(&self) -> f64 {
+        match self.statistics {
+            Some(statistics) if self.rows > 0.0 => {
+                (self.rows - statistics.nulls as f64) / self.rows
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn distinct(&self) -> f64 {
+        self.statistics
+            .map_or(0.0, |statistics| statistics.distinct as f64)
+    }
+
+    // Share of the rows equal to any one value.
+    fn equal(&self) -> f64 {
+        match self.distinct() {
+            0.0 => 0.0,
+            distinct => self.non_null() / distinct,
+        }
+    }
+
+    // Share of the rows below the value, per the histogram.
+    fn below(&self, value: &RV) -> Option<f64> {
+        let fraction = self
+            .statistics?
+            .histogram
+            .fraction_below(&encode_scalar(value)?)?;
+        Some(self.non_null() * fraction)
+    }
+
+    /// The share of the rows for which `field <operation> operand` holds, or
+    /// `None` when the statistics can not tell.
+    fn compare(&self, operation: BinaryOp, operand: &Expr) -> Option<f64> {
+        if self.statistics.is_none() {
+            return Some(0.0);
+        }
+        let Expr::Literal { value, .. } = operand else {
+            return None;
+        };
+        if let Literal::Array(values) = value {
+            let equal = (values.len() as f64 * self.equal()).min(self.non_null());
+            return match operation {
+                BinaryOp::In => Some(equal),
+                BinaryOp::NotIn => Some(self.non_null() - equal),
+                _ => None,
+            };
+        }
+        let value = scalar_of(value)?;
+        let below = || self.below(&value);
+        match operation {
+            BinaryOp::IsEqual => Some(self.equal()),
+            BinaryOp::IsNotEqual => Some(self.non_null() - self.equal()),
+            BinaryOp::Less => below(),
+            BinaryOp::LessEqual => Some(below()? + self.equal()),
+            BinaryOp::Greater => Some(self.non_null() - below()? - self.equal()),
+            BinaryOp::GreaterEqual => Some(self.non_null() - below()?),
+            _ => None,
+        }
+        .map(|share| share.clamp(0.0, 1.0))
+    }
+}
+
+fn scalar_of<'v>(literal: &Literal) -> Option<RV<'v>> {
+    match literal {
+        Literal::Num(n) => Some(RV::Double(*n)),
+        Literal::Str(s) => Some(RV::Str(Arc::clone(s))),
+        Literal::Bool(b) => Some(RV::Bool(*b)),
+        _ => None,
+    }
+}
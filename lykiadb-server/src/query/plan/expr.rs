@@ -3,14 +3,13 @@ use crate::{execution::error::ExecutionError, interpreter::HaltReason, query::pl
 use lykiadb_lang::ast::{
     Spanned,
     expr::Expr,
-    sql::SqlSelect,
     visitor::{ExprReducer, ExprVisitorNode},
 };
 
 use super::error::PlannerError;
 
 pub struct SqlExprReducer<'a> {
-    subqueries: Vec<SqlSelect>,
+    subqueries: Vec<Expr>,
     allow_subqueries: bool,
     scope: &'a Scope,
 }
@@ -25,7 +24,7 @@ impl<'a> SqlExprReducer<'a> {
     }
 }
 
-impl<'scope, 'a> ExprReducer<SqlSelect, HaltReason<'a>> for SqlExprReducer<'scope> {
+impl<'scope, 'a> ExprReducer<Expr, HaltReason<'a>> for SqlExprReducer<'scope> {
     fn visit(&mut self, expr: &Expr, visit: ExprVisitorNode) -> Result<bool, HaltReason<'a>> {
         if matches!(visit, ExprVisitorNode::In) {
             match expr {
@@ -45,13 +44,13 @@ impl<'scope, 'a> ExprReducer<SqlSelect, HaltReason<'a>> for SqlExprReducer<'scop
                     // check if the callee resolves
                     // println!("/Expr::Call({callee:?})/");
                 }
-                Expr::Select { query, .. } => {
+                Expr::Select { .. } => {
                     if !self.allow_subqueries {
                         return Err(HaltReason::Error(ExecutionError::Plan(
                             PlannerError::SubqueryNotAllowed(expr.get_span()),
                         )));
                     }
-                    self.subqueries.push(query.clone());
+                    self.subqueries.push(expr.clone());
                     return Ok(false);
                 }
                 _ => {}
@@ -61,7 +60,7 @@ impl<'scope, 'a> ExprReducer<SqlSelect, HaltReason<'a>> for SqlExprReducer<'scop
         Ok(true)
     }
 
-    fn finalize(&mut self) -> Result<Vec<SqlSelect>, HaltReason<'a>> {
+    fn finalize(&mut self) -> Result<Vec<Expr>, HaltReason<'a>> {
         Ok(self.subqueries.clone())
     }
 }
@@ -0,0 +1,285 @@
+use lykiadb_lang::ast::{
+    AstNode, Identifier, Literal, Spanned,
+    expr::{BinaryOp, Expr},
+};
+
+use crate::{
+    query::context::QueryExecutionContext,
+    value::{RV, callable::RVCallable},
+};
+
+use super::scope::Scope;
+
+/// Simplifies an expression before the query runs. Subtrees made of
+/// constants are evaluated once, and so are the calls of pure functions
+/// with constant arguments. In a `condition`, where only the truthiness of
+/// the value matters, `AND`s and `OR`s with a constant operand are reduced
+/// to their other operand too.
+///
+/// Expressions that fail to evaluate are left as they are, so that the
+/// error is raised when the query runs.
+pub fn fold(
+    expr: &Expr,
+    condition: bool,
+    scope: &Scope,
+    exec_ctx: &QueryExecutionContext<'_>,
+) -> Expr {
+    Folder { scope, exec_ctx }.fold(expr, condition)
+}
+
+struct Folder<'a, 'v> {
+    scope: &'a Scope,
+    exec_ctx: &'a QueryExecutionContext<'v>,
+}
+
+impl<'v> Folder<'_, 'v> {
+    fn fold(&self, expr: &Expr, condition: bool) -> Expr {
+        match expr {
+            Expr::Literal {
+                value: Literal::Array(items),
+                raw,
+                span,
+                id,
+            } => Expr::Literal {
+                value: Literal::Array(items.iter().map(|item| self.fold(item, false)).collect()),
+                raw: raw.clone(),
+                span: *span,
+                id: *id,
+            },
+            Expr::Literal {
+                value: Literal::Object(fields),
+                raw,
+                span,
+                id,
+            } => Expr::Literal {
+                value: Literal::Object(
+                    fields
+                        .iter()
+                        .map(|(key, value)| (key.clone(), Box::new(self.fold(value, false))))
+                        .collect(),
+                ),
+                raw: raw.clone(),
+                span: *span,
+                id: *id,
+            },
+            Expr::Grouping {
+                expr: inner,
+                span,
+                id,
+            } => match self.fold(inner, condition) {
+                literal @ Expr::Literal { .. } => literal,
+                inner => Expr::Grouping {
+                    expr: Box::new(inner),
+                    span: *span,
+                    id: *id,
+                },
+            },
+            Expr::Unary {
+                operation,
+                expr: inner,
+                span,
+                id,
+            } => self.evaluate_if_constant(Expr::Unary {
+                operation: *operation,
+                expr: Box::new(self.fold(inner, false)),
+                span: *span,
+                id: *id,
+            }),
+            Expr::Binary {
+                left,
+                operation,
+                right,
+                span,
+                id,
+            } => self.evaluate_if_constant(Expr::Binary {
+                left: Box::new(self.fold(left, false)),
+                operation: *operation,
+                right: Box::new(self.fold(right, false)),
+                span: *span,
+                id: *id,
+            }),
+            Expr::Ternary {
+                lower,
+                upper,
+                subject,
+                operation,
+                span,
+                id,
+            } => self.evaluate_if_constant(Expr::Ternary {
+                lower: Box::new(self.fold(lower, false)),
+                upper: Box::new(self.fold(upper, false)),
+                subject: Box::new(self.fold(subject, false)),
+                operation: *operation,
+                span: *span,
+                id: *id,
+            }),
+            Expr::Logical {
+                left,
+                operation,
+                right,
+                span,
+                id,
+            } => {
+                // The operands are only ever checked for truthiness.
+                let left = self.fold(left, true);
+                let right = self.fold(right, true);
+                if let Some(folded) = self.fold_logical(&left, *operation, &right, condition, expr)
+                {
+                    return folded;
+                }
+                self.evaluate_if_constant(Expr::Logical {
+                    left: Box::new(left),
+                    operation: *operation,
+                    right: Box::new(right),
+                    span: *span,
+                    id: *id,
+                })
+            }
+            Expr::Call {
+                callee,
+                args,
+                distinct,
+                span,
+                id,
+            } => {
+                // Aggregates are looked up by the text of their call, so
+                // their arguments are left untouched.
+                let Some(callable) = self.callable(callee) else {
+                    return expr.clone();
+                };
+                if callable.is_agg() {
+                    return expr.clone();
+                }
+                let call = Expr::Call {
+                    callee: callee.clone(),
+                    args: args.iter().map(|arg| self.fold(arg, false)).collect(),
+                    distinct: *distinct,
+                    span: *span,
+                    id: *id,
+                };
+                if callable.is_pure() {
+                    self.evaluate_if_constant(call)
+                } else {
+                    call
+                }
+            }
+            _ => expr.clone(),
+        }
+    }
+
+    // `AND`s and `OR`s whose value is settled by one constant operand.
+    // Returns `None` when neither operand settles it.
+    fn fold_logical(
+        &self,
+        left: &Expr,
+        operation: BinaryOp,
+        right: &Expr,
+        condition: bool,
+        origin: &Expr,
+    ) -> Option<Expr> {
+        let settles = |value: bool| match operation {
+            BinaryOp::And => !value,
+            _ => value,
+        };
+
+        for (constant, other) in [(left, right), (right, left)] {
+            if !is_constant(constant) {
+                continue;
+            }
+            let Some(value) = self.evaluate(constant).map(|value| value.to_bool()) else {
+                continue;
+            };
+            if settles(value) {
+                return to_literal(&RV::Bool(value), origin);
+            }
+            if condition && !is_constant(other) {
+                return Some(other.clone());
+            }
+        }
+        None
+    }
+
+    fn evaluate_if_constant(&self, expr: Expr) -> Expr {
+        let constant = match &expr {
+            Expr::Unary { expr, .. } => is_constant(expr),
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                is_constant(left) && is_constant(right)
+            }
+            Expr::Ternary {
+                lower,
+                upper,
+                subject,
+                ..
+            } => is_constant(lower) && is_constant(upper) && is_constant(subject),
+            Expr::Call { args, .. } => args.iter().all(is_constant),
+            _ => false,
+        };
+        if !constant {
+            return expr;
+        }
+        self.evaluate(&expr)
+            .and_then(|value| to_literal(&value, &expr))
+            .unwrap_or(expr)
+    }
+
+    fn evaluate(&self, expr: &Expr) -> Option<RV<'v>> {
+        self.exec_ctx.eval(expr).ok()
+    }
+
+    // The function a call refers to, unless the callee is a field of one
+    // of the sources, which is only known once the query runs.
+    fn callable(&self, callee: &Expr) -> Option<RVCallable<'v>> {
+        let head = head_of(callee)?;
+        if self.scope.has_source(&head.name) {
+            return None;
+        }
+        match self.evaluate(callee) {
+            Some(RV::Callable(callable)) => Some(callable),
+            _ => None,
+        }
+    }
+}
+
+// The name a path of field accesses starts from, e.g. `json` for
+// `json::stringify`.
+fn head_of(expr: &Expr) -> Option<&Identifier> {
+    match expr {
+        Expr::Variable { name, .. } => Some(name),
+        Expr::FieldPath { head, .. } => Some(head),
+        Expr::Get { object, .. } => head_of(object),
+        _ => None,
+    }
+}
+
+fn is_constant(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal {
+            value: Literal::Array(items),
+            ..
+        } => items.iter().all(is_constant),
+        Expr::Literal {
+            value: Literal::Object(fields),
+            ..
+        } => fields.values().all(|value| is_constant(value)),
+        Expr::Literal { .. } => true,
+        _ => false,
+    }
+}
+
+// Scalars are turned back into literals. Other values have no literal of
+// their own, so the expressions that produce them are kept.
+fn to_literal(value: &RV, origin: &Expr) -> Option<Expr> {
+    let (value, raw) = match value {
+        RV::Double(n) => (Literal::Num(*n), n.to_string()),
+        RV::Str(s) => (Literal::Str(s.clone()), format!("\"{s}\"")),
+        RV::Bool(b) => (Literal::Bool(*b), b.to_string()),
+        RV::Undefined => (Literal::Undefined, "undefined".to_string()),
+        _ => return None,
+    };
+    Some(Expr::Literal {
+        value,
+        raw,
+        span: origin.get_span(),
+        id: origin.get_id(),
+    })
+}
@@ -1,12 +1,21 @@
 use crate::{
+    engine::{StoreId, statistics::CollectionStatistics},
     execution::error::ExecutionError,
     interpreter::HaltReason,
     query::{context::QueryExecutionContext, plan::planner::InClause},
 };
 
-use lykiadb_lang::ast::sql::{SqlFrom, SqlJoinType, SqlSource};
+use lykiadb_lang::ast::{
+    expr::Expr,
+    sql::{SqlCollectionIdentifier, SqlFrom, SqlJoinType, SqlSource},
+};
 
-use super::{Node, planner::Planner, scope::Scope};
+use super::{
+    IntermediateExpr, Node,
+    join::{EquiJoin, split_equi_join},
+    planner::Planner,
+    scope::Scope,
+};
 
 // The source can be of following types:
 
@@ -31,6 +40,7 @@ pub fn build_from<'v>(
                 SqlSource::Collection(ident) => Node::Scan {
                     source: ident.clone(),
                     filter: None,
+                    statistics: statistics_of(ident, exec_ctx),
                 },
                 SqlSource::Expr(expr) => Node::EvalScan {
                     source: expr.clone(),
@@ -92,12 +102,40 @@ pub fn build_from<'v>(
                 })
                 .transpose()?;
 
-            Ok(Node::Join {
-                left: Box::new(build_from(planner, left, &mut scope, exec_ctx)?),
-                join_type: join_type.clone(),
-                right: Box::new(build_from(planner, right, &mut scope, exec_ctx)?),
-                constraint: constraint.map(|x| x.0),
-            })
+            let left = build_from(planner, left, &mut scope, exec_ctx)?;
+            let right = build_from(planner, right, &mut scope, exec_ctx)?;
+
+            // Equalities between the two sides let us match rows through a
+            // hash table instead of comparing every pair.
+            let equi_join = match (&constraint, join_type) {
+                (Some((IntermediateExpr::Expr { expr }, _)), join_type)
+                    if *join_type != SqlJoinType::Cross =>
+                {
+                    split_equi_join(expr, &left.aliases(), &right.aliases())
+                }
+                _ => None,
+            };
+
+            if let Some(EquiJoin { keys, residual }) = equi_join {
+                let wrap = |expr: Expr| IntermediateExpr::Expr {
+                    expr: Box::new(expr),
+                };
+                Ok(Node::HashJoin {
+                    left: Box::new(left),
+                    join_type: join_type.clone(),
+                    right: Box::new(right),
+                    keys: keys.into_iter().map(|(l, r)| (wrap(l), wrap(r))).collect(),
+                    residual: residual.map(wrap),
+                    build: None,
+                })
+            } else {
+                Ok(Node::Join {
+                    left: Box::new(left),
+                    join_type: join_type.clone(),
+                    right: Box::new(right),
+                    constraint: constraint.map(|x| x.0),
+                })
+            }
         }
     };
 
@@ -107,3 +145,15 @@ pub fn build_from<'v>(
 
     node
 }
+
+fn statistics_of(
+    collection: &SqlCollectionIdentifier,
+    exec_ctx: &QueryExecutionContext<'_>,
+) -> Option<CollectionStatistics> {
+    let engine = exec_ctx.engine().read().unwrap();
+    let metadata = engine
+        .catalog()
+        .collection(&StoreId::from(collection))
+        .ok()?;
+    metadata.statistics.clone()
+}
@@ -0,0 +1,272 @@
+use std::ops::Bound;
+
+use lykiadb_lang::ast::{
+    Literal,
+    expr::{BinaryOp, Expr, TernaryOp},
+};
+
+use crate::engine::catalog::IndexMetadata;
+
+use super::join::flatten_conjuncts;
+
+/// A conjunct of a filter predicate that an index can answer.
+pub struct IndexPredicate {
+    pub index: String,
+    /// Ranges of values of the first indexed field. A document matches when
+    /// its value falls in any of them.
+    pub ranges: Vec<(Bound<Expr>, Bound<Expr>)>,
+}
+
+/// Picks a conjunct that compares the first field of one of the indexes to
+/// a constant, e.g. `u.age > 30`, `u.age BETWEEN 20 AND 30` or
+/// `u.age IN [20, 30]`. Equalities are preferred over ranges, as they are
+/// likely to match fewer documents. Returns `None` when no conjunct can be
+/// answered with an index.
+pub fn find_index_predicate(
+    predicate: &Expr,
+    alias: &str,
+    indexes: &[IndexMetadata],
+) -> Option<IndexPredicate> {
+    let mut conjuncts = vec![];
+    flatten_conjuncts(predicate, &mut conjuncts);
+
+    let (_, index, ranges, _) = conjuncts
+        .iter()
+        .enumerate()
+        .filter_map(|(position, conjunct)| {
+            let (path, ranges, is_equality) = index_ranges(conjunct, alias)?;
+            let index = indexes.iter().find(|index| index.fields[0] == path)?;
+            Some((position, index, ranges, is_equality))
+        })
+        .min_by_key(|(position, _, _, is_equality)| (!is_equality, *position))?;
+
+    Some(IndexPredicate {
+        index: index.name.clone(),
+        ranges,
+    })
+}
+
+type Ranges = Vec<(Bound<Expr>, Bound<Expr>)>;
+
+// The field a conjunct restricts, the ranges it restricts the field to, and
+// whether it is an equality.
+fn index_ranges(conjunct: &Expr, alias: &str) -> Option<(Vec<String>, Ranges, bool)> {
+    match conjunct {
+        Expr::Binary {
+            left,
+            operation: BinaryOp::In,
+            right,
+            ..
+        } => {
+            let path = field_of(left, alias)?;
+            let Expr::Literal {
+                value: Literal::Array(values),
+                ..
+            } = unwrap_grouping(right)
+            else {
+                return None;
+            };
+            if !values.iter().all(is_constant) {
+                return None;
+            }
+            let ranges = values
+                .iter()
+                .map(|value| {
+                    (
+                        Bound::Included(value.clone()),
+                        Bound::Included(value.clone()),
+                    )
+                })
+                .collect();
+            Some((path, ranges, true))
+        }
+        Expr::Binary {
+            left,
+            operation,
+            right,
+            ..
+        } => {
+            // Comparisons are turned around when the field is on the right.
+            let (path, value, operation) = match (field_of(left, alias), field_of(right, alias)) {
+                (Some(path), None) if is_constant(right) => (path, right, *operation),
+                (None, Some(path)) if is_constant(left) => (path, left, flip(*operation)?),
+                _ => return None,
+            };
+            let value = unwrap_grouping(value).clone();
+            let range = match operation {
+                BinaryOp::IsEqual => (Bound::Included(value.clone()), Bound::Included(value)),
+                BinaryOp::Less => (Bound::Unbounded, Bound::Excluded(value)),
+                BinaryOp::LessEqual => (Bound::Unbounded, Bound::Included(value)),
+                BinaryOp::Greater => (Bound::Excluded(value), Bound::Unbounded),
+                BinaryOp::GreaterEqual => (Bound::Included(value), Bound::Unbounded),
+                _ => return None,
+            };
+            Some((path, vec![range], operation == BinaryOp::IsEqual))
+        }
+        Expr::Ternary {
+            lower,
+            upper,
+            subject,
+            operation: TernaryOp::Between,
+            ..
+        } => {
+            let path = field_of(subject, alias)?;
+            if !is_constant(lower) || !is_constant(upper) {
+                return None;
+            }
+            let range = (
+                Bound::Included(unwrap_grouping(lower).clone()),
+                Bound::Included(unwrap_grouping(upper).clone()),
+            );
+            Some((path, vec![range], false))
+        }
+        _ => None,
+    }
+}
+
+fn flip(operation: BinaryOp) -> Option<BinaryOp> {
+    match operation {
+        BinaryOp::IsEqual => Some(BinaryOp::IsEqual),
+        BinaryOp::Less => Some(BinaryOp::Greater),
+        BinaryOp::LessEqual => Some(BinaryOp::GreaterEqual),
+        BinaryOp::Greater => Some(BinaryOp::Less),
+        BinaryOp::GreaterEqual => Some(BinaryOp::LessEqual),
+        _ => None,
+    }
+}
+
+// The path of a field of the source, e.g. `["address", "city"]` for
+// `u.address.city`.
+fn field_of(expr: &Expr, alias: &str) -> Option<Vec<String>> {
+    match unwrap_grouping(expr) {
+        Expr::FieldPath { head, tail, .. } if head.name == alias && !tail.is_empty() => {
+            Some(tail.iter().map(|field| field.name.clone()).collect())
+        }
+        _ => None,
+    }
+}
+
+// Constants are evaluated once, when the index is read.
+fn is_constant(expr: &Expr) -> bool {
+    match unwrap_grouping(expr) {
+        Expr::Literal { value, .. } => !matches!(value, Literal::Object(_) | Literal::Array(_)),
+        Expr::Variable { .. } => true,
+        _ => false,
+    }
+}
+
+fn unwrap_grouping(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Grouping { expr, .. } => unwrap_grouping(expr),
+        _ => expr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lykiadb_lang::ast::{Identifier, IdentifierKind, Span};
+
+    fn path(head: &str, field: &str) -> Expr {
+        Expr::FieldPath {
+            head: Identifier::new(head, IdentifierKind::Variable),
+            tail: vec![Identifier::new(field, IdentifierKind::Variable)],
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn num(value: f64) -> Expr {
+        Expr::Literal {
+            value: Literal::Num(value),
+            raw: value.to_string(),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn binary(left: Expr, operation: BinaryOp, right: Expr) -> Expr {
+        Expr::Binary {
+            left: Box::new(left),
+            operation,
+            right: Box::new(right),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn and(left: Expr, right: Expr) -> Expr {
+        Expr::Logical {
+            left: Box::new(left),
+            operation: BinaryOp::And,
+            right: Box::new(right),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn indexes() -> Vec<IndexMetadata> {
+        ["age", "name"]
+            .iter()
+            .map(|field| IndexMetadata {
+                name: format!("by_{field}"),
+                fields: vec![vec![field.to_string()]],
+                unique: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_flips_comparisons_with_the_field_on_the_right() {
+        let predicate = binary(num(30.0), BinaryOp::Less, path("u", "age"));
+
+        let split = find_index_predicate(&predicate, "u", &indexes()).unwrap();
+
+        assert_eq!(split.index, "by_age");
+        assert_eq!(
+            split.ranges,
+            vec![(Bound::Excluded(num(30.0)), Bound::Unbounded)]
+        );
+    }
+
+    #[test]
+    fn test_split_prefers_equalities() {
+        let predicate = and(
+            binary(path("u", "age"), BinaryOp::Greater, num(30.0)),
+            binary(path("u", "name"), BinaryOp::IsEqual, num(1.0)),
+        );
+
+        let split = find_index_predicate(&predicate, "u", &indexes()).unwrap();
+
+        assert_eq!(split.index, "by_name");
+        assert_eq!(
+            split.ranges,
+            vec![(Bound::Included(num(1.0)), Bound::Included(num(1.0)))]
+        );
+    }
+
+    #[test]
+    fn test_split_rejects_unindexed_predicates() {
+        // Not an indexed field.
+        let other = binary(path("u", "city"), BinaryOp::IsEqual, num(1.0));
+        assert!(find_index_predicate(&other, "u", &indexes()).is_none());
+
+        // A field of another source.
+        let joined = binary(path("b", "age"), BinaryOp::IsEqual, num(1.0));
+        assert!(find_index_predicate(&joined, "u", &indexes()).is_none());
+
+        // Not compared to a constant.
+        let fields = binary(path("u", "age"), BinaryOp::IsEqual, path("u", "name"));
+        assert!(find_index_predicate(&fields, "u", &indexes()).is_none());
+
+        // Under a disjunction.
+        let or = Expr::Logical {
+            left: Box::new(binary(path("u", "age"), BinaryOp::IsEqual, num(1.0))),
+            operation: BinaryOp::Or,
+            right: Box::new(binary(path("u", "age"), BinaryOp::IsEqual, num(2.0))),
+            span: Span::default(),
+            id: 0,
+        };
+        assert!(find_index_predicate(&or, "u", &indexes()).is_none());
+    }
+}
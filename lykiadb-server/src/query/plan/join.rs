@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+
+use lykiadb_lang::ast::{
+    AstNode, Spanned,
+    expr::{BinaryOp, Expr},
+    visitor::{ExprReducer, ExprVisitor, ExprVisitorNode},
+};
+
+/// Equality conditions of a join constraint that can be answered with a
+/// hash table, along with whatever is left of the constraint.
+pub struct EquiJoin {
+    /// Pairs of (left key, right key). Each key only refers to the sources
+    /// of its own side.
+    pub keys: Vec<(Expr, Expr)>,
+    pub residual: Option<Expr>,
+}
+
+/// Splits a join constraint into its top-level conjuncts and picks the
+/// `a.x = b.y` ones where each side of the equality refers to exactly one
+/// side of the join. Returns `None` when no such conjunct exists, in which
+/// case the join has to be evaluated with nested loops.
+pub fn split_equi_join(
+    constraint: &Expr,
+    left_aliases: &[String],
+    right_aliases: &[String],
+) -> Option<EquiJoin> {
+    let mut conjuncts = vec![];
+    flatten_conjuncts(constraint, &mut conjuncts);
+
+    let mut keys = vec![];
+    let mut residual = vec![];
+
+    for conjunct in conjuncts {
+        if let Expr::Binary {
+            left,
+            operation: BinaryOp::IsEqual,
+            right,
+            ..
+        } = conjunct
+        {
+            let lhs = side_of(left, left_aliases, right_aliases);
+            let rhs = side_of(right, left_aliases, right_aliases);
+
+            match (lhs, rhs) {
+                (Some(Side::Left), Some(Side::Right)) => {
+                    keys.push((*left.clone(), *right.clone()));
+                    continue;
+                }
+                (Some(Side::Right), Some(Side::Left)) => {
+                    keys.push((*right.clone(), *left.clone()));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        residual.push(conjunct.clone());
+    }
+
+    if keys.is_empty() {
+        return None;
+    }
+
+    let residual = residual.into_iter().reduce(|acc, next| Expr::Logical {
+        left: Box::new(acc),
+        operation: BinaryOp::And,
+        right: Box::new(next),
+        span: constraint.get_span(),
+        id: constraint.get_id(),
+    });
+
+    Some(EquiJoin { keys, residual })
+}
+
+pub(super) fn flatten_conjuncts<'e>(expr: &'e Expr, conjuncts: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::Logical {
+            left,
+            operation: BinaryOp::And,
+            right,
+            ..
+        } => {
+            flatten_conjuncts(left, conjuncts);
+            flatten_conjuncts(right, conjuncts);
+        }
+        Expr::Grouping { expr, .. } => flatten_conjuncts(expr, conjuncts),
+        _ => conjuncts.push(expr),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+fn side_of(expr: &Expr, left_aliases: &[String], right_aliases: &[String]) -> Option<Side> {
+    let heads = sources_of(expr, &[left_aliases, right_aliases].concat())?;
+
+    if heads.is_empty() {
+        return None;
+    }
+
+    if heads.iter().all(|h| left_aliases.contains(h)) {
+        Some(Side::Left)
+    } else if heads.iter().all(|h| right_aliases.contains(h)) {
+        Some(Side::Right)
+    } else {
+        None
+    }
+}
+
+/// The sources an expression refers to: the heads of its field paths, and
+/// the variables named after one of `aliases`, which stand for whole rows
+/// of that source. Returns `None` when the expression is opaque.
+pub(super) fn sources_of(expr: &Expr, aliases: &[String]) -> Option<HashSet<String>> {
+    let mut collector = SourceCollector {
+        aliases,
+        heads: vec![],
+    };
+    let mut visitor = ExprVisitor::<String, ()>::new(&mut collector);
+    Some(visitor.visit(expr).ok()?.into_iter().collect())
+}
+
+/// Collects the sources an expression refers to. Nested queries and
+/// function literals make the expression opaque.
+struct SourceCollector<'a> {
+    aliases: &'a [String],
+    heads: Vec<String>,
+}
+
+impl ExprReducer<String, ()> for SourceCollector<'_> {
+    fn visit(&mut self, expr: &Expr, visit: ExprVisitorNode) -> Result<bool, ()> {
+        if matches!(visit, ExprVisitorNode::In) {
+            match expr {
+                Expr::FieldPath { head, .. } => self.heads.push(head.name.clone()),
+                Expr::Variable { name, .. } if self.aliases.contains(&name.name) => {
+                    self.heads.push(name.name.clone())
+                }
+                Expr::Select { .. } | Expr::Function { .. } => return Err(()),
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn finalize(&mut self) -> Result<Vec<String>, ()> {
+        Ok(self.heads.drain(..).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lykiadb_lang::ast::{Identifier, IdentifierKind, Literal, Span};
+
+    fn path(head: &str, field: &str) -> Expr {
+        Expr::FieldPath {
+            head: Identifier::new(head, IdentifierKind::Variable),
+            tail: vec![Identifier::new(field, IdentifierKind::Variable)],
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn num(value: f64) -> Expr {
+        Expr::Literal {
+            value: Literal::Num(value),
+            raw: value.to_string(),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn eq(left: Expr, right: Expr) -> Expr {
+        Expr::Binary {
+            left: Box::new(left),
+            operation: BinaryOp::IsEqual,
+            right: Box::new(right),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn and(left: Expr, right: Expr) -> Expr {
+        Expr::Logical {
+            left: Box::new(left),
+            operation: BinaryOp::And,
+            right: Box::new(right),
+            span: Span::default(),
+            id: 0,
+        }
+    }
+
+    fn aliases(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_single_equality() {
+        let constraint = eq(path("c", "id"), path("b", "category_id"));
+
+        let split = split_equi_join(&constraint, &aliases(&["b"]), &aliases(&["c"])).unwrap();
+
+        assert_eq!(split.keys.len(), 1);
+        assert_eq!(split.keys[0].0.to_string(), "b.category_id");
+        assert_eq!(split.keys[0].1.to_string(), "c.id");
+        assert!(split.residual.is_none());
+    }
+
+    #[test]
+    fn test_split_conjunction_with_residual() {
+        let constraint = and(
+            and(
+                eq(path("b", "category_id"), path("c", "id")),
+                eq(path("b", "lang"), path("c", "lang")),
+            ),
+            eq(path("b", "year"), num(2000.0)),
+        );
+
+        let split = split_equi_join(&constraint, &aliases(&["b"]), &aliases(&["c"])).unwrap();
+
+        assert_eq!(split.keys.len(), 2);
+        assert_eq!(
+            split.residual.unwrap().to_string(),
+            "(b.year IsEqual Num(2000.0))"
+        );
+    }
+
+    #[test]
+    fn test_split_rejects_non_equi_constraints() {
+        let b_id = || path("b", "id");
+        let c_id = || path("c", "id");
+
+        // Both sides refer to the same source.
+        let same_side = eq(b_id(), path("b", "other"));
+        assert!(split_equi_join(&same_side, &aliases(&["b"]), &aliases(&["c"])).is_none());
+
+        // Not an equality.
+        let less = Expr::Binary {
+            left: Box::new(b_id()),
+            operation: BinaryOp::Less,
+            right: Box::new(c_id()),
+            span: Span::default(),
+            id: 0,
+        };
+        assert!(split_equi_join(&less, &aliases(&["b"]), &aliases(&["c"])).is_none());
+
+        // Equality under a disjunction.
+        let or = Expr::Logical {
+            left: Box::new(eq(b_id(), c_id())),
+            operation: BinaryOp::Or,
+            right: Box::new(eq(b_id(), c_id())),
+            span: Span::default(),
+            id: 0,
+        };
+        assert!(split_equi_join(&or, &aliases(&["b"]), &aliases(&["c"])).is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+
+use lykiadb_lang::ast::{
+    AstNode, Spanned,
+    expr::{BinaryOp, Expr},
+    sql::SqlJoinType,
+};
+
+use super::{
+    IntermediateExpr, JoinSide, Node,
+    estimate::{estimate_rows, selectivity, statistics_by_alias},
+    join::{EquiJoin, flatten_conjuncts, sources_of, split_equi_join},
+    rewrite::{conjoin, map_children},
+};
+
+/// Reorders trees of inner joins so that the joins estimated to return the
+/// fewest rows run first, and builds the hash table of each hash join on
+/// the side estimated to be smaller. Joins that read anything without
+/// statistics keep the order they were written in.
+pub fn order_joins<'v>(node: Node<'v>) -> Node<'v> {
+    let mut leaves = vec![];
+    collect_leaves(&node, &mut leaves);
+
+    let node = if leaves.len() > 2 && leaves.iter().all(|leaf| estimate_rows(leaf).is_some()) {
+        reorder(node)
+    } else {
+        map_children(node, &order_joins)
+    };
+    choose_build_side(node)
+}
+
+// A relation being joined, along with the aliases it exposes and its
+// estimated number of rows.
+struct Relation<'v> {
+    node: Node<'v>,
+    aliases: Vec<String>,
+    rows: f64,
+}
+
+// A conjunct of the join constraints, along with the sources it refers to,
+// or `None` when it is opaque, and the share of the rows it keeps.
+struct Conjunct {
+    expr: Expr,
+    sources: Option<HashSet<String>>,
+    selectivity: f64,
+}
+
+// Greedily joins the relation with the fewest rows with the one that gives
+// the smallest result, preferring the relations that a conjunct connects
+// to the joined ones over cross products. Each conjunct is checked at the
+// first join that has all of its sources, and the opaque ones at the last.
+fn reorder<'v>(node: Node<'v>) -> Node<'v> {
+    let mut leaves = vec![];
+    let mut exprs = vec![];
+    flatten(node, &mut leaves, &mut exprs);
+
+    let aliases: Vec<String> = leaves.iter().flat_map(|leaf| leaf.aliases()).collect();
+    let mut conjuncts: Vec<Conjunct> = {
+        let statistics: HashMap<_, _> = leaves.iter().flat_map(statistics_by_alias).collect();
+        exprs
+            .into_iter()
+            .map(|expr| Conjunct {
+                sources: sources_of(&expr, &aliases),
+                selectivity: selectivity(&expr, &statistics),
+                expr,
+            })
+            .collect()
+    };
+
+    let mut relations: Vec<Relation<'v>> = leaves
+        .into_iter()
+        .map(|leaf| {
+            let leaf = order_joins(leaf);
+            Relation {
+                aliases: leaf.aliases(),
+                rows: estimate_rows(&leaf).unwrap_or_default(),
+                node: leaf,
+            }
+        })
+        .collect();
+
+    let smallest = (0..relations.len())
+        .min_by(|a, b| relations[*a].rows.total_cmp(&relations[*b].rows))
+        .unwrap();
+    let mut joined = relations.remove(smallest);
+
+    while !relations.is_empty() {
+        let candidates = relations.iter().enumerate().map(|(idx, candidate)| {
+            let applicable = applicable(&conjuncts, &joined.aliases, &candidate.aliases);
+            let connected = applicable
+                .iter()
+                .any(|&i| conjuncts[i].sources.as_ref().is_some_and(|s| !s.is_empty()));
+            let rows = applicable
+                .iter()
+                .fold(joined.rows * candidate.rows, |rows, &i| {
+                    rows * conjuncts[i].selectivity
+                });
+            (idx, !connected, rows)
+        });
+        let (next, _, rows) = candidates
+            .min_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)))
+            .unwrap();
+
+        let right = relations.remove(next);
+        let mut placed = applicable(&conjuncts, &joined.aliases, &right.aliases);
+        if relations.is_empty() {
+            placed.extend((0..conjuncts.len()).filter(|i| conjuncts[*i].sources.is_none()));
+        }
+        // Taken from the back so that the indices left to take stay valid.
+        placed.sort_unstable();
+        let mut exprs: Vec<Expr> = placed
+            .into_iter()
+            .rev()
+            .map(|i| conjuncts.remove(i).expr)
+            .collect();
+        exprs.reverse();
+
+        let mut aliases = joined.aliases;
+        aliases.extend(right.aliases);
+        joined = Relation {
+            node: choose_build_side(join(joined.node, right.node, exprs)),
+            aliases,
+            rows,
+        };
+    }
+
+    joined.node
+}
+
+// Inner and cross joins whose constraints are all expressions, which can be
+// taken apart into conjuncts and put back together in any order.
+fn is_inner_join(node: &Node) -> bool {
+    let is_expr = |expr: &IntermediateExpr| matches!(expr, IntermediateExpr::Expr { .. });
+    match node {
+        Node::Join {
+            join_type: SqlJoinType::Inner | SqlJoinType::Cross,
+            constraint,
+            ..
+        } => constraint.iter().all(is_expr),
+        Node::HashJoin {
+            join_type: SqlJoinType::Inner | SqlJoinType::Cross,
+            keys,
+            residual,
+            ..
+        } => keys.iter().all(|(l, r)| is_expr(l) && is_expr(r)) && residual.iter().all(is_expr),
+        _ => false,
+    }
+}
+
+fn collect_leaves<'a, 'v>(node: &'a Node<'v>, leaves: &mut Vec<&'a Node<'v>>) {
+    match node {
+        Node::Join { left, right, .. } | Node::HashJoin { left, right, .. }
+            if is_inner_join(node) =>
+        {
+            collect_leaves(left, leaves);
+            collect_leaves(right, leaves);
+        }
+        _ => leaves.push(node),
+    }
+}
+
+fn flatten<'v>(node: Node<'v>, leaves: &mut Vec<Node<'v>>, conjuncts: &mut Vec<Expr>) {
+    if !is_inner_join(&node) {
+        leaves.push(node);
+        return;
+    }
+    let mut add = |expr: IntermediateExpr| {
+        if let IntermediateExpr::Expr { expr } = expr {
+            let mut flat = vec![];
+            flatten_conjuncts(&expr, &mut flat);
+            conjuncts.extend(flat.into_iter().cloned());
+        }
+    };
+    match node {
+        Node::Join {
+            left,
+            right,
+            constraint,
+            ..
+        } => {
+            constraint.into_iter().for_each(&mut add);
+            flatten(*left, leaves, conjuncts);
+            flatten(*right, leaves, conjuncts);
+        }
+        Node::HashJoin {
+            left,
+            right,
+            keys,
+            residual,
+            ..
+        } => {
+            for key in keys {
+                let (IntermediateExpr::Expr { expr: l }, IntermediateExpr::Expr { expr: r }) = key
+                else {
+                    continue;
+                };
+                let (span, id) = (l.get_span(), l.get_id());
+                add(IntermediateExpr::Expr {
+                    expr: Box::new(Expr::Binary {
+                        left: l,
+                        operation: BinaryOp::IsEqual,
+                        right: r,
+                        span,
+                        id,
+                    }),
+                });
+            }
+            residual.into_iter().for_each(&mut add);
+            flatten(*left, leaves, conjuncts);
+            flatten(*right, leaves, conjuncts);
+        }
+        _ => unreachable!(),
+    }
+}
+
+// The conjuncts that refer to some of the relations on the right and to no
+// relation outside the two sides.
+fn applicable(conjuncts: &[Conjunct], left: &[String], right: &[String]) -> Vec<usize> {
+    (0..conjuncts.len())
+        .filter(|&i| {
+            conjuncts[i].sources.as_ref().is_some_and(|sources| {
+                sources
+                    .iter()
+                    .all(|s| left.contains(s) || right.contains(s))
+                    && (sources.is_empty() || sources.iter().any(|s| right.contains(s)))
+            })
+        })
+        .collect()
+}
+
+fn join<'v>(left: Node<'v>, right: Node<'v>, conjuncts: Vec<Expr>) -> Node<'v> {
+    let Some(origin) = conjuncts.first().cloned() else {
+        return Node::Join {
+            left: Box::new(left),
+            join_type: SqlJoinType::Cross,
+            right: Box::new(right),
+            constraint: None,
+        };
+    };
+    let constraint = conjoin(conjuncts, &origin).unwrap();
+    let wrap = |expr: Expr| IntermediateExpr::Expr {
+        expr: Box::new(expr),
+    };
+
+    match split_equi_join(&constraint, &left.aliases(), &right.aliases()) {
+        Some(EquiJoin { keys, residual }) => Node::HashJoin {
+            left: Box::new(left),
+            join_type: SqlJoinType::Inner,
+            right: Box::new(right),
+            keys: keys.into_iter().map(|(l, r)| (wrap(l), wrap(r))).collect(),
+            residual: residual.map(wrap),
+            build: None,
+        },
+        None => Node::Join {
+            left: Box::new(left),
+            join_type: SqlJoinType::Inner,
+            right: Box::new(right),
+            constraint: Some(wrap(constraint)),
+        },
+    }
+}
+
+fn choose_build_side<'v>(node: Node<'v>) -> Node<'v> {
+    let Node::HashJoin {
+        left,
+        join_type,
+        right,
+        keys,
+        residual,
+        build: None,
+    } = node
+    else {
+        return node;
+    };
+    let build = match (estimate_rows(&left), estimate_rows(&right)) {
+        (Some(l), Some(r)) if l <= r => Some(JoinSide::Left),
+        (Some(_), Some(_)) => Some(JoinSide::Right),
+        _ => None,
+    };
+    Node::HashJoin {
+        left,
+        join_type,
+        right,
+        keys,
+        residual,
+        build,
+    }
+}
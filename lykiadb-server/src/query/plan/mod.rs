@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{cell::Cell, fmt::Display, ops::Bound};
 
 use indexmap::IndexMap;
 use lykiadb_lang::ast::{
@@ -11,16 +11,26 @@ use lykiadb_lang::ast::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::engine::{DEFAULT_NAMESPACE, statistics::CollectionStatistics};
+use crate::query::exec::profile::NodeProfile;
 use crate::value::{RV, array::RVArray, callable::AggregatorFactory, object::RVObject};
 use derivative::Derivative;
+use estimate::estimate_rows;
 use std::sync::Arc;
 
 mod aggregation;
 pub mod error;
+mod estimate;
 mod expr;
+mod fold;
 mod from;
+mod index;
+mod join;
+mod join_order;
 pub mod planner;
+mod rewrite;
 mod scope;
+mod subquery;
 
 crate::register_tests!("lykiadb-server/src/query/plan/tests");
 
@@ -41,9 +51,69 @@ impl<'v> Display for IntermediateExpr<'v> {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Assignment<'v> {
+    pub path: Vec<Identifier>,
+    pub value: IntermediateExpr<'v>,
+}
+
+impl<'v> Display for Assignment<'v> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self
+            .path
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        write!(f, "{path} = {}", self.value)
+    }
+}
+
+/// A range of values of the first field of an index. The bounds are
+/// evaluated when the index is read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexRange<'v> {
+    pub lower: Bound<IntermediateExpr<'v>>,
+    pub upper: Bound<IntermediateExpr<'v>>,
+}
+
+impl<'v> Display for IndexRange<'v> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.lower {
+            Bound::Included(expr) => write!(f, "[{expr}, ")?,
+            Bound::Excluded(expr) => write!(f, "({expr}, ")?,
+            Bound::Unbounded => write!(f, "(-inf, ")?,
+        }
+        match &self.upper {
+            Bound::Included(expr) => write!(f, "{expr}]"),
+            Bound::Excluded(expr) => write!(f, "{expr})"),
+            Bound::Unbounded => write!(f, "+inf)"),
+        }
+    }
+}
+
+/// A query nested in a filter predicate. The predicate looks its result up
+/// by `sign`, so uncorrelated subqueries are run once per query, while the
+/// correlated ones are run again for every row of the filter's source.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FilterSubquery<'v> {
+    pub sign: String,
+    pub correlated: bool,
+    pub plan: Node<'v>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinSide {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Plan<'v> {
     Select(Node<'v>),
+    Insert(Node<'v>),
+    Update(Node<'v>),
+    Delete(Node<'v>),
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -63,7 +133,7 @@ pub enum Node<'v> {
     Filter {
         source: Box<Node<'v>>,
         predicate: IntermediateExpr<'v>,
-        subqueries: Vec<Node<'v>>,
+        subqueries: Vec<FilterSubquery<'v>>,
     },
 
     Projection {
@@ -71,6 +141,10 @@ pub enum Node<'v> {
         fields: Vec<SqlProjection>,
     },
 
+    Distinct {
+        source: Box<Node<'v>>,
+    },
+
     Limit {
         source: Box<Node<'v>>,
         limit: usize,
@@ -86,9 +160,22 @@ pub enum Node<'v> {
         key: Vec<(IntermediateExpr<'v>, SqlOrdering)>,
     },
 
+    // Collections carry their statistics, if they were analyzed, for the
+    // plan to estimate how many rows its nodes return.
     Scan {
         source: SqlCollectionIdentifier,
         filter: Option<IntermediateExpr<'v>>,
+        statistics: Option<CollectionStatistics>,
+    },
+
+    // Reads the documents of a collection whose first indexed field falls
+    // in any of the ranges, instead of scanning the whole collection. Values
+    // the comparisons coerce are read too, so the scan is always filtered.
+    IndexScan {
+        source: SqlCollectionIdentifier,
+        index: String,
+        ranges: Vec<IndexRange<'v>>,
+        statistics: Option<CollectionStatistics>,
     },
 
     EvalScan {
@@ -103,20 +190,92 @@ pub enum Node<'v> {
         constraint: Option<IntermediateExpr<'v>>,
     },
 
+    // A join whose constraint has at least one equality between the two
+    // sides. Rows are matched through a hash table built on the keys, and
+    // the residual predicate is checked on the matched pairs only. The table
+    // is built on the side the planner estimated to be smaller, or on the
+    // smaller one at run time when there were no estimates.
+    HashJoin {
+        left: Box<Node<'v>>,
+        join_type: SqlJoinType,
+        right: Box<Node<'v>>,
+        keys: Vec<(IntermediateExpr<'v>, IntermediateExpr<'v>)>,
+        residual: Option<IntermediateExpr<'v>>,
+        build: Option<JoinSide>,
+    },
+
     Subquery {
         source: Box<Node<'v>>,
         alias: Identifier,
     },
 
+    Values {
+        values: Vec<IntermediateExpr<'v>>,
+    },
+
+    Insert {
+        collection: SqlCollectionIdentifier,
+        source: Box<Node<'v>>,
+    },
+
+    Update {
+        collection: SqlCollectionIdentifier,
+        assignments: Vec<Assignment<'v>>,
+        source: Box<Node<'v>>,
+    },
+
+    Delete {
+        collection: SqlCollectionIdentifier,
+        source: Box<Node<'v>>,
+    },
+
+    // Stands for a subtree that the planner proved to return no rows, e.g.
+    // one under a filter that is always false. The aliases are the ones the
+    // subtree would have exposed.
+    Empty {
+        aliases: Vec<String>,
+    },
+
     Nothing,
 }
 
 impl<'v> Plan<'v> {
-    pub fn to_object(&self) -> RV<'v> {
+    pub fn root(&self) -> &Node<'v> {
         match self {
-            Plan::Select(node) => node.to_object(),
+            Plan::Select(node) | Plan::Insert(node) | Plan::Update(node) | Plan::Delete(node) => {
+                node
+            }
         }
     }
+
+    pub fn to_object(&self) -> RV<'v> {
+        self.root().explain(None)
+    }
+
+    /// Describes the plan along with what each of its nodes did when it was
+    /// run, given in the order EXPLAIN lists the nodes.
+    pub fn to_analyzed_object(&self, profile: &[NodeProfile]) -> RV<'v> {
+        let measures = Measures {
+            nodes: profile,
+            next: Cell::new(0),
+        };
+        self.root().explain(Some(&measures))
+    }
+}
+
+// What EXPLAIN ANALYZE measured for the nodes of a plan, handed out to them
+// as they are described.
+struct Measures<'a> {
+    nodes: &'a [NodeProfile],
+    next: Cell<usize>,
+}
+
+impl Measures<'_> {
+    fn next(&self) -> NodeProfile {
+        let position = self.next.get();
+        self.next.set(position + 1);
+        self.nodes.get(position).copied().unwrap_or_default()
+    }
 }
 
 impl<'v> Display for Plan<'v> {
@@ -143,21 +302,161 @@ macro_rules! rv_str {
 }
 
 impl<'v> Node<'v> {
-    fn to_object(&self) -> RV<'v> {
+    /// Aliases under which the rows of this node expose their sources.
+    pub fn aliases(&self) -> Vec<String> {
+        match self {
+            Node::Scan { source, .. } | Node::IndexScan { source, .. } => {
+                vec![source.alias.as_ref().unwrap_or(&source.name).to_string()]
+            }
+            Node::EvalScan { source, .. } => vec![source.alias.to_string()],
+            Node::Empty { aliases } => aliases.clone(),
+            Node::Subquery { alias, .. } => vec![alias.to_string()],
+            Node::Join { left, right, .. } | Node::HashJoin { left, right, .. } => {
+                let mut aliases = left.aliases();
+                aliases.extend(right.aliases());
+                aliases
+            }
+            Node::Filter { source, .. }
+            | Node::Limit { source, .. }
+            | Node::Offset { source, .. }
+            | Node::Order { source, .. }
+            | Node::Distinct { source } => source.aliases(),
+            _ => vec![],
+        }
+    }
+
+    // Describes the node for EXPLAIN, along with the number of rows it is
+    // estimated to return when the collections it reads were analyzed, and
+    // what it did when the plan was run by EXPLAIN ANALYZE.
+    fn explain(&self, measures: Option<&Measures>) -> RV<'v> {
+        // Nodes take their measures before their children do.
+        let measured = measures.map(Measures::next);
+        let description = self.describe(measures);
+        let estimate = estimate_rows(self);
+        let RV::Object(fields) = &description else {
+            return description;
+        };
+        if estimate.is_none() && measured.is_none() {
+            return description;
+        }
+
+        let mut obj = IndexMap::new();
+        for (key, value) in fields.iter() {
+            let is_type = key == "@type";
+            obj.insert(key, value);
+            if !is_type {
+                continue;
+            }
+            if let Some(rows) = estimate {
+                let rows = if rows > 0.0 {
+                    rows.round().max(1.0)
+                } else {
+                    0.0
+                };
+                obj.insert("estimated_rows".to_string(), RV::Int64(rows as i64));
+            }
+            if let Some(measured) = measured {
+                obj.insert("actual_rows".to_string(), RV::Int64(measured.rows as i64));
+                obj.insert("loops".to_string(), RV::Int64(measured.loops as i64));
+                obj.insert(
+                    "elapsed_ms".to_string(),
+                    RV::Double((measured.elapsed.as_secs_f64() * 1e6).round() / 1e3),
+                );
+            }
+        }
+        RV::Object(RVObject::from_map(obj))
+    }
+
+    /// Number of nodes in the plan under this node, including itself and
+    /// the plans of nested queries, in the order EXPLAIN lists them.
+    pub fn size(&self) -> usize {
+        let children = match self {
+            Node::Filter {
+                source, subqueries, ..
+            } => source.size() + subqueries.iter().map(|s| s.plan.size()).sum::<usize>(),
+            Node::Compound { source, right, .. } => source.size() + right.size(),
+            Node::Join { left, right, .. } | Node::HashJoin { left, right, .. } => {
+                left.size() + right.size()
+            }
+            Node::Aggregate { source, .. }
+            | Node::Projection { source, .. }
+            | Node::Distinct { source }
+            | Node::Limit { source, .. }
+            | Node::Offset { source, .. }
+            | Node::Order { source, .. }
+            | Node::Subquery { source, .. }
+            | Node::Insert { source, .. }
+            | Node::Update { source, .. }
+            | Node::Delete { source, .. } => source.size(),
+            Node::Scan { .. }
+            | Node::IndexScan { .. }
+            | Node::EvalScan { .. }
+            | Node::Values { .. }
+            | Node::Empty { .. }
+            | Node::Nothing => 0,
+        };
+        1 + children
+    }
+
+    fn describe(&self, measures: Option<&Measures>) -> RV<'v> {
         match self {
             Node::Nothing => rv_object! { "@type" => rv_str!("nothing") },
 
-            Node::Scan { source, .. } => rv_object! {
-                "@type" => rv_str!("scan"),
+            Node::Empty { .. } => rv_object! { "@type" => rv_str!("empty") },
+
+            Node::Scan { source, filter, .. } => {
+                let mut obj = IndexMap::new();
+
+                obj.insert("@type".to_string(), rv_str!("scan"));
+                obj.insert("collection".to_string(), rv_str!(source.name.name));
+                obj.insert(
+                    "alias".to_string(),
+                    rv_str!(
+                        source
+                            .alias
+                            .as_ref()
+                            .map(|a| a.name.clone())
+                            .unwrap_or_else(|| source.name.name.clone())
+                    ),
+                );
+
+                if let Some(filter) = filter {
+                    obj.insert("filter".to_string(), rv_str!(filter.to_string()));
+                }
+
+                RV::Object(RVObject::from_map(obj))
+            }
+
+            Node::IndexScan {
+                source,
+                index,
+                ranges,
+                ..
+            } => rv_object! {
+                "@type" => rv_str!("index_scan"),
                 "collection" => rv_str!(source.name.name),
                 "alias" => rv_str!(source.alias.as_ref().map(|a| a.name.clone()).unwrap_or_else(|| source.name.name.clone())),
+                "index" => rv_str!(index),
+                "ranges" => RV::Array(
+                    RVArray::from_vec(
+                        ranges.iter().map(|r| rv_str!(r.to_string())).collect::<Vec<_>>(),
+                    )
+                ),
             },
 
-            Node::EvalScan { source, .. } => rv_object! {
-                "@type" => rv_str!("eval_scan"),
-                "expr" => rv_str!(source.expr.to_string()),
-                "alias" => rv_str!(source.alias.name),
-            },
+            Node::EvalScan { source, filter } => {
+                let mut obj = IndexMap::new();
+
+                obj.insert("@type".to_string(), rv_str!("eval_scan"));
+                obj.insert("expr".to_string(), rv_str!(source.expr.to_string()));
+                obj.insert("alias".to_string(), rv_str!(source.alias.name));
+
+                if let Some(filter) = filter {
+                    obj.insert("filter".to_string(), rv_str!(filter.to_string()));
+                }
+
+                RV::Object(RVObject::from_map(obj))
+            }
 
             Node::Filter {
                 source,
@@ -168,12 +467,16 @@ impl<'v> Node<'v> {
 
                 obj.insert("@type".to_string(), rv_str!("filter"));
                 obj.insert("predicate".to_string(), rv_str!(predicate.to_string()));
-                obj.insert("source".to_string(), source.to_object());
+                obj.insert("source".to_string(), source.explain(measures));
 
                 if !subqueries.is_empty() {
                     obj.insert("subqueries".to_string(), rv_object!{ 
                         "@type" => rv_str!("subqueries"), 
-                        "queries" => RV::Array(RVArray::from_vec(subqueries.iter().map(|s| s.to_object()).collect()))
+                        "queries" => RV::Array(RVArray::from_vec(subqueries.iter().map(|s| rv_object! {
+                            "@type" => rv_str!("subquery"),
+                            "correlated" => RV::Bool(s.correlated),
+                            "source" => s.plan.explain(measures),
+                        }).collect()))
                     });
                 }
 
@@ -200,7 +503,7 @@ impl<'v> Node<'v> {
                 rv_object! {
                     "@type" => rv_str!("projection"),
                     "fields" => RV::Array(field_strs),
-                    "source" => source.to_object(),
+                    "source" => source.explain(measures),
                 }
             }
 
@@ -220,7 +523,7 @@ impl<'v> Node<'v> {
                         aggregates.iter().map(|a| rv_str!(a.to_string())).collect::<Vec<_>>(),
                     )
                 ),
-                "source" => source.to_object(),
+                "source" => source.explain(measures),
             },
 
             Node::Order { source, key } => {
@@ -241,20 +544,25 @@ impl<'v> Node<'v> {
                 rv_object! {
                     "@type" => rv_str!("order"),
                     "key" => key_json,
-                    "source" => source.to_object(),
+                    "source" => source.explain(measures),
                 }
             }
 
+            Node::Distinct { source } => rv_object! {
+                "@type" => rv_str!("distinct"),
+                "source" => source.explain(measures),
+            },
+
             Node::Limit { source, limit } => rv_object! {
                 "@type" => rv_str!("limit"),
                 "count" => RV::Int64(*limit as i64),
-                "source" => source.to_object(),
+                "source" => source.explain(measures),
             },
 
             Node::Offset { source, offset } => rv_object! {
                 "@type" => rv_str!("offset"),
                 "count" => RV::Int64(*offset as i64),
-                "source" => source.to_object(),
+                "source" => source.explain(measures),
             },
 
             Node::Join {
@@ -262,20 +570,56 @@ impl<'v> Node<'v> {
                 join_type,
                 right,
                 constraint,
+            } => rv_object! {
+                "@type" => rv_str!("join"),
+                "join_type" => rv_str!(join_type_str(join_type)),
+                "strategy" => rv_str!("nested_loop"),
+                "constraint" => constraint.as_ref().map(|c| rv_str!(c.to_string())).unwrap_or(RV::Undefined),
+                "left" => left.explain(measures),
+                "right" => right.explain(measures),
+            },
+
+            Node::HashJoin {
+                left,
+                join_type,
+                right,
+                keys,
+                residual,
+                build,
             } => {
-                let join_type_str = match join_type {
-                    SqlJoinType::Inner => "inner",
-                    SqlJoinType::Cross => "cross",
-                    SqlJoinType::Left => "left",
-                    SqlJoinType::Right => "right",
-                };
-                rv_object! {
-                    "@type" => rv_str!("join"),
-                    "join_type" => rv_str!(join_type_str),
-                    "constraint" => constraint.as_ref().map(|c| rv_str!(c.to_string())).unwrap_or(RV::Undefined),
-                    "left" => left.to_object(),
-                    "right" => right.to_object(),
+                let mut obj = IndexMap::new();
+
+                obj.insert("@type".to_string(), rv_str!("join"));
+                obj.insert("join_type".to_string(), rv_str!(join_type_str(join_type)));
+                obj.insert("strategy".to_string(), rv_str!("hash"));
+                obj.insert(
+                    "keys".to_string(),
+                    RV::Array(RVArray::from_vec(
+                        keys.iter()
+                            .map(|(l, r)| rv_str!(format!("{l} = {r}")))
+                            .collect::<Vec<_>>(),
+                    )),
+                );
+                obj.insert(
+                    "residual".to_string(),
+                    residual
+                        .as_ref()
+                        .map(|c| rv_str!(c.to_string()))
+                        .unwrap_or(RV::Undefined),
+                );
+
+                if let Some(build) = build {
+                    let side = match build {
+                        JoinSide::Left => "left",
+                        JoinSide::Right => "right",
+                    };
+                    obj.insert("build".to_string(), rv_str!(side));
                 }
+
+                obj.insert("left".to_string(), left.explain(measures));
+                obj.insert("right".to_string(), right.explain(measures));
+
+                RV::Object(RVObject::from_map(obj))
             }
 
             Node::Compound {
@@ -292,20 +636,76 @@ impl<'v> Node<'v> {
                 rv_object! {
                     "@type" => rv_str!("compound"),
                     "operator" => rv_str!(op_str),
-                    "source" => source.to_object(),
-                    "right" => right.to_object(),
+                    "source" => source.explain(measures),
+                    "right" => right.explain(measures),
                 }
             }
 
             Node::Subquery { source, alias } => rv_object! {
                 "@type" => rv_str!("subquery"),
                 "alias" => rv_str!(alias.name),
-                "source" => source.to_object(),
+                "source" => source.explain(measures),
+            },
+
+            Node::Values { values } => rv_object! {
+                "@type" => rv_str!("values"),
+                "values" => RV::Array(
+                    RVArray::from_vec(
+                        values.iter().map(|v| rv_str!(v.to_string())).collect::<Vec<_>>(),
+                    )
+                ),
+            },
+
+            Node::Insert { collection, source } => rv_object! {
+                "@type" => rv_str!("insert"),
+                "namespace" => rv_str!(namespace_str(collection)),
+                "collection" => rv_str!(collection.name.name),
+                "source" => source.explain(measures),
+            },
+
+            Node::Update {
+                collection,
+                assignments,
+                source,
+            } => rv_object! {
+                "@type" => rv_str!("update"),
+                "namespace" => rv_str!(namespace_str(collection)),
+                "collection" => rv_str!(collection.name.name),
+                "assignments" => RV::Array(
+                    RVArray::from_vec(
+                        assignments.iter().map(|a| rv_str!(a.to_string())).collect::<Vec<_>>(),
+                    )
+                ),
+                "source" => source.explain(measures),
+            },
+
+            Node::Delete { collection, source } => rv_object! {
+                "@type" => rv_str!("delete"),
+                "namespace" => rv_str!(namespace_str(collection)),
+                "collection" => rv_str!(collection.name.name),
+                "source" => source.explain(measures),
             },
         }
     }
 }
 
+// The namespace the collection is in, the default one unless it is named.
+fn namespace_str(collection: &SqlCollectionIdentifier) -> &str {
+    collection
+        .namespace
+        .as_ref()
+        .map_or(DEFAULT_NAMESPACE, |namespace| namespace.name.as_str())
+}
+
+fn join_type_str(join_type: &SqlJoinType) -> &'static str {
+    match join_type {
+        SqlJoinType::Inner => "inner",
+        SqlJoinType::Cross => "cross",
+        SqlJoinType::Left => "left",
+        SqlJoinType::Right => "right",
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
 pub struct Aggregation<'v> {
@@ -315,6 +715,7 @@ pub struct Aggregation<'v> {
     #[derivative(Hash = "ignore")]
     pub callable: Option<AggregatorFactory<'v>>,
     pub args: Vec<Expr>,
+    pub distinct: bool,
     pub call_expr: Expr,
     pub call_sign: String,
 }
@@ -330,6 +731,7 @@ impl<'v> Aggregation<'v> {
             name: agg_name.to_string(),
             callable: Some(*agg_factory),
             args: args.clone(),
+            distinct: matches!(expr, Expr::Call { distinct: true, .. }),
             call_expr: expr.clone(),
             call_sign: expr.sign(),
         }
@@ -340,8 +742,9 @@ impl<'v> Display for Aggregation<'v> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}({})",
+            "{}({}{})",
             self.name,
+            if self.distinct { "DISTINCT " } else { "" },
             self.args
                 .iter()
                 .map(|x| x.to_string())
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use crate::{
+    engine::StoreId,
     execution::error::ExecutionError,
     interpreter::HaltReason,
     query::{
@@ -11,15 +12,25 @@ use crate::{
 };
 
 use lykiadb_lang::ast::{
-    Spanned,
+    Literal, Spanned,
     expr::Expr,
-    sql::{SqlProjection, SqlSelect, SqlSelectCore},
+    sql::{
+        SqlCollectionIdentifier, SqlDelete, SqlDistinct, SqlFrom, SqlInsert, SqlProjection,
+        SqlSelect, SqlSelectCore, SqlSource, SqlUpdate, SqlValues,
+    },
     visitor::ExprVisitor,
 };
 
 use super::{
-    IntermediateExpr, Node, Plan, aggregation::collect_aggregates, expr::SqlExprReducer,
-    from::build_from, scope::Scope,
+    Assignment, FilterSubquery, IndexRange, IntermediateExpr, Node, Plan,
+    aggregation::collect_aggregates,
+    expr::SqlExprReducer,
+    fold::fold,
+    from::build_from,
+    index::{IndexPredicate, find_index_predicate},
+    rewrite::rewrite,
+    scope::Scope,
+    subquery::is_correlated,
 };
 
 #[derive(Debug)]
@@ -30,6 +41,8 @@ pub enum InClause {
     GroupBy,
     OrderBy,
     JoinOn,
+    Values,
+    Set,
 }
 
 impl Display for InClause {
@@ -41,6 +54,8 @@ impl Display for InClause {
             InClause::GroupBy => write!(f, "GROUP BY"),
             InClause::OrderBy => write!(f, "ORDER BY"),
             InClause::JoinOn => write!(f, "JOIN ON"),
+            InClause::Values => write!(f, "VALUES"),
+            InClause::Set => write!(f, "SET"),
         }
     }
 }
@@ -65,7 +80,19 @@ impl<'v, 'q> Planner {
     ) -> Result<Plan<'v>, HaltReason<'v>> {
         match expr {
             Expr::Select { query, .. } => {
-                let plan = Plan::Select(self.build_select(query, exec_ctx)?);
+                let plan = Plan::Select(rewrite(self.build_select(query, exec_ctx)?));
+                Ok(plan)
+            }
+            Expr::Insert { command, .. } => {
+                let plan = Plan::Insert(rewrite(self.build_insert(command, exec_ctx)?));
+                Ok(plan)
+            }
+            Expr::Update { command, .. } => {
+                let plan = Plan::Update(rewrite(self.build_update(command, exec_ctx)?));
+                Ok(plan)
+            }
+            Expr::Delete { command, .. } => {
+                let plan = Plan::Delete(rewrite(self.build_delete(command, exec_ctx)?));
                 Ok(plan)
             }
             _ => panic!("Bummer."),
@@ -88,7 +115,15 @@ impl<'v, 'q> Planner {
         allow_subqueries: bool,
         allow_aggregates: bool,
         exec_ctx: &'q QueryExecutionContext<'v>,
-    ) -> Result<(IntermediateExpr<'v>, Vec<Node<'v>>), HaltReason<'v>> {
+    ) -> Result<(IntermediateExpr<'v>, Vec<FilterSubquery<'v>>), HaltReason<'v>> {
+        // Conditions are only checked for truthiness, which lets the folding
+        // drop the operands that do not change it.
+        let fold_as = match in_clause {
+            InClause::Where | InClause::Having | InClause::JoinOn => Some(true),
+            InClause::Values | InClause::Set => Some(false),
+            _ => None,
+        };
+
         if !allow_aggregates {
             prevent_aggregates_in(expr, in_clause, exec_ctx)?;
         }
@@ -99,21 +134,44 @@ impl<'v, 'q> Planner {
             scope,
         );
 
-        let mut visitor = ExprVisitor::<SqlSelect, HaltReason<'v>>::new(&mut reducer);
+        let mut visitor = ExprVisitor::<Expr, HaltReason<'v>>::new(&mut reducer);
 
         let selects = visitor.visit(expr)?;
 
-        let subqueries = selects
-            .into_iter()
-            .map(|select| self.build_select(&select, exec_ctx))
-            .collect::<Result<Vec<Node<'v>>, HaltReason<'v>>>()?;
+        let mut subqueries = vec![];
+
+        for select in selects {
+            let Expr::Select { query, .. } = &select else {
+                unreachable!()
+            };
+            subqueries.push(FilterSubquery {
+                sign: select.sign(),
+                correlated: is_correlated(query),
+                plan: self.build_select(query, exec_ctx)?,
+            });
+        }
 
-        Ok((
-            IntermediateExpr::Expr {
-                expr: Box::new(expr.clone()),
+        let Some(condition) = fold_as else {
+            return Ok((
+                IntermediateExpr::Expr {
+                    expr: Box::new(expr.clone()),
+                },
+                subqueries,
+            ));
+        };
+
+        // A scalar that is known upfront is not evaluated again for each row.
+        let intermediate = match fold(expr, condition, scope, exec_ctx) {
+            expr @ Expr::Literal {
+                value: Literal::Num(_) | Literal::Str(_) | Literal::Bool(_) | Literal::Undefined,
+                ..
+            } => IntermediateExpr::Constant(exec_ctx.eval(&expr)?),
+            expr => IntermediateExpr::Expr {
+                expr: Box::new(expr),
             },
-            subqueries,
-        ))
+        };
+
+        Ok((intermediate, subqueries))
     }
 }
 
@@ -153,19 +211,7 @@ impl<'v, 'q> Planner {
         }
 
         if let Some(predicate) = &core.r#where {
-            let (expr, subqueries): (IntermediateExpr, Vec<Node>) = self.build_expr(
-                predicate.as_ref(),
-                InClause::Where,
-                &mut core_scope,
-                true,
-                false,
-                exec_ctx,
-            )?;
-            node = Node::Filter {
-                source: Box::new(node),
-                predicate: expr,
-                subqueries,
-            }
+            node = self.build_where(node, predicate, &mut core_scope, exec_ctx)?;
         }
 
         let aggregates = collect_aggregates(core, exec_ctx)?;
@@ -206,7 +252,7 @@ impl<'v, 'q> Planner {
             };
 
             if let Some(having) = &core.having {
-                let (expr, subqueries): (IntermediateExpr, Vec<Node>) = self.build_expr(
+                let (expr, subqueries): (IntermediateExpr, Vec<FilterSubquery>) = self.build_expr(
                     having,
                     InClause::Having,
                     &mut core_scope,
@@ -246,6 +292,12 @@ impl<'v, 'q> Planner {
             };
         }
 
+        if core.distinct == SqlDistinct::Distinct {
+            node = Node::Distinct {
+                source: Box::new(node),
+            };
+        }
+
         if let Some(compound) = &core.compound {
             node = Node::Compound {
                 source: Box::new(node),
@@ -256,6 +308,94 @@ impl<'v, 'q> Planner {
         Ok(node)
     }
 
+    fn build_where(
+        &mut self,
+        source: Node<'v>,
+        predicate: &Expr,
+        scope: &mut Scope,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<Node<'v>, HaltReason<'v>> {
+        let (expr, subqueries): (IntermediateExpr, Vec<FilterSubquery>) =
+            self.build_expr(predicate, InClause::Where, scope, true, false, exec_ctx)?;
+
+        // A collection scan filtered on an indexed field reads the index
+        // instead. Comparisons coerce values of other types than the one in
+        // the index, so the index is read for those too, and the filter is
+        // kept whole to drop what does not match.
+        if let Node::Scan {
+            source: collection,
+            statistics,
+            ..
+        } = &source
+            && let IntermediateExpr::Expr { expr: predicate } = &expr
+            && let Some(IndexPredicate { index, ranges }) =
+                self.find_index_predicate(collection, predicate, exec_ctx)
+        {
+            let wrap = |expr: Expr| IntermediateExpr::Expr {
+                expr: Box::new(expr),
+            };
+            let node = Node::IndexScan {
+                source: collection.clone(),
+                index,
+                ranges: ranges
+                    .into_iter()
+                    .map(|(lower, upper)| IndexRange {
+                        lower: lower.map(wrap),
+                        upper: upper.map(wrap),
+                    })
+                    .collect(),
+                statistics: statistics.clone(),
+            };
+            return Ok(Node::Filter {
+                source: Box::new(node),
+                predicate: expr,
+                subqueries,
+            });
+        }
+
+        Ok(Node::Filter {
+            source: Box::new(source),
+            predicate: expr,
+            subqueries,
+        })
+    }
+
+    fn find_index_predicate(
+        &self,
+        collection: &SqlCollectionIdentifier,
+        predicate: &Expr,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Option<IndexPredicate> {
+        let engine = exec_ctx.engine().read().unwrap();
+        // Unknown collections are reported when they are scanned.
+        let metadata = engine
+            .catalog()
+            .collection(&StoreId::from(collection))
+            .ok()?;
+        let alias = collection.alias.as_ref().unwrap_or(&collection.name);
+        find_index_predicate(predicate, &alias.name, &metadata.indexes)
+    }
+
+    // Mutations operate on a single collection. The documents to be mutated
+    // are read through a regular scan, optionally followed by a filter for
+    // the WHERE clause, just like a SELECT would do.
+    fn build_mutation_source(
+        &mut self,
+        collection: &SqlCollectionIdentifier,
+        predicate: Option<&Expr>,
+        scope: &mut Scope,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<Node<'v>, HaltReason<'v>> {
+        let from = SqlFrom::Source(SqlSource::Collection(collection.clone()));
+        let mut node = build_from(self, &from, scope, exec_ctx)?;
+
+        if let Some(predicate) = predicate {
+            node = self.build_where(node, predicate, scope, exec_ctx)?;
+        }
+
+        Ok(node)
+    }
+
     pub fn build_select(
         &mut self,
         query: &SqlSelect,
@@ -308,6 +448,92 @@ impl<'v, 'q> Planner {
 
         Ok(node)
     }
+
+    pub fn build_insert(
+        &mut self,
+        command: &SqlInsert,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<Node<'v>, HaltReason<'v>> {
+        let source = match &command.values {
+            SqlValues::Values { values } => {
+                let mut scope = Scope::new();
+                let mut exprs = vec![];
+                for value in values {
+                    let (expr, _) = self.build_expr(
+                        value,
+                        InClause::Values,
+                        &mut scope,
+                        false,
+                        false,
+                        exec_ctx,
+                    )?;
+                    exprs.push(expr);
+                }
+                Node::Values { values: exprs }
+            }
+            SqlValues::Select(query) => self.build_select(query, exec_ctx)?,
+        };
+
+        Ok(Node::Insert {
+            collection: command.collection.clone(),
+            source: Box::new(source),
+        })
+    }
+
+    pub fn build_update(
+        &mut self,
+        command: &SqlUpdate,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<Node<'v>, HaltReason<'v>> {
+        let mut scope = Scope::new();
+
+        let source = self.build_mutation_source(
+            &command.collection,
+            command.r#where.as_deref(),
+            &mut scope,
+            exec_ctx,
+        )?;
+
+        let mut assignments = vec![];
+        for assignment in &command.assignments {
+            let (value, _) = self.build_expr(
+                &assignment.expr,
+                InClause::Set,
+                &mut scope,
+                false,
+                false,
+                exec_ctx,
+            )?;
+            assignments.push(Assignment {
+                path: assignment.path.clone(),
+                value,
+            });
+        }
+
+        Ok(Node::Update {
+            collection: command.collection.clone(),
+            assignments,
+            source: Box::new(source),
+        })
+    }
+
+    pub fn build_delete(
+        &mut self,
+        command: &SqlDelete,
+        exec_ctx: &'q QueryExecutionContext<'v>,
+    ) -> Result<Node<'v>, HaltReason<'v>> {
+        let source = self.build_mutation_source(
+            &command.collection,
+            command.r#where.as_deref(),
+            &mut Scope::new(),
+            exec_ctx,
+        )?;
+
+        Ok(Node::Delete {
+            collection: command.collection.clone(),
+            source: Box::new(source),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -322,6 +548,7 @@ mod tests {
                 scope::tests::create_test_scope,
             },
         },
+        value::RV,
     };
     use lykiadb_common::extract;
     use lykiadb_lang::ast::{
@@ -359,6 +586,18 @@ mod tests {
         };
     }
 
+    macro_rules! assert_build_expr_constant {
+        ($result:expr, $expected_value:expr) => {
+            assert!($result.is_ok());
+            let (intermediate_expr, subqueries) = $result.unwrap();
+
+            extract!(IntermediateExpr::Constant(value), intermediate_expr);
+
+            assert_eq!(value, $expected_value);
+            assert!(subqueries.is_empty());
+        };
+    }
+
     #[test]
     fn test_build_expr_simple_literal() {
         let (mut planner, exec_ctx) = create_test_planner();
@@ -367,8 +606,8 @@ mod tests {
 
         let result = planner.build_expr(&expr, InClause::Where, &mut scope, false, false, exec_ctx);
 
-        // Use helper function to assert standard expectations
-        assert_build_expr_result!(result, &expr, 0);
+        // Literals in a WHERE are known before the query runs
+        assert_build_expr_constant!(result, RV::Double(42.0));
     }
 
     #[test]
@@ -587,7 +826,7 @@ mod tests {
 
         let result = planner.build_expr(&expr, InClause::Where, &mut scope, false, false, exec_ctx);
 
-        assert_build_expr_result!(result, &expr, 0);
+        assert_build_expr_constant!(result, RV::Bool(true));
     }
 
     #[test]
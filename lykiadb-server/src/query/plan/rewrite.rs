@@ -0,0 +1,384 @@
+use lykiadb_lang::ast::{
+    AstNode, Spanned,
+    expr::{BinaryOp, Expr},
+    sql::SqlJoinType,
+};
+
+use super::{
+    FilterSubquery, IntermediateExpr, Node,
+    join::{flatten_conjuncts, sources_of},
+    join_order::order_joins,
+};
+
+/// Rewrites a plan into one that returns the same rows for less work. The
+/// children of a node are simplified before the node itself, including the
+/// plans of nested queries. Joins are ordered last, once the filters have
+/// found their place.
+pub fn rewrite<'v>(node: Node<'v>) -> Node<'v> {
+    order_joins(simplify(node))
+}
+
+fn simplify<'v>(node: Node<'v>) -> Node<'v> {
+    push_down_filter(collapse(map_children(node, &simplify)))
+}
+
+/// Applies `f` to the children of the node, including the plans of nested
+/// queries.
+pub(super) fn map_children<'v>(node: Node<'v>, f: &impl Fn(Node<'v>) -> Node<'v>) -> Node<'v> {
+    let map_box = |node: Box<Node<'v>>| Box::new(f(*node));
+    match node {
+        Node::Compound {
+            source,
+            operator,
+            right,
+        } => Node::Compound {
+            source: map_box(source),
+            operator,
+            right: map_box(right),
+        },
+        Node::Aggregate {
+            source,
+            group_by,
+            aggregates,
+        } => Node::Aggregate {
+            source: map_box(source),
+            group_by,
+            aggregates,
+        },
+        Node::Filter {
+            source,
+            predicate,
+            subqueries,
+        } => Node::Filter {
+            source: map_box(source),
+            predicate,
+            subqueries: subqueries
+                .into_iter()
+                .map(|subquery| FilterSubquery {
+                    plan: f(subquery.plan),
+                    ..subquery
+                })
+                .collect(),
+        },
+        Node::Projection { source, fields } => Node::Projection {
+            source: map_box(source),
+            fields,
+        },
+        Node::Distinct { source } => Node::Distinct {
+            source: map_box(source),
+        },
+        Node::Limit { source, limit } => Node::Limit {
+            source: map_box(source),
+            limit,
+        },
+        Node::Offset { source, offset } => Node::Offset {
+            source: map_box(source),
+            offset,
+        },
+        Node::Order { source, key } => Node::Order {
+            source: map_box(source),
+            key,
+        },
+        Node::Join {
+            left,
+            join_type,
+            right,
+            constraint,
+        } => Node::Join {
+            left: map_box(left),
+            join_type,
+            right: map_box(right),
+            constraint,
+        },
+        Node::HashJoin {
+            left,
+            join_type,
+            right,
+            keys,
+            residual,
+            build,
+        } => Node::HashJoin {
+            left: map_box(left),
+            join_type,
+            right: map_box(right),
+            keys,
+            residual,
+            build,
+        },
+        Node::Subquery { source, alias } => Node::Subquery {
+            source: map_box(source),
+            alias,
+        },
+        Node::Insert { collection, source } => Node::Insert {
+            collection,
+            source: map_box(source),
+        },
+        Node::Update {
+            collection,
+            assignments,
+            source,
+        } => Node::Update {
+            collection,
+            assignments,
+            source: map_box(source),
+        },
+        Node::Delete { collection, source } => Node::Delete {
+            collection,
+            source: map_box(source),
+        },
+        node @ (Node::Scan { .. }
+        | Node::IndexScan { .. }
+        | Node::EvalScan { .. }
+        | Node::Values { .. }
+        | Node::Empty { .. }
+        | Node::Nothing) => node,
+    }
+}
+
+// Drops the filters and join constraints that are always true, and replaces
+// the nodes that return no rows with an empty node, which spares the work
+// of their children.
+fn collapse<'v>(node: Node<'v>) -> Node<'v> {
+    if returns_nothing(&node) {
+        return Node::Empty {
+            aliases: node.aliases(),
+        };
+    }
+    match node {
+        Node::Filter {
+            source,
+            predicate: IntermediateExpr::Constant(_),
+            ..
+        } => *source,
+        Node::Join {
+            left,
+            join_type: join_type @ (SqlJoinType::Inner | SqlJoinType::Cross),
+            right,
+            constraint: Some(IntermediateExpr::Constant(_)),
+        } => Node::Join {
+            left,
+            join_type,
+            right,
+            constraint: None,
+        },
+        node => node,
+    }
+}
+
+// Only the nodes whose rows all come from the listed children are collapsed.
+fn returns_nothing(node: &Node) -> bool {
+    let empty = |node: &Node| matches!(node, Node::Empty { .. });
+    match node {
+        Node::Filter {
+            predicate: IntermediateExpr::Constant(value),
+            ..
+        } => !value.to_bool(),
+        Node::Filter { source, .. }
+        | Node::Projection { source, .. }
+        | Node::Distinct { source }
+        | Node::Limit { source, .. }
+        | Node::Offset { source, .. }
+        | Node::Order { source, .. }
+        | Node::Subquery { source, .. } => empty(source),
+        Node::Join {
+            join_type: SqlJoinType::Inner | SqlJoinType::Cross,
+            constraint: Some(IntermediateExpr::Constant(value)),
+            ..
+        } if !value.to_bool() => true,
+        // The side whose rows are all kept decides whether an outer join
+        // returns anything.
+        Node::Join {
+            left,
+            join_type,
+            right,
+            ..
+        }
+        | Node::HashJoin {
+            left,
+            join_type,
+            right,
+            ..
+        } => match join_type {
+            SqlJoinType::Left => empty(left),
+            SqlJoinType::Right => empty(right),
+            _ => empty(left) || empty(right),
+        },
+        _ => false,
+    }
+}
+
+// Moves the conjuncts of a filter that refer to a single source into the
+// filter of the scan that reads the source, so that the rows they reject
+// are dropped before reaching any join. The filter keeps whatever could
+// not be moved, and goes away when nothing is left.
+fn push_down_filter<'v>(node: Node<'v>) -> Node<'v> {
+    let Node::Filter {
+        source,
+        predicate: IntermediateExpr::Expr { expr },
+        subqueries,
+    } = node
+    else {
+        return node;
+    };
+
+    let mut conjuncts = vec![];
+    flatten_conjuncts(&expr, &mut conjuncts);
+    let conjuncts = conjuncts.into_iter().cloned().collect();
+
+    let aliases = source.aliases();
+    let (source, rest) = push_down(*source, conjuncts, &aliases);
+
+    match conjoin(rest, &expr) {
+        Some(predicate) => Node::Filter {
+            source: Box::new(source),
+            predicate: IntermediateExpr::Expr {
+                expr: Box::new(predicate),
+            },
+            subqueries,
+        },
+        None => source,
+    }
+}
+
+// Places the conjuncts in the scans under `node`, and returns the ones that
+// found no place. `aliases` are all the sources the conjuncts might refer to.
+fn push_down<'v>(
+    node: Node<'v>,
+    conjuncts: Vec<Expr>,
+    aliases: &[String],
+) -> (Node<'v>, Vec<Expr>) {
+    match node {
+        Node::Scan {
+            source,
+            filter,
+            statistics,
+        } if !is_constant(&filter) => {
+            let alias = source.alias.as_ref().unwrap_or(&source.name).to_string();
+            let (here, rest) = split_for(conjuncts, &alias, aliases);
+            let filter = add_to_filter(filter, here);
+            let node = Node::Scan {
+                source,
+                filter,
+                statistics,
+            };
+            (node, rest)
+        }
+        Node::EvalScan { source, filter } if !is_constant(&filter) => {
+            let (here, rest) = split_for(conjuncts, &source.alias.name, aliases);
+            let filter = add_to_filter(filter, here);
+            (Node::EvalScan { source, filter }, rest)
+        }
+        Node::Filter {
+            source,
+            predicate,
+            subqueries,
+        } => {
+            let (source, rest) = push_down(*source, conjuncts, aliases);
+            let node = Node::Filter {
+                source: Box::new(source),
+                predicate,
+                subqueries,
+            };
+            (node, rest)
+        }
+        // The rows an outer join makes up for its unmatched side would not
+        // be rejected by a filter under that side, so conjuncts only go to
+        // the side whose rows are all kept.
+        Node::Join {
+            left,
+            join_type,
+            right,
+            constraint,
+        } => {
+            let (left, right, rest) = push_down_join(*left, &join_type, *right, conjuncts, aliases);
+            let node = Node::Join {
+                left: Box::new(left),
+                join_type,
+                right: Box::new(right),
+                constraint,
+            };
+            (node, rest)
+        }
+        Node::HashJoin {
+            left,
+            join_type,
+            right,
+            keys,
+            residual,
+            build,
+        } => {
+            let (left, right, rest) = push_down_join(*left, &join_type, *right, conjuncts, aliases);
+            let node = Node::HashJoin {
+                left: Box::new(left),
+                join_type,
+                right: Box::new(right),
+                keys,
+                residual,
+                build,
+            };
+            (node, rest)
+        }
+        node => (node, conjuncts),
+    }
+}
+
+fn push_down_join<'v>(
+    left: Node<'v>,
+    join_type: &SqlJoinType,
+    right: Node<'v>,
+    conjuncts: Vec<Expr>,
+    aliases: &[String],
+) -> (Node<'v>, Node<'v>, Vec<Expr>) {
+    let (left, rest) = match join_type {
+        SqlJoinType::Right => (left, conjuncts),
+        _ => push_down(left, conjuncts, aliases),
+    };
+    let (right, rest) = match join_type {
+        SqlJoinType::Left => (right, rest),
+        _ => push_down(right, rest, aliases),
+    };
+    (left, right, rest)
+}
+
+// Splits the conjuncts into the ones that only refer to `alias`, and the
+// rest. Conjuncts that refer to no source at all are left where they are.
+fn split_for(conjuncts: Vec<Expr>, alias: &str, aliases: &[String]) -> (Vec<Expr>, Vec<Expr>) {
+    conjuncts.into_iter().partition(|conjunct| {
+        sources_of(conjunct, aliases)
+            .is_some_and(|sources| !sources.is_empty() && sources.iter().all(|s| s == alias))
+    })
+}
+
+fn is_constant(filter: &Option<IntermediateExpr>) -> bool {
+    matches!(filter, Some(IntermediateExpr::Constant(_)))
+}
+
+fn add_to_filter<'v>(
+    filter: Option<IntermediateExpr<'v>>,
+    conjuncts: Vec<Expr>,
+) -> Option<IntermediateExpr<'v>> {
+    let Some(first) = conjuncts.first() else {
+        return filter;
+    };
+    let mut all = match filter {
+        Some(IntermediateExpr::Expr { expr }) => vec![*expr],
+        Some(IntermediateExpr::Constant(_)) | None => vec![],
+    };
+    let origin = first.clone();
+    all.extend(conjuncts);
+    conjoin(all, &origin).map(|expr| IntermediateExpr::Expr {
+        expr: Box::new(expr),
+    })
+}
+
+// Joins the conjuncts back with ANDs, which take the span and id of
+// `origin`.
+pub(super) fn conjoin(conjuncts: Vec<Expr>, origin: &Expr) -> Option<Expr> {
+    conjuncts.into_iter().reduce(|acc, next| Expr::Logical {
+        left: Box::new(acc),
+        operation: BinaryOp::And,
+        right: Box::new(next),
+        span: origin.get_span(),
+        id: origin.get_id(),
+    })
+}
@@ -47,6 +47,10 @@ impl Scope {
     pub fn is_path_valid(&self, head: &Identifier, _tail: &[Identifier]) -> bool {
         self.from.get(head).is_some()
     }
+
+    pub fn has_source(&self, name: &str) -> bool {
+        self.from.keys().any(|alias| alias.name == name)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use lykiadb_lang::ast::{
+    expr::Expr,
+    sql::{SqlFrom, SqlProjection, SqlSelect, SqlSelectCore},
+    visitor::{ExprReducer, ExprVisitor, ExprVisitorNode},
+};
+
+/// A subquery is correlated when it refers to a source it does not define
+/// itself, i.e. to a row of an enclosing query. Such subqueries have to be
+/// evaluated once per outer row, while the rest can be evaluated once per
+/// query.
+pub fn is_correlated(query: &SqlSelect) -> bool {
+    let mut free = HashSet::new();
+    collect_free_in_select(query, &HashSet::new(), &mut free);
+    !free.is_empty()
+}
+
+fn collect_free_in_select(query: &SqlSelect, bound: &HashSet<String>, free: &mut HashSet<String>) {
+    let bound = collect_free_in_core(&query.core, bound, free);
+
+    for order in query.order_by.iter().flatten() {
+        collect_free_in_expr(&order.expr, &bound, free);
+    }
+
+    if let Some(limit) = &query.limit {
+        collect_free_in_expr(&limit.count, &bound, free);
+        if let Some(offset) = &limit.offset {
+            collect_free_in_expr(offset, &bound, free);
+        }
+    }
+}
+
+/// Returns the names visible to the clauses of the core, so that ORDER BY
+/// and LIMIT can be checked against the same scope.
+fn collect_free_in_core(
+    core: &SqlSelectCore,
+    outer: &HashSet<String>,
+    free: &mut HashSet<String>,
+) -> HashSet<String> {
+    let mut bound = outer.clone();
+
+    if let Some(from) = &core.from {
+        bind_from(from, &mut bound);
+    }
+
+    for projection in &core.projection {
+        if let SqlProjection::Expr {
+            alias: Some(alias), ..
+        } = projection
+        {
+            bound.insert(alias.name.clone());
+        }
+    }
+
+    if let Some(from) = &core.from {
+        collect_free_in_from(from, outer, &bound, free);
+    }
+
+    for projection in &core.projection {
+        if let SqlProjection::Expr { expr, .. } = projection {
+            collect_free_in_expr(expr, &bound, free);
+        }
+    }
+
+    for expr in core.r#where.iter().chain(core.having.iter()) {
+        collect_free_in_expr(expr, &bound, free);
+    }
+
+    for expr in core.group_by.iter().flatten() {
+        collect_free_in_expr(expr, &bound, free);
+    }
+
+    if let Some(compound) = &core.compound {
+        collect_free_in_core(&compound.core, outer, free);
+    }
+
+    bound
+}
+
+fn bind_from(from: &SqlFrom, bound: &mut HashSet<String>) {
+    match from {
+        SqlFrom::Source(source) => {
+            bound.insert(source.alias().name.clone());
+        }
+        SqlFrom::Select { alias, .. } => {
+            bound.insert(alias.name.clone());
+        }
+        SqlFrom::Group { values } => {
+            for value in values {
+                bind_from(value, bound);
+            }
+        }
+        SqlFrom::Join { left, right, .. } => {
+            bind_from(left, bound);
+            bind_from(right, bound);
+        }
+    }
+}
+
+fn collect_free_in_from(
+    from: &SqlFrom,
+    outer: &HashSet<String>,
+    bound: &HashSet<String>,
+    free: &mut HashSet<String>,
+) {
+    match from {
+        SqlFrom::Source(_) => {}
+        // Derived tables cannot see their siblings, only the enclosing
+        // queries.
+        SqlFrom::Select { subquery, .. } => collect_free_in_select(subquery, outer, free),
+        SqlFrom::Group { values } => {
+            for value in values {
+                collect_free_in_from(value, outer, bound, free);
+            }
+        }
+        SqlFrom::Join {
+            left,
+            right,
+            constraint,
+            ..
+        } => {
+            collect_free_in_from(left, outer, bound, free);
+            collect_free_in_from(right, outer, bound, free);
+            if let Some(constraint) = constraint {
+                collect_free_in_expr(constraint, bound, free);
+            }
+        }
+    }
+}
+
+fn collect_free_in_expr(expr: &Expr, bound: &HashSet<String>, free: &mut HashSet<String>) {
+    let mut collector = ReferenceCollector::default();
+    let mut visitor = ExprVisitor::<(), ()>::new(&mut collector);
+    // The collector never fails.
+    let _ = visitor.visit(expr);
+
+    for head in collector.heads {
+        if !bound.contains(&head) {
+            free.insert(head);
+        }
+    }
+
+    for query in collector.queries {
+        collect_free_in_select(&query, bound, free);
+    }
+}
+
+/// Collects the heads of the field paths an expression refers to, along
+/// with the queries nested in it, which are checked in their own scope.
+#[derive(Default)]
+struct ReferenceCollector {
+    heads: Vec<String>,
+    queries: Vec<SqlSelect>,
+}
+
+impl ExprReducer<(), ()> for ReferenceCollector {
+    fn visit(&mut self, expr: &Expr, visit: ExprVisitorNode) -> Result<bool, ()> {
+        if matches!(visit, ExprVisitorNode::In) {
+            match expr {
+                Expr::FieldPath { head, .. } => self.heads.push(head.name.clone()),
+                Expr::Select { query, .. } => {
+                    self.queries.push(query.clone());
+                    return Ok(false);
+                }
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn finalize(&mut self) -> Result<Vec<()>, ()> {
+        Ok(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lykiadb_lang::{SourceProcessor, ast::stmt::Stmt};
+
+    // Returns the first subquery in the WHERE clause of the given query.
+    fn subquery_of(source: &str) -> SqlSelect {
+        let root = SourceProcessor::new().process(source).unwrap().get_root();
+        let Stmt::Program { body, .. } = root.as_ref() else {
+            unreachable!()
+        };
+        let Stmt::Expression { expr, .. } = &body[0] else {
+            unreachable!()
+        };
+        let Expr::Select { query, .. } = expr.as_ref() else {
+            unreachable!()
+        };
+        let mut collector = ReferenceCollector::default();
+        let mut visitor = ExprVisitor::<(), ()>::new(&mut collector);
+        visitor.visit(query.core.r#where.as_ref().unwrap()).unwrap();
+        collector.queries.remove(0)
+    }
+
+    #[test]
+    fn test_uncorrelated_subquery() {
+        let query = subquery_of(
+            "SELECT * FROM books b WHERE b.id IN (SELECT c.book_id FROM comments c WHERE c.score > 3);",
+        );
+        assert!(!is_correlated(&query));
+    }
+
+    #[test]
+    fn test_correlated_subquery() {
+        let query = subquery_of(
+            "SELECT * FROM books b WHERE EXISTS (SELECT * FROM comments c WHERE c.book_id = b.id);",
+        );
+        assert!(is_correlated(&query));
+    }
+
+    #[test]
+    fn test_reference_from_nested_subquery() {
+        let query = subquery_of(
+            "SELECT * FROM books b WHERE EXISTS (SELECT * FROM comments c WHERE c.id IN (SELECT r.comment_id FROM replies r WHERE r.book_id = b.id));",
+        );
+        assert!(is_correlated(&query));
+    }
+}
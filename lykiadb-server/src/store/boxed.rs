@@ -0,0 +1,66 @@
+use crate::store::{IteratorItem, KeyBounds, ScanDirection, Store, error::StoreError};
+
+/// A store whose backend is picked at runtime, e.g. from the configuration
+/// the server starts with, or a wrapper an embedder brings along.
+pub struct BoxedStore(Box<dyn DynStore + Send + Sync>);
+
+impl BoxedStore {
+    pub fn new(store: impl for<'a> Store<'a> + Send + Sync + 'static) -> Self {
+        BoxedStore(Box::new(store))
+    }
+}
+
+// `Store` names the iterators of its backend, which a trait object cannot
+// do, so this is `Store` with the iterators boxed.
+trait DynStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError>;
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError>;
+    fn delete(&mut self, key: &[u8]) -> Result<(), StoreError>;
+    fn range<'a>(
+        &'a self,
+        bounds: KeyBounds,
+        direction: ScanDirection,
+    ) -> Box<dyn Iterator<Item = IteratorItem> + 'a>;
+}
+
+impl<S: for<'a> Store<'a>> DynStore for S {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        Store::get(self, key)
+    }
+
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError> {
+        Store::set(self, key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), StoreError> {
+        Store::delete(self, key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        bounds: KeyBounds,
+        direction: ScanDirection,
+    ) -> Box<dyn Iterator<Item = IteratorItem> + 'a> {
+        Box::new(Store::range(self, bounds, direction))
+    }
+}
+
+impl<'a> Store<'a> for BoxedStore {
+    type ScanIterator = Box<dyn Iterator<Item = IteratorItem> + 'a>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError> {
+        self.0.set(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), StoreError> {
+        self.0.delete(key)
+    }
+
+    fn range(&'a self, bounds: KeyBounds, direction: ScanDirection) -> Self::ScanIterator {
+        self.0.range(bounds, direction)
+    }
+}
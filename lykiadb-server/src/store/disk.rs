@@ -0,0 +1,480 @@
+use std::{
+    collections::{BTreeMap, btree_map::Range},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::store::{
+    KeyBounds, ScanDirection, Store, checked_bounds,
+    error::StoreError,
+    record::{Decoded, HEADER_LEN, Record},
+    sync_dir,
+};
+
+const DATA_FILE: &str = "data.log";
+const COMPACTION_FILE: &str = "data.log.compact";
+
+/// Position of a value in the data file.
+#[derive(Clone, Copy, Debug)]
+struct Location {
+    offset: u64,
+    len: u32,
+}
+
+type Index = BTreeMap<Vec<u8>, Location>;
+
+/// A file-backed store.
+///
+/// Every `set` and `delete` is appended to a data file in the data directory
+/// and synced before returning. An in-memory index maps each live key to the
+/// position of its latest value in that file, so reads cost a single seek.
+/// The index is rebuilt by replaying the data file on `open`, which also
+/// rewrites the file if most of it is taken by overwritten or deleted entries.
+/// A crash may leave a partially written record at the end of the file, which
+/// `open` cuts off.
+pub struct DiskStore {
+    dir: PathBuf,
+    file: Mutex<File>,
+    index: Index,
+    // Size of the data file, and how much of it belongs to live entries.
+    len: u64,
+    live: u64,
+}
+
+impl DiskStore {
+    /// Opens the store in `dir`, creating the directory if it is missing.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let file = open_data_file(&dir)?;
+        let (index, len, live) = replay(&file)?;
+
+        let mut store = DiskStore {
+            dir,
+            file: Mutex::new(file),
+            index,
+            len,
+            live,
+        };
+
+        if store.live * 2 < store.len {
+            store.compact()?;
+        }
+
+        Ok(store)
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Rewrites the data file so that it only contains the live entries.
+    pub fn compact(&mut self) -> Result<(), StoreError> {
+        let path = self.dir.join(COMPACTION_FILE);
+        let mut out = BufWriter::new(File::create(&path)?);
+        let mut index = BTreeMap::new();
+        let mut len = 0;
+
+        for (key, location) in &self.index {
+            let value = self.read(*location)?;
+            let record = Record::Set {
+                key: key.clone(),
+                value,
+            }
+            .encode();
+            out.write_all(&record)?;
+            index.insert(
+                key.clone(),
+                Location {
+                    offset: len + HEADER_LEN + key.len() as u64,
+                    len: location.len,
+                },
+            );
+            len += record.len() as u64;
+        }
+
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&path, self.dir.join(DATA_FILE))?;
+        sync_dir(&self.dir)?;
+
+        *self.file.get_mut().unwrap() = open_data_file(&self.dir)?;
+        self.index = index;
+        self.len = len;
+        self.live = len;
+
+        Ok(())
+    }
+
+    fn read(&self, location: Location) -> Result<Vec<u8>, StoreError> {
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(location.offset))?;
+        let mut value = vec![0; location.len as usize];
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    // Appends a record and returns the offset of its value.
+    fn append(&mut self, record: &Record) -> Result<u64, StoreError> {
+        let key_len = record.key().len() as u64;
+        let record = record.encode();
+        let file = self.file.get_mut().unwrap();
+
+        if let Err(err) = file.write_all(&record).and_then(|_| file.sync_data()) {
+            // Drop whatever part of the record made it to the file, so that
+            // the next append does not land after a partial record.
+            let _ = file.set_len(self.len);
+            return Err(err.into());
+        }
+
+        let offset = self.len + HEADER_LEN + key_len;
+        self.len += record.len() as u64;
+        Ok(offset)
+    }
+}
+
+fn open_data_file(dir: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(dir.join(DATA_FILE))
+}
+
+fn record_len(key: &[u8], location: &Location) -> u64 {
+    HEADER_LEN + key.len() as u64 + location.len as u64
+}
+
+// Reads the data file from the beginning and returns the index of the live
+// entries, along with the size of the file and the size of the live entries.
+// A crash can only tear the last record, so an incomplete record, or a last
+// one that does not match its checksum, is cut off, and new records are
+// appended right after the last intact one. A damaged record before the last
+// one is an error, as cutting it off would lose the intact ones after it.
+fn replay(file: &File) -> Result<(Index, u64, u64), StoreError> {
+    let len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut index = BTreeMap::new();
+    let mut live = 0;
+    let mut offset = 0;
+
+    loop {
+        let (record, size) = match Record::read(&mut reader, len - offset)? {
+            Decoded::Record(record, size) => (record, size),
+            Decoded::Incomplete => break,
+            Decoded::Corrupt(size) if offset + size == len => break,
+            Decoded::Corrupt(_) => {
+                return Err(StoreError::InvalidData {
+                    message: format!("record at offset {offset} does not match its checksum"),
+                });
+            }
+        };
+        let (key, previous) = match record {
+            Record::Set { key, value } => {
+                let location = Location {
+                    offset: offset + HEADER_LEN + key.len() as u64,
+                    len: value.len() as u32,
+                };
+                live += size;
+                let previous = index.insert(key.clone(), location);
+                (key, previous)
+            }
+            Record::Delete { key } => {
+                let previous = index.remove(&key);
+                (key, previous)
+            }
+        };
+
+        if let Some(previous) = previous {
+            live -= record_len(&key, &previous);
+        }
+
+        offset += size;
+    }
+
+    if offset < len {
+        file.set_len(offset)?;
+        file.sync_all()?;
+    }
+
+    Ok((index, offset, live))
+}
+
+pub struct DiskScanIterator<'a> {
+    store: &'a DiskStore,
+    range: Range<'a, Vec<u8>, Location>,
+    direction: ScanDirection,
+}
+
+impl Iterator for DiskScanIterator<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>), StoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.direction {
+            ScanDirection::Forward => self.range.next(),
+            ScanDirection::Reverse => self.range.next_back(),
+        };
+        entry.map(|(key, location)| self.store.read(*location).map(|value| (key.clone(), value)))
+    }
+}
+
+impl<'a> Store<'a> for DiskStore {
+    type ScanIterator = DiskScanIterator<'a>;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        self.index
+            .get(key)
+            .map(|location| self.read(*location))
+            .transpose()
+    }
+
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError> {
+        let len = value.len() as u32;
+        let offset = self.append(&Record::Set {
+            key: key.to_vec(),
+            value,
+        })?;
+        let location = Location { offset, len };
+
+        self.live += record_len(key, &location);
+        if let Some(previous) = self.index.insert(key.to_vec(), location) {
+            self.live -= record_len(key, &previous);
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), StoreError> {
+        if !self.index.contains_key(key) {
+            return Ok(());
+        }
+
+        self.append(&Record::Delete { key: key.to_vec() })?;
+        if let Some(previous) = self.index.remove(key) {
+            self.live -= record_len(key, &previous);
+        }
+
+        Ok(())
+    }
+
+    fn range(&'a self, bounds: KeyBounds, direction: ScanDirection) -> Self::ScanIterator {
+        DiskScanIterator {
+            store: self,
+            range: self.index.range(checked_bounds(bounds)),
+            direction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_file_len(dir: &Path) -> u64 {
+        fs::metadata(dir.join(DATA_FILE)).unwrap().len()
+    }
+
+    #[test]
+    fn test_entries_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut store = DiskStore::open(dir.path()).unwrap();
+            store.set(b"a", b"1".to_vec()).unwrap();
+            store.set(b"b", b"2".to_vec()).unwrap();
+            store.set(b"a", b"3".to_vec()).unwrap();
+            store.delete(b"b").unwrap();
+            store.set(b"c", b"4".to_vec()).unwrap();
+        }
+
+        let store = DiskStore::open(dir.path()).unwrap();
+        let entries: Vec<_> = store.scan().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (b"a".to_vec(), b"3".to_vec()),
+                (b"c".to_vec(), b"4".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_creates_missing_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("nested").join("data");
+
+        let mut store = DiskStore::open(&nested).unwrap();
+        store.set(b"key", b"value".to_vec()).unwrap();
+
+        assert_eq!(store.data_dir(), nested.as_path());
+        assert!(nested.join(DATA_FILE).exists());
+    }
+
+    #[test]
+    fn test_deleting_absent_key_does_not_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DiskStore::open(dir.path()).unwrap();
+
+        store.delete(b"ghost").unwrap();
+
+        assert_eq!(data_file_len(dir.path()), 0);
+    }
+
+    #[test]
+    fn test_compact_keeps_only_live_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DiskStore::open(dir.path()).unwrap();
+
+        for i in 0..10u8 {
+            store.set(b"counter", vec![i]).unwrap();
+        }
+        store.set(b"gone", b"x".to_vec()).unwrap();
+        store.delete(b"gone").unwrap();
+
+        let before = data_file_len(dir.path());
+        store.compact().unwrap();
+        let after = data_file_len(dir.path());
+
+        assert!(after < before);
+        assert_eq!(after, HEADER_LEN + b"counter".len() as u64 + 1);
+        assert_eq!(store.get(b"counter").unwrap(), Some(vec![9]));
+
+        // Writes after compaction go to the new file.
+        store.set(b"next", b"y".to_vec()).unwrap();
+        drop(store);
+
+        let store = DiskStore::open(dir.path()).unwrap();
+        assert_eq!(store.get(b"counter").unwrap(), Some(vec![9]));
+        assert_eq!(store.get(b"next").unwrap(), Some(b"y".to_vec()));
+        assert_eq!(store.get(b"gone").unwrap(), None);
+    }
+
+    #[test]
+    fn test_open_compacts_mostly_dead_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let mut store = DiskStore::open(dir.path()).unwrap();
+            for i in 0..10u8 {
+                store.set(b"counter", vec![i]).unwrap();
+            }
+        }
+
+        let store = DiskStore::open(dir.path()).unwrap();
+        assert_eq!(
+            data_file_len(dir.path()),
+            HEADER_LEN + b"counter".len() as u64 + 1
+        );
+        assert_eq!(store.get(b"counter").unwrap(), Some(vec![9]));
+    }
+
+    #[test]
+    fn test_open_rejects_unknown_record_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        // A record of kind 42 that matches its checksum.
+        let mut record = Record::Set {
+            key: b"k".to_vec(),
+            value: b"v".to_vec(),
+        }
+        .encode();
+        record[4] = 42;
+        let checksum = crc32fast::hash(&record[4..]);
+        record[..4].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(dir.path().join(DATA_FILE), record).unwrap();
+
+        assert!(matches!(
+            DiskStore::open(dir.path()),
+            Err(StoreError::InvalidData { .. })
+        ));
+    }
+
+    // Writes a store holding `a` and `b`, and returns the offset the record
+    // of `b` starts at.
+    fn write_two_entries(dir: &Path) -> u64 {
+        let mut store = DiskStore::open(dir).unwrap();
+        store.set(b"a", b"1".to_vec()).unwrap();
+        let start = data_file_len(dir);
+        store.set(b"b", b"second".to_vec()).unwrap();
+        start
+    }
+
+    fn truncate_data_file(dir: &Path, len: u64) {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(dir.join(DATA_FILE))
+            .unwrap();
+        file.set_len(len).unwrap();
+    }
+
+    // Checks that the store recovered `a` alone, cut the torn record off,
+    // and appends right after `a`.
+    fn assert_recovered(dir: &Path, start: u64) {
+        let mut store = DiskStore::open(dir).unwrap();
+        let entries: Vec<_> = store.scan().collect::<Result<_, _>>().unwrap();
+        assert_eq!(entries, vec![(b"a".to_vec(), b"1".to_vec())]);
+        assert_eq!(data_file_len(dir), start);
+
+        store.set(b"c", b"3".to_vec()).unwrap();
+        drop(store);
+
+        let store = DiskStore::open(dir).unwrap();
+        assert_eq!(store.get(b"a").unwrap(), Some(b"1".to_vec()));
+        assert_eq!(store.get(b"b").unwrap(), None);
+        assert_eq!(store.get(b"c").unwrap(), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn test_open_cuts_off_torn_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = write_two_entries(dir.path());
+
+        truncate_data_file(dir.path(), start + HEADER_LEN - 1);
+
+        assert_recovered(dir.path(), start);
+    }
+
+    #[test]
+    fn test_open_cuts_off_torn_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = write_two_entries(dir.path());
+
+        truncate_data_file(dir.path(), data_file_len(dir.path()) - 1);
+
+        assert_recovered(dir.path(), start);
+    }
+
+    #[test]
+    fn test_open_cuts_off_corrupted_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = write_two_entries(dir.path());
+
+        let path = dir.path().join(DATA_FILE);
+        let mut data = fs::read(&path).unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        fs::write(&path, data).unwrap();
+
+        assert_recovered(dir.path(), start);
+    }
+
+    #[test]
+    fn test_open_rejects_corrupted_record_before_the_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = write_two_entries(dir.path());
+
+        // Flip the last byte of the value of `a`.
+        let path = dir.path().join(DATA_FILE);
+        let mut data = fs::read(&path).unwrap();
+        data[start as usize - 1] ^= 1;
+        fs::write(&path, &data).unwrap();
+
+        assert!(matches!(
+            DiskStore::open(dir.path()),
+            Err(StoreError::InvalidData { .. })
+        ));
+        assert_eq!(fs::read(&path).unwrap(), data);
+    }
+}
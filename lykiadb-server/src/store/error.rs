@@ -6,6 +6,18 @@ use serde::{Deserialize, Serialize};
 pub enum StoreError {
     #[error("Unspecified error at {span:?}")]
     UnspecifiedError { span: Span },
+    #[error("I/O error in the store: {message}")]
+    Io { message: String },
+    #[error("Invalid data in the store: {message}")]
+    InvalidData { message: String },
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(value: std::io::Error) -> Self {
+        StoreError::Io {
+            message: value.to_string(),
+        }
+    }
 }
 
 impl From<StoreError> for InputError {
@@ -13,10 +25,18 @@ impl From<StoreError> for InputError {
         let (hint, sp) = match &value {
             StoreError::UnspecifiedError { span } => (
                 "An unspecified error occurred in the store".to_string(),
-                *span,
+                Some(*span),
+            ),
+            StoreError::Io { .. } => (
+                "Check that the data directory is accessible and the disk is not full".to_string(),
+                None,
+            ),
+            StoreError::InvalidData { .. } => (
+                "The data is damaged, restore it from a backup".to_string(),
+                None,
             ),
         };
 
-        InputError::new(&value.to_string(), &hint, Some(sp.into()))
+        InputError::new(&value.to_string(), &hint, sp.map(|sp| sp.into()))
     }
 }
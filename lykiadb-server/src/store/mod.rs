@@ -1,8 +1,69 @@
+pub mod boxed;
+pub mod disk;
 pub mod error;
 pub mod memory;
+pub mod record;
+pub mod wal;
+
+use std::{fs::File, io, ops::Bound, path::Path};
 
 pub type IteratorItem = Result<(Vec<u8>, Vec<u8>), error::StoreError>;
 
+/// Lower and upper bounds of a range of keys.
+pub type KeyBounds = (Bound<Vec<u8>>, Bound<Vec<u8>>);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanDirection {
+    Forward,
+    Reverse,
+}
+
+/// The smallest key that is greater than every key starting with `prefix`,
+/// if there is one.
+pub fn successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut key = prefix.to_vec();
+    while let Some(last) = key.pop() {
+        if last < u8::MAX {
+            key.push(last + 1);
+            return Some(key);
+        }
+    }
+    None
+}
+
+/// The bounds of the keys that start with `prefix`.
+pub fn prefix_bounds(prefix: &[u8]) -> KeyBounds {
+    (
+        Bound::Included(prefix.to_vec()),
+        successor(prefix).map_or(Bound::Unbounded, Bound::Excluded),
+    )
+}
+
+/// Syncs the entries of a directory, so that a file renamed into it is still
+/// there after a crash.
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+// Ranges of a `BTreeMap` panic when the bounds are out of order, so those
+// are turned into an empty range.
+fn checked_bounds(bounds: KeyBounds) -> KeyBounds {
+    let out_of_order = match &bounds {
+        (Bound::Excluded(lower), Bound::Excluded(upper)) => lower >= upper,
+        (
+            Bound::Included(lower) | Bound::Excluded(lower),
+            Bound::Included(upper) | Bound::Excluded(upper),
+        ) => lower > upper,
+        _ => false,
+    };
+    match bounds {
+        (Bound::Included(lower) | Bound::Excluded(lower), _) if out_of_order => {
+            (Bound::Included(lower.clone()), Bound::Excluded(lower))
+        }
+        bounds => bounds,
+    }
+}
+
 pub trait StoreScanIterator<'a>: Iterator<Item = IteratorItem> + 'a {}
 
 impl<'a, I: Iterator<Item = IteratorItem> + 'a> StoreScanIterator<'a> for I {}
@@ -10,8 +71,202 @@ impl<'a, I: Iterator<Item = IteratorItem> + 'a> StoreScanIterator<'a> for I {}
 pub trait Store<'a> {
     type ScanIterator: StoreScanIterator<'a>;
 
-    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
-    fn set(&mut self, key: &[u8], value: Vec<u8>);
-    fn delete(&mut self, key: &[u8]);
-    fn scan(&'a self) -> Self::ScanIterator;
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, error::StoreError>;
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), error::StoreError>;
+    fn delete(&mut self, key: &[u8]) -> Result<(), error::StoreError>;
+    /// Iterates over the entries whose keys fall in `bounds`, in key order
+    /// or in reverse.
+    fn range(&'a self, bounds: KeyBounds, direction: ScanDirection) -> Self::ScanIterator;
+
+    fn scan(&'a self) -> Self::ScanIterator {
+        self.range((Bound::Unbounded, Bound::Unbounded), ScanDirection::Forward)
+    }
+
+    fn scan_prefix(&'a self, prefix: &[u8], direction: ScanDirection) -> Self::ScanIterator {
+        self.range(prefix_bounds(prefix), direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Every backend has to pass the same suite. `make_store` returns the
+    // store along with whatever has to outlive it, such as a data directory.
+    macro_rules! store_tests {
+        ($backend:ident, $make_store:expr) => {
+            mod $backend {
+                use crate::store::Store;
+
+                fn make_store() -> (impl for<'a> Store<'a>, impl Sized) {
+                    $make_store
+                }
+
+                #[test]
+                fn test_set_and_get() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"key", b"value".to_vec()).unwrap();
+                    assert_eq!(store.get(b"key").unwrap(), Some(b"value".to_vec()));
+                }
+
+                #[test]
+                fn test_overwrite_existing_key() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"key", b"first".to_vec()).unwrap();
+                    store.set(b"key", b"second".to_vec()).unwrap();
+                    assert_eq!(store.get(b"key").unwrap(), Some(b"second".to_vec()));
+                }
+
+                #[test]
+                fn test_delete_existing_key() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"key", b"value".to_vec()).unwrap();
+                    store.delete(b"key").unwrap();
+                    assert_eq!(store.get(b"key").unwrap(), None);
+                }
+
+                #[test]
+                fn test_delete_is_idempotent() {
+                    let (mut store, _dir) = make_store();
+                    // deleting a key that was never inserted should not panic
+                    store.delete(b"ghost").unwrap();
+                    assert_eq!(store.get(b"ghost").unwrap(), None);
+                }
+
+                #[test]
+                fn test_get_absent_key_returns_none() {
+                    let (store, _dir) = make_store();
+                    assert_eq!(store.get(b"missing").unwrap(), None);
+                }
+
+                #[test]
+                fn test_get_after_delete_returns_none() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"key", b"value".to_vec()).unwrap();
+                    store.delete(b"key").unwrap();
+                    assert_eq!(store.get(b"key").unwrap(), None);
+                }
+
+                #[test]
+                fn test_scan_empty_store() {
+                    let (store, _dir) = make_store();
+                    let entries: Vec<_> = store.scan().collect();
+                    assert!(entries.is_empty());
+                }
+
+                #[test]
+                fn test_scan_returns_all_entries_sorted() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"c", b"3".to_vec()).unwrap();
+                    store.set(b"a", b"1".to_vec()).unwrap();
+                    store.set(b"b", b"2".to_vec()).unwrap();
+
+                    let entries: Vec<_> = store.scan().collect::<Result<_, _>>().unwrap();
+                    assert_eq!(
+                        entries,
+                        vec![
+                            (b"a".to_vec(), b"1".to_vec()),
+                            (b"b".to_vec(), b"2".to_vec()),
+                            (b"c".to_vec(), b"3".to_vec()),
+                        ]
+                    );
+                }
+
+                fn keys(entries: impl Iterator<Item = crate::store::IteratorItem>) -> Vec<Vec<u8>> {
+                    entries.map(|entry| entry.unwrap().0).collect()
+                }
+
+                #[test]
+                fn test_range_in_both_directions() {
+                    use crate::store::ScanDirection::{Forward, Reverse};
+                    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+                    let (mut store, _dir) = make_store();
+                    for key in [b"a", b"b", b"c", b"d"] {
+                        store.set(key, vec![]).unwrap();
+                    }
+
+                    let bounds = (Included(b"b".to_vec()), Excluded(b"d".to_vec()));
+                    assert_eq!(keys(store.range(bounds.clone(), Forward)), vec![b"b", b"c"]);
+                    assert_eq!(keys(store.range(bounds, Reverse)), vec![b"c", b"b"]);
+
+                    let bounds = (Excluded(b"b".to_vec()), Unbounded);
+                    assert_eq!(keys(store.range(bounds, Reverse)), vec![b"d", b"c"]);
+
+                    // Bounds out of order make an empty range.
+                    let bounds = (Included(b"c".to_vec()), Included(b"b".to_vec()));
+                    assert!(keys(store.range(bounds, Forward)).is_empty());
+                    let bounds = (Excluded(b"b".to_vec()), Excluded(b"b".to_vec()));
+                    assert!(keys(store.range(bounds, Forward)).is_empty());
+                }
+
+                #[test]
+                fn test_scan_prefix() {
+                    use crate::store::ScanDirection::{Forward, Reverse};
+
+                    let (mut store, _dir) = make_store();
+                    for key in [
+                        &b"a"[..],
+                        b"ab",
+                        b"ab\xff",
+                        b"ab\xff\xff",
+                        b"ac",
+                        b"\xff",
+                        b"\xff\x01",
+                    ] {
+                        store.set(key, vec![]).unwrap();
+                    }
+
+                    assert_eq!(
+                        keys(store.scan_prefix(b"ab", Forward)),
+                        vec![&b"ab"[..], b"ab\xff", b"ab\xff\xff"]
+                    );
+                    assert_eq!(
+                        keys(store.scan_prefix(b"ab\xff", Reverse)),
+                        vec![&b"ab\xff\xff"[..], b"ab\xff"]
+                    );
+                    assert_eq!(
+                        keys(store.scan_prefix(b"\xff", Forward)),
+                        vec![&b"\xff"[..], b"\xff\x01"]
+                    );
+                    assert_eq!(keys(store.scan_prefix(b"", Forward)).len(), 7);
+                }
+
+                #[test]
+                fn test_scan_excludes_deleted_entries() {
+                    let (mut store, _dir) = make_store();
+                    store.set(b"keep", b"yes".to_vec()).unwrap();
+                    store.set(b"drop", b"no".to_vec()).unwrap();
+                    store.delete(b"drop").unwrap();
+
+                    let entries: Vec<_> = store.scan().collect::<Result<_, _>>().unwrap();
+                    assert_eq!(entries, vec![(b"keep".to_vec(), b"yes".to_vec())]);
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn test_successor() {
+        assert_eq!(super::successor(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(super::successor(b"a\xff\xff"), Some(b"b".to_vec()));
+        assert_eq!(super::successor(b"\xff"), None);
+        assert_eq!(super::successor(b""), None);
+    }
+
+    store_tests!(memory, (crate::store::memory::MemoryStore::new(), ()));
+
+    store_tests!(disk, {
+        let dir = tempfile::tempdir().unwrap();
+        (
+            crate::store::disk::DiskStore::open(dir.path()).unwrap(),
+            dir,
+        )
+    });
+
+    store_tests!(
+        boxed,
+        (
+            crate::store::boxed::BoxedStore::new(crate::store::memory::MemoryStore::new()),
+            (),
+        )
+    );
 }
@@ -0,0 +1,145 @@
+use std::io::Read;
+
+use crate::store::error::StoreError;
+
+const RECORD_SET: u8 = 1;
+const RECORD_DELETE: u8 = 2;
+
+// Checksum, record kind, key length and value length. The checksum covers
+// everything in the record that comes after it.
+pub(super) const HEADER_LEN: u64 = 4 + 1 + 4 + 4;
+
+/// A change to the entries of a store, as the data files and the logs keep
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Record {
+    Set { key: Vec<u8>, value: Vec<u8> },
+    Delete { key: Vec<u8> },
+}
+
+/// What is found where a record should start.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Decoded {
+    /// An intact record, along with its length.
+    Record(Record, u64),
+    /// The bytes end before the record does, as they do after a torn write.
+    Incomplete,
+    /// A record of the given length that does not match its checksum.
+    Corrupt(u64),
+}
+
+impl Record {
+    pub fn key(&self) -> &[u8] {
+        match self {
+            Record::Set { key, .. } | Record::Delete { key } => key,
+        }
+    }
+
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let (kind, key, value) = match self {
+            Record::Set { key, value } => (RECORD_SET, key, value.as_slice()),
+            Record::Delete { key } => (RECORD_DELETE, key, &[][..]),
+        };
+
+        let mut record = Vec::with_capacity(HEADER_LEN as usize + key.len() + value.len());
+        record.extend_from_slice(&[0; 4]);
+        record.push(kind);
+        record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        record.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        record.extend_from_slice(key);
+        record.extend_from_slice(value);
+
+        let checksum = crc32fast::hash(&record[4..]);
+        record[..4].copy_from_slice(&checksum.to_le_bytes());
+        record
+    }
+
+    /// Reads the record `reader` is positioned at, given the number of bytes
+    /// left before the end of the file. A record of an unknown kind that
+    /// matches its checksum was not torn, so it is an error instead.
+    pub(super) fn read(reader: &mut impl Read, remaining: u64) -> Result<Decoded, StoreError> {
+        if remaining < HEADER_LEN {
+            return Ok(Decoded::Incomplete);
+        }
+        let mut header = [0; HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        let checksum = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let kind = header[4];
+        let key_len = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+        let value_len = u32::from_le_bytes(header[9..13].try_into().unwrap()) as usize;
+
+        let len = HEADER_LEN + key_len as u64 + value_len as u64;
+        if remaining < len {
+            return Ok(Decoded::Incomplete);
+        }
+        let mut body = vec![0; key_len + value_len];
+        reader.read_exact(&mut body)?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header[4..]);
+        hasher.update(&body);
+        if hasher.finalize() != checksum {
+            return Ok(Decoded::Corrupt(len));
+        }
+
+        let value = body.split_off(key_len);
+        let key = body;
+        let record = match kind {
+            RECORD_SET => Record::Set { key, value },
+            RECORD_DELETE => Record::Delete { key },
+            _ => {
+                return Err(StoreError::InvalidData {
+                    message: format!("unknown record kind {kind}"),
+                });
+            }
+        };
+        Ok(Decoded::Record(record, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(bytes: &[u8]) -> Result<Decoded, StoreError> {
+        Record::read(&mut &bytes[..], bytes.len() as u64)
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        for record in [
+            Record::Set {
+                key: b"key".to_vec(),
+                value: b"value".to_vec(),
+            },
+            Record::Set {
+                key: vec![],
+                value: vec![],
+            },
+            Record::Delete {
+                key: b"key".to_vec(),
+            },
+        ] {
+            let encoded = record.encode();
+            let len = encoded.len() as u64;
+            assert_eq!(read(&encoded), Ok(Decoded::Record(record, len)));
+        }
+    }
+
+    #[test]
+    fn test_tells_torn_records_from_corrupted_ones() {
+        let encoded = Record::Set {
+            key: b"key".to_vec(),
+            value: b"value".to_vec(),
+        }
+        .encode();
+
+        for cut in 0..encoded.len() {
+            assert_eq!(read(&encoded[..cut]), Ok(Decoded::Incomplete));
+        }
+
+        let mut corrupted = encoded.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(read(&corrupted), Ok(Decoded::Corrupt(encoded.len() as u64)));
+    }
+}
@@ -0,0 +1,508 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::store::{
+    KeyBounds, ScanDirection, Store,
+    error::StoreError,
+    record::{Decoded, Record},
+    sync_dir,
+};
+
+/// When the log is flushed to the disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Sync after every record, so that an acknowledged write is never lost.
+    #[default]
+    Always,
+    /// Sync once every given number of records, and when the log is dropped.
+    /// A crash can lose the records written since the last sync.
+    Batched(usize),
+    /// Leave flushing to the operating system.
+    None,
+}
+
+impl FromStr for SyncPolicy {
+    type Err = String;
+
+    /// Parses `always`, `none`, or the number of records of a batch.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(SyncPolicy::Always),
+            "none" => Ok(SyncPolicy::None),
+            _ => match s.parse() {
+                Ok(size) if size > 0 => Ok(SyncPolicy::Batched(size)),
+                _ => Err(format!(
+                    "Unknown sync policy {s:?}, expected always, none or a batch size"
+                )),
+            },
+        }
+    }
+}
+
+/// An append-only write-ahead log.
+pub struct Wal {
+    path: PathBuf,
+    file: File,
+    policy: SyncPolicy,
+    len: u64,
+    // Records written since the last sync.
+    pending: usize,
+}
+
+impl Wal {
+    /// Opens the log at `path` and returns it along with the records it
+    /// holds. A crash may leave a partially written record at the end of the
+    /// log. Recovery keeps the longest prefix of intact records and cuts off
+    /// the rest, so that new records are appended right after it.
+    pub fn open(
+        path: impl AsRef<Path>,
+        policy: SyncPolicy,
+    ) -> Result<(Self, Vec<Record>), StoreError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = open_log(&path)?;
+
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+
+        let mut records = vec![];
+        let mut len = 0;
+        while let Decoded::Record(record, record_len) =
+            Record::read(&mut &bytes[len as usize..], bytes.len() as u64 - len)?
+        {
+            records.push(record);
+            len += record_len;
+        }
+
+        if len < bytes.len() as u64 {
+            file.set_len(len)?;
+            file.sync_all()?;
+        }
+
+        let wal = Wal {
+            path,
+            file,
+            policy,
+            len,
+            pending: 0,
+        };
+
+        Ok((wal, records))
+    }
+
+    pub fn append(&mut self, record: &Record) -> Result<(), StoreError> {
+        let encoded = record.encode();
+
+        self.pending += 1;
+        let written = self
+            .file
+            .write_all(&encoded)
+            .map_err(StoreError::from)
+            .and_then(|_| match self.policy {
+                SyncPolicy::Always => self.sync(),
+                SyncPolicy::Batched(size) if self.pending >= size => self.sync(),
+                SyncPolicy::Batched(_) | SyncPolicy::None => Ok(()),
+            });
+
+        if let Err(err) = written {
+            // The record is reported as not written, so drop whatever part
+            // of it made it to the file. Otherwise replay would apply it, or
+            // it would hide the records appended after it.
+            let _ = self.file.set_len(self.len);
+            self.pending -= 1;
+            return Err(err);
+        }
+
+        self.len += encoded.len() as u64;
+        Ok(())
+    }
+
+    /// Flushes the records written so far to the disk.
+    pub fn sync(&mut self) -> Result<(), StoreError> {
+        if self.pending > 0 {
+            self.file.sync_data()?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Replaces the records of the log with `records`. They are written to a
+    /// new file that takes the place of the log once it is synced, so a
+    /// crash leaves either the old log or the new one.
+    pub fn rewrite(&mut self, records: &[Record]) -> Result<(), StoreError> {
+        let path = self.path.with_extension("checkpoint");
+        let mut out = BufWriter::new(File::create(&path)?);
+        let mut len = 0;
+        for record in records {
+            let encoded = record.encode();
+            out.write_all(&encoded)?;
+            len += encoded.len() as u64;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&path, &self.path)?;
+        // A log given by its bare file name is in the working directory.
+        match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => sync_dir(dir)?,
+            _ => sync_dir(Path::new("."))?,
+        }
+
+        self.file = open_log(&self.path)?;
+        self.len = len;
+        self.pending = 0;
+        Ok(())
+    }
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)
+}
+
+impl Drop for Wal {
+    fn drop(&mut self) {
+        if self.policy != SyncPolicy::None {
+            let _ = self.sync();
+        }
+    }
+}
+
+/// Makes any store durable by logging its writes ahead of applying them. The
+/// log is replayed into the wrapped store when it is opened, so a volatile
+/// store like [`MemoryStore`](crate::store::memory::MemoryStore) survives
+/// restarts.
+pub struct WalStore<S> {
+    inner: S,
+    wal: Wal,
+    // Records logged since the last checkpoint, and how many of them trigger
+    // the next one. Records replayed on open count as logged since one.
+    since_checkpoint: usize,
+    checkpoint_every: Option<usize>,
+}
+
+impl<S: for<'a> Store<'a>> WalStore<S> {
+    pub fn open(
+        mut inner: S,
+        path: impl AsRef<Path>,
+        policy: SyncPolicy,
+    ) -> Result<Self, StoreError> {
+        let (wal, records) = Wal::open(path, policy)?;
+        let since_checkpoint = records.len();
+
+        for record in records {
+            match record {
+                Record::Set { key, value } => inner.set(&key, value)?,
+                Record::Delete { key } => inner.delete(&key)?,
+            }
+        }
+
+        Ok(WalStore {
+            inner,
+            wal,
+            since_checkpoint,
+            checkpoint_every: None,
+        })
+    }
+
+    /// Checkpoints the log once the given number of records were logged
+    /// since the last checkpoint.
+    pub fn checkpoint_every(mut self, records: usize) -> Self {
+        self.checkpoint_every = Some(records);
+        self
+    }
+
+    /// Flushes the pending log records to the disk.
+    pub fn sync(&mut self) -> Result<(), StoreError> {
+        self.wal.sync()
+    }
+
+    /// Rewrites the log so that it holds a record for each entry of the
+    /// store, instead of every write the store took since it was created.
+    pub fn checkpoint(&mut self) -> Result<(), StoreError> {
+        let records = self
+            .inner
+            .scan()
+            .map(|entry| entry.map(|(key, value)| Record::Set { key, value }))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.wal.rewrite(&records)?;
+        self.since_checkpoint = 0;
+        Ok(())
+    }
+
+    fn log(&mut self, record: &Record) -> Result<(), StoreError> {
+        if self
+            .checkpoint_every
+            .is_some_and(|records| self.since_checkpoint >= records)
+        {
+            self.checkpoint()?;
+        }
+        self.wal.append(record)?;
+        self.since_checkpoint += 1;
+        Ok(())
+    }
+}
+
+impl<'a, S: for<'b> Store<'b>> Store<'a> for WalStore<S> {
+    type ScanIterator = <S as Store<'a>>::ScanIterator;
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        self.inner.get(key)
+    }
+
+    fn set(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), StoreError> {
+        self.log(&Record::Set {
+            key: key.to_vec(),
+            value: value.clone(),
+        })?;
+        self.inner.set(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), StoreError> {
+        self.log(&Record::Delete { key: key.to_vec() })?;
+        self.inner.delete(key)
+    }
+
+    fn range(&'a self, bounds: KeyBounds, direction: ScanDirection) -> Self::ScanIterator {
+        self.inner.range(bounds, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, fs};
+
+    use super::*;
+    use crate::store::memory::MemoryStore;
+
+    fn set(key: &str, value: &str) -> Record {
+        Record::Set {
+            key: key.as_bytes().to_vec(),
+            value: value.as_bytes().to_vec(),
+        }
+    }
+
+    fn delete(key: &str) -> Record {
+        Record::Delete {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    fn workload() -> Vec<Record> {
+        vec![
+            set("a", "1"),
+            set("b", "2"),
+            set("a", "3"),
+            delete("b"),
+            set("c", ""),
+            set("", "empty key"),
+            delete("missing"),
+            set("b", "4"),
+        ]
+    }
+
+    // The state a store ends up in after applying the given records.
+    fn expected_state(records: &[Record]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut state = BTreeMap::new();
+        for record in records {
+            match record {
+                Record::Set { key, value } => {
+                    state.insert(key.clone(), value.clone());
+                }
+                Record::Delete { key } => {
+                    state.remove(key);
+                }
+            }
+        }
+        state.into_iter().collect()
+    }
+
+    fn state_of(store: &WalStore<MemoryStore>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        store.scan().collect::<Result<_, _>>().unwrap()
+    }
+
+    // Writes the workload through a store and returns the resulting log
+    // along with the offset each record ends at.
+    fn write_log(dir: &Path, records: &[Record]) -> (Vec<u8>, Vec<usize>) {
+        let path = dir.join("source.wal");
+        let mut store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+        for record in records {
+            match record {
+                Record::Set { key, value } => store.set(key, value.clone()).unwrap(),
+                Record::Delete { key } => store.delete(key).unwrap(),
+            }
+        }
+        drop(store);
+
+        let ends = records
+            .iter()
+            .scan(0, |end, record| {
+                *end += record.encode().len();
+                Some(*end)
+            })
+            .collect();
+
+        (fs::read(&path).unwrap(), ends)
+    }
+
+    #[test]
+    fn test_replay_restores_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = workload();
+        let (log, _) = write_log(dir.path(), &records);
+
+        let path = dir.path().join("copy.wal");
+        fs::write(&path, &log).unwrap();
+        let store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+
+        assert_eq!(state_of(&store), expected_state(&records));
+    }
+
+    // Simulates a crash at every byte of the log. The store has to recover
+    // the records that were written completely, and keep working afterwards.
+    #[test]
+    fn test_recovers_prefix_from_truncated_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = workload();
+        let (log, ends) = write_log(dir.path(), &records);
+
+        for cut in 0..=log.len() {
+            let path = dir.path().join(format!("cut_{cut}.wal"));
+            fs::write(&path, &log[..cut]).unwrap();
+
+            let complete = ends.iter().filter(|end| **end <= cut).count();
+            let boundary = if complete == 0 { 0 } else { ends[complete - 1] };
+
+            let mut store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+            assert_eq!(
+                state_of(&store),
+                expected_state(&records[..complete]),
+                "cut at {cut}"
+            );
+            assert_eq!(
+                fs::metadata(&path).unwrap().len(),
+                boundary as u64,
+                "torn tail at {cut} was not cut off"
+            );
+
+            store.set(b"after", b"crash".to_vec()).unwrap();
+            drop(store);
+
+            let mut expected = records[..complete].to_vec();
+            expected.push(set("after", "crash"));
+            let store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+            assert_eq!(state_of(&store), expected_state(&expected), "cut at {cut}");
+        }
+    }
+
+    #[test]
+    fn test_corrupted_record_ends_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = workload();
+        let (mut log, ends) = write_log(dir.path(), &records);
+
+        // Flip a bit in the value of the third record.
+        log[ends[2] - 1] ^= 1;
+
+        let path = dir.path().join("corrupted.wal");
+        fs::write(&path, &log).unwrap();
+        let store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+
+        assert_eq!(state_of(&store), expected_state(&records[..2]));
+        assert_eq!(fs::metadata(&path).unwrap().len(), ends[1] as u64);
+    }
+
+    #[test]
+    fn test_batched_policy_syncs_every_n_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut wal, _) =
+            Wal::open(dir.path().join("batched.wal"), SyncPolicy::Batched(3)).unwrap();
+
+        wal.append(&set("a", "1")).unwrap();
+        wal.append(&set("b", "2")).unwrap();
+        assert_eq!(wal.pending, 2);
+
+        wal.append(&set("c", "3")).unwrap();
+        assert_eq!(wal.pending, 0);
+
+        wal.append(&set("d", "4")).unwrap();
+        wal.sync().unwrap();
+        assert_eq!(wal.pending, 0);
+    }
+
+    #[test]
+    fn test_unsynced_records_are_still_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("none.wal");
+
+        {
+            let (mut wal, _) = Wal::open(&path, SyncPolicy::None).unwrap();
+            wal.append(&set("a", "1")).unwrap();
+            wal.append(&delete("a")).unwrap();
+            assert_eq!(wal.pending, 2);
+        }
+
+        let (_, records) = Wal::open(&path, SyncPolicy::None).unwrap();
+        assert_eq!(records, vec![set("a", "1"), delete("a")]);
+    }
+
+    #[test]
+    fn test_parses_sync_policies() {
+        assert_eq!("always".parse(), Ok(SyncPolicy::Always));
+        assert_eq!("none".parse(), Ok(SyncPolicy::None));
+        assert_eq!("8".parse(), Ok(SyncPolicy::Batched(8)));
+        assert!("0".parse::<SyncPolicy>().is_err());
+        assert!("sometimes".parse::<SyncPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_shrinks_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.wal");
+        let records = workload();
+        let mut store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+        for record in &records {
+            match record {
+                Record::Set { key, value } => store.set(key, value.clone()).unwrap(),
+                Record::Delete { key } => store.delete(key).unwrap(),
+            }
+        }
+        let before = fs::metadata(&path).unwrap().len();
+
+        store.checkpoint().unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < before);
+        assert!(!path.with_extension("checkpoint").exists());
+
+        store.set(b"d", b"5".to_vec()).unwrap();
+        drop(store);
+
+        let mut expected = records.clone();
+        expected.push(set("d", "5"));
+        let store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::Always).unwrap();
+        assert_eq!(state_of(&store), expected_state(&expected));
+    }
+
+    #[test]
+    fn test_checkpoints_keep_log_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bounded.wal");
+        let mut store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::None)
+            .unwrap()
+            .checkpoint_every(10);
+        for i in 0..100 {
+            store.set(b"counter", i.to_string().into_bytes()).unwrap();
+        }
+        drop(store);
+
+        let (_, records) = Wal::open(&path, SyncPolicy::None).unwrap();
+        // The record of the last checkpoint, and the writes logged since.
+        assert!(records.len() <= 1 + 10, "{} records", records.len());
+
+        let store = WalStore::open(MemoryStore::new(), &path, SyncPolicy::None).unwrap();
+        assert_eq!(state_of(&store), expected_state(&[set("counter", "99")]));
+    }
+}
@@ -36,6 +36,10 @@ impl<'v> RVCallable<'v> {
         matches!(&*self.function, Function::Agg { .. })
     }
 
+    pub fn is_pure(&self) -> bool {
+        matches!(&*self.function, Function::Native { pure: true, .. })
+    }
+
     pub fn call(
         &self,
         state: &ProgramState<'v>,
@@ -44,7 +48,7 @@ impl<'v> RVCallable<'v> {
     ) -> Result<RV<'v>, HaltReason<'v>> {
         let mut interpreter = Interpreter::from_state(state);
         match &self.function.as_ref() {
-            Function::Native { function } => function(&mut interpreter, called_from, arguments),
+            Function::Native { function, .. } => function(&mut interpreter, called_from, arguments),
             Function::Agg { function, .. } => {
                 let mut aggregator = function();
 
@@ -80,6 +84,7 @@ pub enum Function<'v> {
             called_from: &Span,
             &[RV<'v>],
         ) -> Result<RV<'v>, HaltReason<'v>>,
+        pure: bool,
     },
     UserDefined {
         name: Symbol,
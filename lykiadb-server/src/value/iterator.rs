@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, hash::Hash};
 
 use dyn_clone::DynClone;
 use interb::Symbol;
@@ -20,6 +20,9 @@ impl<'v, 'q, I: Iterator<Item = ExecutionRow<'v>> + DynClone + 'q> RVIterator<'v
 pub struct ExecutionRow<'v> {
     pub keys: SmallVec<[Symbol; 4]>,
     pub values: SmallVec<[RV<'v>; 4]>,
+    // Key of the stored document the row was scanned from, if any. Rows
+    // derived from other rows (projections, joins, etc.) don't carry it.
+    pub doc_key: Option<String>,
 }
 
 impl<'v> Default for ExecutionRow<'v> {
@@ -33,6 +36,7 @@ impl<'v> ExecutionRow<'v> {
         ExecutionRow {
             keys: SmallVec::new(),
             values: SmallVec::new(),
+            doc_key: None,
         }
     }
 
@@ -57,6 +61,10 @@ impl<'v> ExecutionRow<'v> {
         RV::Object(map)
     }
 
+    pub fn to_row_key(&self) -> RowKey<'v> {
+        RowKey(self.values.clone())
+    }
+
     pub fn copy_to(&self, target: &mut ExecutionRow<'v>) {
         for (k, v) in self.keys.iter().zip(self.values.iter()) {
             target.insert(*k, v.clone());
@@ -64,6 +72,37 @@ impl<'v> ExecutionRow<'v> {
     }
 }
 
+// Values of a row, compared and hashed structurally. Unlike RV's own
+// equality, two objects (or arrays) with the same contents are equal,
+// which is what set operations on rows require.
+#[derive(Debug, Clone)]
+pub struct RowKey<'v>(SmallVec<[RV<'v>; 4]>);
+
+impl<'v> PartialEq for RowKey<'v> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|(a, b)| a.total_cmp(b).is_eq())
+    }
+}
+
+impl<'v> Eq for RowKey<'v> {}
+
+impl<'v> From<RV<'v>> for RowKey<'v> {
+    fn from(value: RV<'v>) -> Self {
+        RowKey(smallvec::smallvec![value])
+    }
+}
+
+impl<'v> Hash for RowKey<'v> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
 impl<'v> Display for ExecutionRow<'v> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut first = true;
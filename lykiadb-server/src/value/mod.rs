@@ -54,9 +54,15 @@ impl<'v> std::hash::Hash for RV<'v> {
             RV::Decimal128(d) => d.to_string().hash(state),
             RV::Bool(b) => b.hash(state),
             RV::Object(obj) => {
-                // Hash the object by its pointer address
-                // This is necessary because RVObject doesn't implement Hash
-                (obj as *const _ as usize).hash(state);
+                // Hash object length and each entry. Entries are sorted by
+                // key, as the insertion order doesn't matter for equality.
+                let mut entries = obj.iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                entries.len().hash(state);
+                for (key, value) in entries {
+                    key.hash(state);
+                    value.hash(state);
+                }
             }
             RV::Array(arr) => {
                 // Hash array length and each element
@@ -296,6 +302,96 @@ impl<'v> PartialOrd for RV<'v> {
     }
 }
 
+impl<'v> RV<'v> {
+    // Rank of each type in the total ordering. Numbers of different
+    // representations share a rank, so that they compare by value.
+    fn type_rank(&self) -> u8 {
+        match self {
+            RV::Undefined => 0,
+            RV::Null => 1,
+            RV::Bool(_) => 2,
+            RV::Int32(_) | RV::Int64(_) | RV::Double(_) | RV::Decimal128(_) => 3,
+            RV::Str(_) => 4,
+            RV::DateTime(_) => 5,
+            RV::Object(_) => 6,
+            RV::Array(_) => 7,
+            RV::Datatype(_) => 8,
+            RV::Callable(_) => 9,
+        }
+    }
+
+    fn number_rank(&self) -> u8 {
+        match self {
+            RV::Int32(_) => 0,
+            RV::Int64(_) => 1,
+            RV::Double(_) => 2,
+            _ => 3,
+        }
+    }
+
+    /// Compares two values with a total ordering, unlike `partial_cmp`
+    /// which follows the comparison semantics of the language. Values of
+    /// different types are ordered by type: undefined, null, booleans,
+    /// numbers, strings, datetimes, objects, arrays, datatypes and callables.
+    pub fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+
+        match (self, other) {
+            (RV::Bool(a), RV::Bool(b)) => a.cmp(b),
+            (RV::Int32(a), RV::Int32(b)) => a.cmp(b),
+            (RV::Int64(a), RV::Int64(b)) => a.cmp(b),
+            (RV::Int32(a), RV::Int64(b)) => (*a as i64).cmp(b).then(Ordering::Less),
+            (RV::Int64(a), RV::Int32(b)) => a.cmp(&(*b as i64)).then(Ordering::Greater),
+            (RV::Str(a), RV::Str(b)) => a.cmp(b),
+            (RV::DateTime(a), RV::DateTime(b)) => a.cmp(b),
+            (RV::Object(a), RV::Object(b)) => {
+                // Objects are compared entry by entry, in key order
+                let sorted = |obj: &RVObject<'v>| {
+                    let mut entries = obj.iter().collect::<Vec<_>>();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    entries
+                };
+                let (a, b) = (sorted(a), sorted(b));
+                for ((ka, va), (kb, vb)) in a.iter().zip(b.iter()) {
+                    let ord = ka.cmp(kb).then_with(|| va.total_cmp(vb));
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (RV::Array(a), RV::Array(b)) => {
+                for (va, vb) in a.iter().zip(b.iter()) {
+                    let ord = va.total_cmp(&vb);
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (RV::Datatype(a), RV::Datatype(b)) => a.to_string().cmp(&b.to_string()),
+            (RV::Decimal128(a), RV::Decimal128(b)) => {
+                let (a, b) = (a.to_string(), b.to_string());
+                match (a.parse::<f64>(), b.parse::<f64>()) {
+                    (Ok(x), Ok(y)) => x.total_cmp(&y).then_with(|| a.cmp(&b)),
+                    _ => a.cmp(&b),
+                }
+            }
+            // Equal numbers of different representations are still ordered
+            // by representation, so that only identical values are equal.
+            (a, b) if a.type_rank() == 3 && b.type_rank() == 3 => {
+                let (x, y) = (
+                    a.to_double().unwrap_or(f64::NAN),
+                    b.to_double().unwrap_or(f64::NAN),
+                );
+                x.total_cmp(&y)
+                    .then_with(|| a.number_rank().cmp(&b.number_rank()))
+            }
+            (a, b) => a.type_rank().cmp(&b.type_rank()),
+        }
+    }
+}
+
 impl<'v> ops::Add for RV<'v> {
     type Output = Self;
 
@@ -603,7 +699,7 @@ mod tests {
             hasher.finish()
         }
 
-        // Objects: pointer-based hashing (identity)
+        // Objects: content-based hashing
         let mut map = IndexMap::default();
         map.insert("key".to_string(), RV::Double(1.0));
         let obj1 = RV::Object(RVObject::from_map(map));
@@ -612,8 +708,93 @@ mod tests {
         map.insert("key".to_string(), RV::Double(1.0));
         let obj2 = RV::Object(RVObject::from_map(map));
 
-        // Different instances, even with same content, hash differently
-        assert_ne!(hash(&obj1), hash(&obj2));
+        let mut map = IndexMap::default();
+        map.insert("key".to_string(), RV::Double(2.0));
+        let obj3 = RV::Object(RVObject::from_map(map));
+
+        let mut map = IndexMap::default();
+        map.insert("other".to_string(), RV::Double(1.0));
+        let obj4 = RV::Object(RVObject::from_map(map));
+
+        // Different instances with same content hash the same
+        assert_eq!(hash(&obj1), hash(&obj2));
+        assert_ne!(hash(&obj1), hash(&obj3)); // Different values
+        assert_ne!(hash(&obj1), hash(&obj4)); // Different keys
+
+        let mut map = IndexMap::default();
+        map.insert("a".to_string(), RV::Double(1.0));
+        map.insert("b".to_string(), RV::Double(2.0));
+        let obj5 = RV::Object(RVObject::from_map(map));
+
+        let mut map = IndexMap::default();
+        map.insert("b".to_string(), RV::Double(2.0));
+        map.insert("a".to_string(), RV::Double(1.0));
+        let obj6 = RV::Object(RVObject::from_map(map));
+
+        // Insertion order of the keys doesn't matter
+        assert_eq!(hash(&obj5), hash(&obj6));
+        assert!(obj5.total_cmp(&obj6).is_eq());
+    }
+
+    #[test]
+    fn test_rv_total_cmp_across_types() {
+        let mut map = IndexMap::default();
+        map.insert("key".to_string(), RV::Double(1.0));
+
+        let ordered = vec![
+            RV::Undefined,
+            RV::Null,
+            RV::Bool(false),
+            RV::Bool(true),
+            RV::Double(-1.0),
+            RV::Int32(0),
+            RV::Int64(5),
+            RV::Double(5.5),
+            RV::Str(Arc::new("a".to_string())),
+            RV::Str(Arc::new("b".to_string())),
+            RV::DateTime(bson::DateTime::from_millis(0)),
+            RV::DateTime(bson::DateTime::from_millis(1)),
+            RV::Object(RVObject::from_map(map)),
+            RV::Array(RVArray::from_vec(vec![RV::Double(1.0)])),
+        ];
+
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(a.total_cmp(b), i.cmp(&j), "comparing {a:?} and {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rv_total_cmp_numbers_by_value() {
+        use std::cmp::Ordering;
+
+        assert_eq!(RV::Int32(2).total_cmp(&RV::Double(2.0)), Ordering::Less);
+        assert_eq!(RV::Double(2.0).total_cmp(&RV::Int64(2)), Ordering::Greater);
+        assert_eq!(RV::Double(2.0).total_cmp(&RV::Double(2.0)), Ordering::Equal);
+        assert_eq!(RV::Int64(3).total_cmp(&RV::Double(2.5)), Ordering::Greater);
+        assert_eq!(
+            RV::Int64(i64::MAX).total_cmp(&RV::Int64(i64::MAX - 1)),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_rv_total_cmp_structural() {
+        use std::cmp::Ordering;
+
+        let object = |v: f64| {
+            let mut map = IndexMap::default();
+            map.insert("key".to_string(), RV::Double(v));
+            RV::Object(RVObject::from_map(map))
+        };
+
+        assert_eq!(object(1.0).total_cmp(&object(1.0)), Ordering::Equal);
+        assert_eq!(object(1.0).total_cmp(&object(2.0)), Ordering::Less);
+
+        let short = RV::Array(RVArray::from_vec(vec![RV::Double(1.0)]));
+        let long = RV::Array(RVArray::from_vec(vec![RV::Double(1.0), RV::Double(0.0)]));
+        assert_eq!(short.total_cmp(&long), Ordering::Less);
     }
 
     #[test]